@@ -0,0 +1,1304 @@
+//! Typed builders for every program instruction
+//!
+//! Builders derive every PDA themselves and take the remaining accounts as
+//! arguments. Signers are the wallets named after the instruction's actors
+//! (`user`, `authority`, `holder`, ...).
+
+use anchor_lang::prelude::{AccountMeta, Pubkey};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::sysvar;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::{self, get_associated_token_address_with_program_id};
+use anchor_spl::{token, token_2022};
+use memecoin_staking::{accounts, instruction as ix};
+
+use crate::{
+    pda, CheckpointRing, FeeConfig, RateOracle, RewardRate, Tier, VoterWeightAction,
+    VoterWeightConfig, ID,
+};
+
+/// Addresses shared by every instruction on a pool
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolKeys {
+    pub pool: Pubkey,
+    pub staking_mint: Pubkey,
+    pub reward_mint: Pubkey,
+    /// Token program owning `staking_mint` (Token or Token-2022)
+    pub staking_token_program: Pubkey,
+    /// Treasury of deposit and withdrawal fees, passed to the instructions charging them
+    pub staking_treasury: Option<Pubkey>,
+    /// Treasury of performance fees, passed to every rewards claim
+    pub reward_treasury: Option<Pubkey>,
+    /// Oracle price account, passed to every stake, unstake and claim in oracle rate mode
+    pub price_account: Option<Pubkey>,
+    /// Pages of the pool's total staked history, passed to every change of the total
+    pub checkpoint_ring: CheckpointRing,
+}
+
+impl PoolKeys {
+    pub fn new(staking_mint: Pubkey, reward_mint: Pubkey, staking_token_program: Pubkey) -> Self {
+        Self {
+            pool: pda::pool(&staking_mint),
+            staking_mint,
+            reward_mint,
+            staking_token_program,
+            staking_treasury: None,
+            reward_treasury: None,
+            price_account: None,
+            checkpoint_ring: CheckpointRing::INITIAL,
+        }
+    }
+
+    /// Pass the treasuries of the pool's `fees`, required while it charges them
+    pub fn with_fees(mut self, fees: &FeeConfig) -> Self {
+        let configured = |treasury: Pubkey| (treasury != Pubkey::default()).then_some(treasury);
+        self.staking_treasury = configured(fees.staking_treasury);
+        self.reward_treasury = configured(fees.reward_treasury);
+        self
+    }
+
+    /// Pass the price account of the pool's `rate_oracle`, required in oracle rate mode
+    pub fn with_rate_oracle(mut self, rate_oracle: &RateOracle) -> Self {
+        self.price_account = rate_oracle
+            .is_enabled()
+            .then_some(rate_oracle.price_account);
+        self
+    }
+
+    /// Write to the pool's current `checkpoint_ring` pages
+    pub fn with_checkpoint_ring(mut self, checkpoint_ring: &CheckpointRing) -> Self {
+        self.checkpoint_ring = *checkpoint_ring;
+        self
+    }
+
+    pub fn pool_vault(&self) -> Pubkey {
+        pda::pool_vault(&self.pool)
+    }
+
+    pub fn reward_vault(&self) -> Pubkey {
+        pda::reward_vault(&self.pool)
+    }
+
+    pub fn user_stake(&self, owner: &Pubkey) -> Pubkey {
+        pda::user_stake(&self.pool, owner)
+    }
+
+    pub fn checkpoints(&self, owner: &Pubkey) -> Pubkey {
+        pda::checkpoints(&self.pool, owner)
+    }
+
+    pub fn pool_checkpoints(&self) -> Pubkey {
+        pda::pool_checkpoints(&self.pool, self.checkpoint_ring.current)
+    }
+
+    pub fn next_pool_checkpoints(&self) -> Pubkey {
+        pda::pool_checkpoints(&self.pool, self.checkpoint_ring.next)
+    }
+}
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+/// Position NFT account of `holder` (its Token-2022 associated token account)
+fn position_account(holder: &Pubkey, position_mint: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(holder, position_mint, &token_2022::ID)
+}
+
+/// Build an instruction that leads its remaining accounts with the pool's oracle price
+/// account in oracle rate mode
+fn build_with_price(
+    keys: &PoolKeys,
+    accounts: impl ToAccountMetas,
+    data: impl InstructionData,
+) -> Instruction {
+    let mut instruction = build(accounts, data);
+    if let Some(price_account) = keys.price_account {
+        instruction
+            .accounts
+            .push(AccountMeta::new_readonly(price_account, false));
+    }
+    instruction
+}
+
+// ============ STAKING ============
+
+pub fn initialize_pool(
+    keys: &PoolKeys,
+    authority: &Pubkey,
+    reward_rate: RewardRate,
+    lock_period: i64,
+    min_stake_amount: u64,
+) -> Instruction {
+    build(
+        accounts::InitializePool {
+            authority: *authority,
+            pool: keys.pool,
+            pool_checkpoints: keys.pool_checkpoints(),
+            next_pool_checkpoints: keys.next_pool_checkpoints(),
+            staking_mint: keys.staking_mint,
+            reward_mint: keys.reward_mint,
+            pool_vault: keys.pool_vault(),
+            reward_vault: keys.reward_vault(),
+            system_program: system_program::ID,
+            staking_token_program: keys.staking_token_program,
+            token_program: token::ID,
+            rent: sysvar::rent::ID,
+        },
+        ix::InitializePool {
+            reward_rate,
+            lock_period,
+            min_stake_amount,
+        },
+    )
+}
+
+pub fn stake(
+    keys: &PoolKeys,
+    user: &Pubkey,
+    user_token_account: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    build_with_price(
+        keys,
+        accounts::Stake {
+            user: *user,
+            pool: keys.pool,
+            user_stake: keys.user_stake(user),
+            user_checkpoints: keys.checkpoints(user),
+            pool_checkpoints: keys.pool_checkpoints(),
+            next_pool_checkpoints: keys.next_pool_checkpoints(),
+            staking_mint: keys.staking_mint,
+            user_token_account: *user_token_account,
+            pool_vault: keys.pool_vault(),
+            staking_treasury: keys.staking_treasury,
+            system_program: system_program::ID,
+            staking_token_program: keys.staking_token_program,
+        },
+        ix::Stake { amount },
+    )
+}
+
+pub fn stake_for(
+    keys: &PoolKeys,
+    funder: &Pubkey,
+    funder_token_account: &Pubkey,
+    beneficiary: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    build_with_price(
+        keys,
+        accounts::StakeFor {
+            funder: *funder,
+            pool: keys.pool,
+            user_stake: keys.user_stake(beneficiary),
+            user_checkpoints: keys.checkpoints(beneficiary),
+            pool_checkpoints: keys.pool_checkpoints(),
+            next_pool_checkpoints: keys.next_pool_checkpoints(),
+            staking_mint: keys.staking_mint,
+            funder_token_account: *funder_token_account,
+            pool_vault: keys.pool_vault(),
+            staking_treasury: keys.staking_treasury,
+            system_program: system_program::ID,
+            staking_token_program: keys.staking_token_program,
+        },
+        ix::StakeFor {
+            beneficiary: *beneficiary,
+            amount,
+        },
+    )
+}
+
+fn unstake_accounts(
+    keys: &PoolKeys,
+    user: &Pubkey,
+    user_token_account: &Pubkey,
+) -> accounts::Unstake {
+    accounts::Unstake {
+        user: *user,
+        pool: keys.pool,
+        user_stake: keys.user_stake(user),
+        user_checkpoints: keys.checkpoints(user),
+        pool_checkpoints: keys.pool_checkpoints(),
+        next_pool_checkpoints: keys.next_pool_checkpoints(),
+        staking_mint: keys.staking_mint,
+        user_token_account: *user_token_account,
+        pool_vault: keys.pool_vault(),
+        staking_treasury: keys.staking_treasury,
+        system_program: system_program::ID,
+        staking_token_program: keys.staking_token_program,
+    }
+}
+
+pub fn unstake(
+    keys: &PoolKeys,
+    user: &Pubkey,
+    user_token_account: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    build_with_price(
+        keys,
+        unstake_accounts(keys, user, user_token_account),
+        ix::Unstake { amount },
+    )
+}
+
+pub fn emergency_withdraw(
+    keys: &PoolKeys,
+    user: &Pubkey,
+    user_token_account: &Pubkey,
+) -> Instruction {
+    build(
+        unstake_accounts(keys, user, user_token_account),
+        ix::EmergencyWithdraw {},
+    )
+}
+
+pub fn close_user_stake(keys: &PoolKeys, user: &Pubkey) -> Instruction {
+    build(
+        accounts::CloseUserStake {
+            user: *user,
+            pool: keys.pool,
+            user_stake: keys.user_stake(user),
+            user_checkpoints: Some(keys.checkpoints(user)),
+        },
+        ix::CloseUserStake {},
+    )
+}
+
+// ============ CLAIMS ============
+
+pub fn claim_rewards(keys: &PoolKeys, user: &Pubkey, user_reward_account: &Pubkey) -> Instruction {
+    build_with_price(
+        keys,
+        accounts::ClaimRewards {
+            user: *user,
+            pool: keys.pool,
+            user_stake: keys.user_stake(user),
+            user_reward_account: *user_reward_account,
+            reward_vault: keys.reward_vault(),
+            reward_treasury: keys.reward_treasury,
+            token_program: token::ID,
+        },
+        ix::ClaimRewards {},
+    )
+}
+
+/// Claim `owner`'s rewards as the owner or its claim delegate
+pub fn claim_rewards_for(
+    keys: &PoolKeys,
+    claimer: &Pubkey,
+    owner: &Pubkey,
+    reward_destination: &Pubkey,
+) -> Instruction {
+    build_with_price(
+        keys,
+        accounts::ClaimRewardsFor {
+            claimer: *claimer,
+            pool: keys.pool,
+            user_stake: keys.user_stake(owner),
+            reward_destination: *reward_destination,
+            reward_vault: keys.reward_vault(),
+            reward_treasury: keys.reward_treasury,
+            token_program: token::ID,
+        },
+        ix::ClaimRewardsFor {},
+    )
+}
+
+/// Claim for every `(owner, reward_destination)` pair, tipping the cranker
+pub fn crank_claim(
+    keys: &PoolKeys,
+    cranker: &Pubkey,
+    cranker_reward_account: &Pubkey,
+    claims: &[(Pubkey, Pubkey)],
+) -> Instruction {
+    let mut instruction = build_with_price(
+        keys,
+        accounts::CrankClaim {
+            cranker: *cranker,
+            pool: keys.pool,
+            cranker_reward_account: *cranker_reward_account,
+            reward_vault: keys.reward_vault(),
+            reward_treasury: keys.reward_treasury,
+            token_program: token::ID,
+        },
+        ix::CrankClaim {},
+    );
+    for (owner, reward_destination) in claims {
+        instruction
+            .accounts
+            .push(AccountMeta::new(keys.user_stake(owner), false));
+        instruction
+            .accounts
+            .push(AccountMeta::new(*reward_destination, false));
+    }
+    instruction
+}
+
+pub fn set_claim_delegate(keys: &PoolKeys, user: &Pubkey, delegate: &Pubkey) -> Instruction {
+    build(
+        accounts::UpdateUserStake {
+            user: *user,
+            pool: keys.pool,
+            user_stake: keys.user_stake(user),
+        },
+        ix::SetClaimDelegate {
+            delegate: *delegate,
+        },
+    )
+}
+
+/// `None` lets delegated claims pay any reward account the owner holds
+pub fn set_reward_destination(
+    keys: &PoolKeys,
+    user: &Pubkey,
+    reward_destination: Option<&Pubkey>,
+) -> Instruction {
+    build(
+        accounts::SetRewardDestination {
+            user: *user,
+            pool: keys.pool,
+            user_stake: keys.user_stake(user),
+            reward_destination: reward_destination.copied(),
+        },
+        ix::SetRewardDestination {},
+    )
+}
+
+/// Name `referrer` before `user`'s first stake; send with that `stake`
+pub fn register_referrer(keys: &PoolKeys, user: &Pubkey, referrer: &Pubkey) -> Instruction {
+    build(
+        accounts::RegisterReferrer {
+            user: *user,
+            pool: keys.pool,
+            user_stake: keys.user_stake(user),
+            referrer_stats: pda::referrer_stats(&keys.pool, referrer),
+            referrer_stake: keys.user_stake(referrer),
+            system_program: system_program::ID,
+        },
+        ix::RegisterReferrer {
+            referrer: *referrer,
+        },
+    )
+}
+
+/// Pay `referrer` the referral rewards accrued by the stakes of `referees`
+pub fn claim_referral_rewards(
+    keys: &PoolKeys,
+    referrer: &Pubkey,
+    referrer_reward_account: &Pubkey,
+    referees: &[Pubkey],
+) -> Instruction {
+    let mut instruction = build(
+        accounts::ClaimReferralRewards {
+            pool: keys.pool,
+            referrer_stats: pda::referrer_stats(&keys.pool, referrer),
+            referrer_reward_account: *referrer_reward_account,
+            reward_vault: keys.reward_vault(),
+            token_program: token::ID,
+        },
+        ix::ClaimReferralRewards {},
+    );
+    instruction.accounts.extend(
+        referees
+            .iter()
+            .map(|referee| AccountMeta::new(keys.user_stake(referee), false)),
+    );
+    instruction
+}
+
+// ============ NFT BOOST ============
+
+/// `nft_token_program` owns `nft_mint` (Token or Token-2022)
+pub fn attach_boost_nft(
+    keys: &PoolKeys,
+    user: &Pubkey,
+    nft_mint: &Pubkey,
+    user_nft_account: &Pubkey,
+    nft_token_program: &Pubkey,
+) -> Instruction {
+    let user_stake = keys.user_stake(user);
+    build(
+        accounts::AttachBoostNft {
+            user: *user,
+            pool: keys.pool,
+            user_stake,
+            nft_mint: *nft_mint,
+            nft_metadata: pda::nft_metadata(nft_mint),
+            user_nft_account: *user_nft_account,
+            boost_escrow: pda::boost_escrow(&user_stake),
+            system_program: system_program::ID,
+            nft_token_program: *nft_token_program,
+        },
+        ix::AttachBoostNft {},
+    )
+}
+
+/// Return the NFT attached to `user`'s stake into `user_nft_account`
+pub fn detach_boost_nft(
+    keys: &PoolKeys,
+    user: &Pubkey,
+    nft_mint: &Pubkey,
+    user_nft_account: &Pubkey,
+    nft_token_program: &Pubkey,
+) -> Instruction {
+    let user_stake = keys.user_stake(user);
+    build(
+        accounts::DetachBoostNft {
+            user: *user,
+            pool: keys.pool,
+            user_stake,
+            nft_mint: *nft_mint,
+            user_nft_account: *user_nft_account,
+            boost_escrow: pda::boost_escrow(&user_stake),
+            nft_token_program: *nft_token_program,
+        },
+        ix::DetachBoostNft {},
+    )
+}
+
+// ============ POSITIONS ============
+
+pub fn transfer_stake(keys: &PoolKeys, user: &Pubkey, new_owner: &Pubkey) -> Instruction {
+    build(
+        accounts::TransferStake {
+            user: *user,
+            pool: keys.pool,
+            user_stake: keys.user_stake(user),
+            destination_stake: keys.user_stake(new_owner),
+            user_checkpoints: keys.checkpoints(user),
+            destination_checkpoints: keys.checkpoints(new_owner),
+            system_program: system_program::ID,
+        },
+        ix::TransferStake {
+            new_owner: *new_owner,
+        },
+    )
+}
+
+/// `position_mint` is a fresh keypair that must also sign
+pub fn tokenize_stake(keys: &PoolKeys, user: &Pubkey, position_mint: &Pubkey) -> Instruction {
+    build(
+        accounts::TokenizeStake {
+            user: *user,
+            pool: keys.pool,
+            user_stake: keys.user_stake(user),
+            user_checkpoints: keys.checkpoints(user),
+            position_mint: *position_mint,
+            position_stake: pda::position_stake(position_mint),
+            user_position_account: position_account(user, position_mint),
+            system_program: system_program::ID,
+            position_token_program: token_2022::ID,
+            associated_token_program: associated_token::ID,
+        },
+        ix::TokenizeStake {},
+    )
+}
+
+pub fn unstake_position(
+    keys: &PoolKeys,
+    holder: &Pubkey,
+    position_mint: &Pubkey,
+    holder_token_account: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    build_with_price(
+        keys,
+        accounts::UnstakePosition {
+            holder: *holder,
+            pool: keys.pool,
+            pool_checkpoints: keys.pool_checkpoints(),
+            next_pool_checkpoints: keys.next_pool_checkpoints(),
+            position_stake: pda::position_stake(position_mint),
+            position_mint: *position_mint,
+            holder_position_account: position_account(holder, position_mint),
+            staking_mint: keys.staking_mint,
+            holder_token_account: *holder_token_account,
+            pool_vault: keys.pool_vault(),
+            staking_treasury: keys.staking_treasury,
+            staking_token_program: keys.staking_token_program,
+            position_token_program: token_2022::ID,
+        },
+        ix::UnstakePosition { amount },
+    )
+}
+
+pub fn claim_position_rewards(
+    keys: &PoolKeys,
+    holder: &Pubkey,
+    position_mint: &Pubkey,
+    holder_reward_account: &Pubkey,
+) -> Instruction {
+    build_with_price(
+        keys,
+        accounts::ClaimPositionRewards {
+            holder: *holder,
+            pool: keys.pool,
+            position_stake: pda::position_stake(position_mint),
+            holder_position_account: position_account(holder, position_mint),
+            holder_reward_account: *holder_reward_account,
+            reward_vault: keys.reward_vault(),
+            reward_treasury: keys.reward_treasury,
+            token_program: token::ID,
+        },
+        ix::ClaimPositionRewards {},
+    )
+}
+
+pub fn redeem_position(keys: &PoolKeys, holder: &Pubkey, position_mint: &Pubkey) -> Instruction {
+    build(
+        accounts::RedeemPosition {
+            holder: *holder,
+            pool: keys.pool,
+            position_stake: pda::position_stake(position_mint),
+            position_mint: *position_mint,
+            holder_position_account: position_account(holder, position_mint),
+            user_stake: keys.user_stake(holder),
+            user_checkpoints: keys.checkpoints(holder),
+            system_program: system_program::ID,
+            position_token_program: token_2022::ID,
+        },
+        ix::RedeemPosition {},
+    )
+}
+
+// ============ LIQUID STAKING ============
+
+pub fn initialize_liquid_staking(
+    keys: &PoolKeys,
+    authority: &Pubkey,
+    receipt_token_program: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::InitializeLiquidStaking {
+            authority: *authority,
+            pool: keys.pool,
+            staking_mint: keys.staking_mint,
+            receipt_mint: pda::receipt_mint(&keys.pool),
+            liquid_stake: pda::liquid_stake(&keys.pool),
+            system_program: system_program::ID,
+            receipt_token_program: *receipt_token_program,
+        },
+        ix::InitializeLiquidStaking {},
+    )
+}
+
+/// Token accounts of a liquid staker
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LiquidStaker {
+    pub user: Pubkey,
+    pub user_token_account: Pubkey,
+    pub user_receipt_account: Pubkey,
+    pub receipt_token_program: Pubkey,
+}
+
+fn liquid_stake_accounts(keys: &PoolKeys, staker: &LiquidStaker) -> accounts::LiquidStake {
+    accounts::LiquidStake {
+        user: staker.user,
+        pool: keys.pool,
+        pool_checkpoints: keys.pool_checkpoints(),
+        next_pool_checkpoints: keys.next_pool_checkpoints(),
+        liquid_stake: pda::liquid_stake(&keys.pool),
+        receipt_mint: pda::receipt_mint(&keys.pool),
+        user_receipt_account: staker.user_receipt_account,
+        staking_mint: keys.staking_mint,
+        user_token_account: staker.user_token_account,
+        pool_vault: keys.pool_vault(),
+        reward_vault: keys.reward_vault(),
+        staking_treasury: keys.staking_treasury,
+        staking_token_program: keys.staking_token_program,
+        receipt_token_program: staker.receipt_token_program,
+        token_program: token::ID,
+    }
+}
+
+pub fn liquid_stake(keys: &PoolKeys, staker: &LiquidStaker, amount: u64) -> Instruction {
+    build_with_price(
+        keys,
+        liquid_stake_accounts(keys, staker),
+        ix::LiquidStake { amount },
+    )
+}
+
+pub fn liquid_unstake(keys: &PoolKeys, staker: &LiquidStaker, receipts: u64) -> Instruction {
+    build_with_price(
+        keys,
+        liquid_stake_accounts(keys, staker),
+        ix::LiquidUnstake { receipts },
+    )
+}
+
+pub fn compound_liquid(keys: &PoolKeys) -> Instruction {
+    build_with_price(
+        keys,
+        accounts::CompoundLiquid {
+            pool: keys.pool,
+            pool_checkpoints: keys.pool_checkpoints(),
+            next_pool_checkpoints: keys.next_pool_checkpoints(),
+            liquid_stake: pda::liquid_stake(&keys.pool),
+            pool_vault: keys.pool_vault(),
+            reward_vault: keys.reward_vault(),
+            token_program: token::ID,
+        },
+        ix::CompoundLiquid {},
+    )
+}
+
+// ============ VIEWS ============
+
+fn view_user_stake(keys: &PoolKeys, user: &Pubkey) -> accounts::ViewUserStake {
+    accounts::ViewUserStake {
+        pool: keys.pool,
+        user_stake: keys.user_stake(user),
+    }
+}
+
+pub fn get_pending_rewards(keys: &PoolKeys, user: &Pubkey) -> Instruction {
+    build(
+        view_user_stake(keys, user),
+        ix::GetPendingRewards { _user: *user },
+    )
+}
+
+pub fn get_user_position(keys: &PoolKeys, user: &Pubkey) -> Instruction {
+    build(
+        view_user_stake(keys, user),
+        ix::GetUserPosition { _user: *user },
+    )
+}
+
+pub fn get_pool_stats(keys: &PoolKeys) -> Instruction {
+    build(
+        accounts::ViewPool {
+            pool: keys.pool,
+            pool_vault: keys.pool_vault(),
+            reward_vault: keys.reward_vault(),
+        },
+        ix::GetPoolStats {},
+    )
+}
+
+pub fn get_stake_at(keys: &PoolKeys, user: &Pubkey, timestamp: i64) -> Instruction {
+    build(
+        accounts::ViewStakeAt {
+            pool: keys.pool,
+            user_checkpoints: keys.checkpoints(user),
+        },
+        ix::GetStakeAt {
+            _user: *user,
+            timestamp,
+        },
+    )
+}
+
+/// `page` holds the last change before `timestamp` (see `state::checkpoint_page_at`)
+pub fn get_pool_stake_at(keys: &PoolKeys, timestamp: i64, page: u32) -> Instruction {
+    let ring = &keys.checkpoint_ring;
+    build(
+        accounts::ViewPoolStakeAt {
+            pool: keys.pool,
+            pool_checkpoints: pda::pool_checkpoints(&keys.pool, page),
+            following_checkpoints: (page != ring.current)
+                .then(|| pda::pool_checkpoints(&keys.pool, (page + 1) % ring.pages)),
+        },
+        ix::GetPoolStakeAt {
+            timestamp,
+            _page: page,
+        },
+    )
+}
+
+pub fn add_checkpoint_page(keys: &PoolKeys, authority: &Pubkey) -> Instruction {
+    build(
+        accounts::AddCheckpointPage {
+            authority: *authority,
+            pool: keys.pool,
+            page: pda::pool_checkpoints(&keys.pool, keys.checkpoint_ring.pages),
+            system_program: system_program::ID,
+        },
+        ix::AddCheckpointPage {},
+    )
+}
+
+// ============ GOVERNANCE ============
+
+/// `config.realm` must be owned by `governance_program` and governed by `realm_authority`
+pub fn set_voter_weight_config(
+    keys: &PoolKeys,
+    authority: &Pubkey,
+    realm_authority: &Pubkey,
+    governance_program: &Pubkey,
+    config: &VoterWeightConfig,
+) -> Instruction {
+    build(
+        accounts::SetVoterWeightConfig {
+            authority: *authority,
+            realm_authority: *realm_authority,
+            pool: keys.pool,
+            realm: config.realm,
+            governance_program: *governance_program,
+        },
+        ix::SetVoterWeightConfig {
+            governing_token_mint: config.governing_token_mint,
+            max_lock_boost_bps: config.max_lock_boost_bps,
+            lock_boost_saturation: config.lock_boost_saturation,
+        },
+    )
+}
+
+pub fn disable_voter_weight(keys: &PoolKeys, authority: &Pubkey) -> Instruction {
+    build(admin(keys, authority), ix::DisableVoterWeight {})
+}
+
+/// `realm` is the pool's `voter_weight.realm`; votes pass the proposal as `target`
+pub fn update_voter_weight_record(
+    keys: &PoolKeys,
+    payer: &Pubkey,
+    voter: &Pubkey,
+    realm: &Pubkey,
+    action: VoterWeightAction,
+    target: Option<Pubkey>,
+) -> Instruction {
+    build(
+        accounts::UpdateVoterWeightRecord {
+            payer: *payer,
+            pool: keys.pool,
+            user_stake: keys.user_stake(voter),
+            user_checkpoints: keys.checkpoints(voter),
+            realm: *realm,
+            proposal: target.filter(|_| action == VoterWeightAction::CastVote),
+            voter_weight_record: pda::voter_weight_record(&keys.pool, voter),
+            system_program: system_program::ID,
+        },
+        ix::UpdateVoterWeightRecord {
+            voter: *voter,
+            action,
+            target,
+        },
+    )
+}
+
+// ============ REWARD RATE ============
+
+pub fn update_reward_rate(
+    keys: &PoolKeys,
+    authority: &Pubkey,
+    new_rate: RewardRate,
+) -> Instruction {
+    build(admin(keys, authority), ix::UpdateRewardRate { new_rate })
+}
+
+pub fn update_reward_rate_per_period(
+    keys: &PoolKeys,
+    authority: &Pubkey,
+    reward_amount: u64,
+    per_staked_amount: u64,
+    period_seconds: u64,
+) -> Instruction {
+    build(
+        admin(keys, authority),
+        ix::UpdateRewardRatePerPeriod {
+            reward_amount,
+            per_staked_amount,
+            period_seconds,
+        },
+    )
+}
+
+pub fn set_rate_oracle(
+    keys: &PoolKeys,
+    authority: &Pubkey,
+    price_account: &Pubkey,
+    target_apr_bps: u32,
+    max_staleness: i64,
+    max_confidence_bps: u16,
+    max_rate_change_bps: u16,
+) -> Instruction {
+    build(
+        accounts::SetRateOracle {
+            authority: *authority,
+            pool: keys.pool,
+            staking_mint: keys.staking_mint,
+            reward_mint: keys.reward_mint,
+            price_account: *price_account,
+        },
+        ix::SetRateOracle {
+            target_apr_bps,
+            max_staleness,
+            max_confidence_bps,
+            max_rate_change_bps,
+        },
+    )
+}
+
+pub fn disable_rate_oracle(keys: &PoolKeys, authority: &Pubkey) -> Instruction {
+    build(admin(keys, authority), ix::DisableRateOracle {})
+}
+
+pub fn refresh_reward_rate(keys: &PoolKeys, price_account: &Pubkey) -> Instruction {
+    build(
+        accounts::RefreshRewardRate {
+            pool: keys.pool,
+            price_account: *price_account,
+        },
+        ix::RefreshRewardRate {},
+    )
+}
+
+// ============ MERKLE DISTRIBUTOR ============
+
+/// `epoch` must be the pool's next epoch, `StakePool::distributor.epochs`
+pub fn post_distributor_epoch(
+    keys: &PoolKeys,
+    authority: &Pubkey,
+    epoch: u64,
+    merkle_root: [u8; 32],
+    total_amount: u64,
+    num_leaves: u32,
+) -> Instruction {
+    build(
+        accounts::PostDistributorEpoch {
+            authority: *authority,
+            pool: keys.pool,
+            distributor_epoch: pda::distributor_epoch(&keys.pool, epoch),
+            reward_vault: keys.reward_vault(),
+            system_program: system_program::ID,
+        },
+        ix::PostDistributorEpoch {
+            merkle_root,
+            total_amount,
+            num_leaves,
+        },
+    )
+}
+
+/// Claim leaf `index` of `epoch`, proven by `MerkleTree::proof`
+pub fn claim_distributor(
+    keys: &PoolKeys,
+    claimant: &Pubkey,
+    claimant_reward_account: &Pubkey,
+    epoch: u64,
+    index: u32,
+    amount: u64,
+    proof: &[[u8; 32]],
+) -> Instruction {
+    build(
+        accounts::ClaimDistributor {
+            claimant: *claimant,
+            pool: keys.pool,
+            distributor_epoch: pda::distributor_epoch(&keys.pool, epoch),
+            claimant_reward_account: *claimant_reward_account,
+            reward_vault: keys.reward_vault(),
+            token_program: token::ID,
+        },
+        ix::ClaimDistributor {
+            index,
+            amount,
+            proof: proof.to_vec(),
+        },
+    )
+}
+
+pub fn close_distributor_epoch(keys: &PoolKeys, authority: &Pubkey, epoch: u64) -> Instruction {
+    build(
+        accounts::CloseDistributorEpoch {
+            authority: *authority,
+            pool: keys.pool,
+            distributor_epoch: pda::distributor_epoch(&keys.pool, epoch),
+        },
+        ix::CloseDistributorEpoch {},
+    )
+}
+
+// ============ CAMPAIGNS ============
+
+/// Addresses of a sponsored campaign
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CampaignKeys {
+    pub campaign: Pubkey,
+    pub reward_mint: Pubkey,
+    /// Token program owning `reward_mint` (Token or Token-2022)
+    pub reward_token_program: Pubkey,
+}
+
+impl CampaignKeys {
+    /// Keys of campaign `id` of `pool`
+    pub fn new(pool: &Pubkey, id: u64, reward_mint: Pubkey, reward_token_program: Pubkey) -> Self {
+        Self {
+            campaign: pda::campaign(pool, id),
+            reward_mint,
+            reward_token_program,
+        }
+    }
+
+    pub fn vault(&self) -> Pubkey {
+        pda::campaign_vault(&self.campaign)
+    }
+}
+
+/// `campaign` must be for the pool's next id, `StakePool::campaigns.created`
+pub fn create_campaign(
+    keys: &PoolKeys,
+    campaign: &CampaignKeys,
+    funder: &Pubkey,
+    funder_token_account: &Pubkey,
+    amount: u64,
+    start_time: i64,
+    end_time: i64,
+) -> Instruction {
+    build(
+        accounts::CreateCampaign {
+            funder: *funder,
+            pool: keys.pool,
+            campaign: campaign.campaign,
+            reward_mint: campaign.reward_mint,
+            campaign_vault: campaign.vault(),
+            funder_token_account: *funder_token_account,
+            system_program: system_program::ID,
+            reward_token_program: campaign.reward_token_program,
+        },
+        ix::CreateCampaign {
+            amount,
+            start_time,
+            end_time,
+        },
+    )
+}
+
+pub fn claim_campaign_rewards(
+    keys: &PoolKeys,
+    campaign: &CampaignKeys,
+    user: &Pubkey,
+    user_reward_account: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::ClaimCampaignRewards {
+            user: *user,
+            pool: keys.pool,
+            user_stake: keys.user_stake(user),
+            campaign: campaign.campaign,
+            campaign_vault: campaign.vault(),
+            reward_mint: campaign.reward_mint,
+            user_reward_account: *user_reward_account,
+            reward_token_program: campaign.reward_token_program,
+        },
+        ix::ClaimCampaignRewards {},
+    )
+}
+
+pub fn claim_position_campaign_rewards(
+    keys: &PoolKeys,
+    campaign: &CampaignKeys,
+    holder: &Pubkey,
+    position_mint: &Pubkey,
+    holder_reward_account: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::ClaimPositionCampaignRewards {
+            holder: *holder,
+            pool: keys.pool,
+            position_stake: pda::position_stake(position_mint),
+            holder_position_account: position_account(holder, position_mint),
+            campaign: campaign.campaign,
+            campaign_vault: campaign.vault(),
+            reward_mint: campaign.reward_mint,
+            holder_reward_account: *holder_reward_account,
+            reward_token_program: campaign.reward_token_program,
+        },
+        ix::ClaimPositionCampaignRewards {},
+    )
+}
+
+fn campaign_funder_action(
+    keys: &PoolKeys,
+    campaign: &CampaignKeys,
+    funder: &Pubkey,
+    funder_token_account: &Pubkey,
+) -> accounts::CampaignFunderAction {
+    accounts::CampaignFunderAction {
+        funder: *funder,
+        pool: keys.pool,
+        campaign: campaign.campaign,
+        campaign_vault: campaign.vault(),
+        reward_mint: campaign.reward_mint,
+        funder_token_account: *funder_token_account,
+        reward_token_program: campaign.reward_token_program,
+    }
+}
+
+pub fn reclaim_campaign_leftovers(
+    keys: &PoolKeys,
+    campaign: &CampaignKeys,
+    funder: &Pubkey,
+    funder_token_account: &Pubkey,
+) -> Instruction {
+    build(
+        campaign_funder_action(keys, campaign, funder, funder_token_account),
+        ix::ReclaimCampaignLeftovers {},
+    )
+}
+
+pub fn close_campaign(
+    keys: &PoolKeys,
+    campaign: &CampaignKeys,
+    funder: &Pubkey,
+    funder_token_account: &Pubkey,
+) -> Instruction {
+    build(
+        campaign_funder_action(keys, campaign, funder, funder_token_account),
+        ix::CloseCampaign {},
+    )
+}
+
+// ============ ADMIN ============
+
+fn admin(keys: &PoolKeys, authority: &Pubkey) -> accounts::AdminAction {
+    accounts::AdminAction {
+        authority: *authority,
+        pool: keys.pool,
+    }
+}
+
+pub fn set_paused(keys: &PoolKeys, authority: &Pubkey, paused: bool) -> Instruction {
+    build(admin(keys, authority), ix::SetPaused { paused })
+}
+
+pub fn set_pause_flags(keys: &PoolKeys, authority: &Pubkey, pause_flags: u8) -> Instruction {
+    build(admin(keys, authority), ix::SetPauseFlags { pause_flags })
+}
+
+pub fn set_emergency_mode(keys: &PoolKeys, authority: &Pubkey, enabled: bool) -> Instruction {
+    build(admin(keys, authority), ix::SetEmergencyMode { enabled })
+}
+
+pub fn set_transfers_disabled(keys: &PoolKeys, authority: &Pubkey, disabled: bool) -> Instruction {
+    build(
+        admin(keys, authority),
+        ix::SetTransfersDisabled { disabled },
+    )
+}
+
+pub fn set_crank_tip(keys: &PoolKeys, authority: &Pubkey, tip_bps: u16) -> Instruction {
+    build(admin(keys, authority), ix::SetCrankTip { tip_bps })
+}
+
+pub fn set_referral_bps(keys: &PoolKeys, authority: &Pubkey, referral_bps: u16) -> Instruction {
+    build(admin(keys, authority), ix::SetReferralBps { referral_bps })
+}
+
+/// `tiers` must be in ascending order of `min_stake`
+pub fn set_tiers(keys: &PoolKeys, authority: &Pubkey, tiers: Vec<Tier>) -> Instruction {
+    build(admin(keys, authority), ix::SetTiers { tiers })
+}
+
+/// Settle `user_stake` at its old multiplier and move it onto the pool's current tiers
+pub fn refresh_tier(keys: &PoolKeys, user_stake: &Pubkey) -> Instruction {
+    build(
+        accounts::RefreshTier {
+            pool: keys.pool,
+            user_stake: *user_stake,
+        },
+        ix::RefreshTier {},
+    )
+}
+
+/// A default `collection` disables the boost
+pub fn set_nft_boost(
+    keys: &PoolKeys,
+    authority: &Pubkey,
+    collection: &Pubkey,
+    multiplier_bps: u16,
+) -> Instruction {
+    build(
+        admin(keys, authority),
+        ix::SetNftBoost {
+            collection: *collection,
+            multiplier_bps,
+        },
+    )
+}
+
+/// Fees are paid to `staking_treasury` (staking mint) and `reward_treasury` (reward mint)
+pub fn set_fees(
+    keys: &PoolKeys,
+    authority: &Pubkey,
+    staking_treasury: &Pubkey,
+    reward_treasury: &Pubkey,
+    deposit_bps: u16,
+    withdrawal_bps: u16,
+    performance_bps: u16,
+) -> Instruction {
+    build(
+        accounts::SetFees {
+            authority: *authority,
+            pool: keys.pool,
+            staking_treasury: *staking_treasury,
+            reward_treasury: *reward_treasury,
+        },
+        ix::SetFees {
+            deposit_bps,
+            withdrawal_bps,
+            performance_bps,
+        },
+    )
+}
+
+pub fn fund_rewards(
+    keys: &PoolKeys,
+    funder: &Pubkey,
+    funder_token_account: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    build(
+        accounts::FundRewards {
+            funder: *funder,
+            pool: keys.pool,
+            funder_token_account: *funder_token_account,
+            reward_vault: keys.reward_vault(),
+            token_program: token::ID,
+        },
+        ix::FundRewards { amount },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::Discriminator;
+
+    fn keys() -> PoolKeys {
+        PoolKeys::new(Pubkey::new_unique(), Pubkey::new_unique(), token_2022::ID)
+    }
+
+    #[test]
+    fn stake_marks_signer_and_writable_accounts() {
+        let keys = keys();
+        let user = Pubkey::new_unique();
+        let token_account = Pubkey::new_unique();
+        let instruction = stake(&keys, &user, &token_account, 42);
+
+        assert_eq!(instruction.program_id, ID);
+        assert_eq!(instruction.data[..8], *ix::Stake::DISCRIMINATOR);
+        assert_eq!(instruction.data[8..], 42u64.to_le_bytes());
+
+        let meta = |key: Pubkey| {
+            instruction
+                .accounts
+                .iter()
+                .find(|m| m.pubkey == key)
+                .unwrap()
+        };
+        assert!(meta(user).is_signer && meta(user).is_writable);
+        assert!(meta(keys.pool).is_writable && !meta(keys.pool).is_signer);
+        assert!(meta(keys.user_stake(&user)).is_writable);
+        assert!(meta(keys.pool_vault()).is_writable);
+        assert!(!meta(keys.staking_mint).is_writable);
+    }
+
+    #[test]
+    fn oracle_pools_append_the_price_account() {
+        let fixed_rate = keys();
+        let user = Pubkey::new_unique();
+        let reward_account = Pubkey::new_unique();
+        let rate_oracle = RateOracle {
+            price_account: Pubkey::new_unique(),
+            ..RateOracle::default()
+        };
+        let oracle = fixed_rate.with_rate_oracle(&rate_oracle);
+        let price_meta = AccountMeta::new_readonly(rate_oracle.price_account, false);
+
+        let fixed_claim = claim_rewards(&fixed_rate, &user, &reward_account);
+        let oracle_claim = claim_rewards(&oracle, &user, &reward_account);
+        assert_eq!(oracle_claim.accounts.len(), fixed_claim.accounts.len() + 1);
+        assert_eq!(oracle_claim.accounts.last(), Some(&price_meta));
+        let unstake = unstake(&oracle, &user, &Pubkey::new_unique(), 1);
+        assert_eq!(unstake.accounts.last(), Some(&price_meta));
+
+        // The price account leads the crank's claim pairs
+        let (owner, destination) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let crank = crank_claim(&oracle, &user, &reward_account, &[(owner, destination)]);
+        assert_eq!(crank.accounts[crank.accounts.len() - 3], price_meta);
+
+        assert_eq!(oracle.with_rate_oracle(&RateOracle::default()), fixed_rate);
+    }
+
+    #[test]
+    fn crank_claim_appends_writable_pairs() {
+        let keys = keys();
+        let (owner, destination) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let instruction = crank_claim(
+            &keys,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &[(owner, destination)],
+        );
+
+        let pair = &instruction.accounts[instruction.accounts.len() - 2..];
+        assert_eq!(pair[0], AccountMeta::new(keys.user_stake(&owner), false));
+        assert_eq!(pair[1], AccountMeta::new(destination, false));
+    }
+
+    #[test]
+    fn fee_treasuries_fill_their_optional_slots() {
+        let user = Pubkey::new_unique();
+        let reward_treasury = Pubkey::new_unique();
+        let fees = FeeConfig {
+            performance_bps: 100,
+            reward_treasury,
+            ..FeeConfig::default()
+        };
+        let keys = keys().with_fees(&fees);
+        assert_eq!(keys.staking_treasury, None);
+
+        let claim = claim_rewards(&keys, &user, &Pubkey::new_unique());
+        assert!(claim
+            .accounts
+            .contains(&AccountMeta::new(reward_treasury, false)));
+        // Anchor reads the program id as an omitted optional account
+        let stake = stake(&keys, &user, &Pubkey::new_unique(), 1);
+        assert!(stake
+            .accounts
+            .contains(&AccountMeta::new_readonly(ID, false)));
+    }
+
+    #[test]
+    fn campaigns_pay_out_of_their_own_vault() {
+        let keys = keys();
+        let first = CampaignKeys::new(&keys.pool, 0, Pubkey::new_unique(), token_2022::ID);
+        let second = CampaignKeys::new(&keys.pool, 1, first.reward_mint, token_2022::ID);
+        assert_ne!(first.vault(), second.vault());
+
+        let user = Pubkey::new_unique();
+        let claim = claim_campaign_rewards(&keys, &second, &user, &Pubkey::new_unique());
+        assert_eq!(claim.data[..8], *ix::ClaimCampaignRewards::DISCRIMINATOR);
+        assert!(claim.accounts.contains(&AccountMeta::new(keys.pool, false)));
+        assert!(claim
+            .accounts
+            .contains(&AccountMeta::new(second.vault(), false)));
+        assert!(!claim
+            .accounts
+            .iter()
+            .any(|meta| meta.pubkey == first.vault()));
+
+        let position_mint = Pubkey::new_unique();
+        let claim = claim_position_campaign_rewards(
+            &keys,
+            &second,
+            &user,
+            &position_mint,
+            &Pubkey::new_unique(),
+        );
+        assert!(claim.accounts.contains(&AccountMeta::new(
+            pda::position_stake(&position_mint),
+            false
+        )));
+        assert!(claim
+            .accounts
+            .contains(&AccountMeta::new(second.vault(), false)));
+    }
+}
@@ -0,0 +1,21 @@
+//! Rust client for the memecoin staking program
+//!
+//! - [`pda`]: program-derived addresses
+//! - [`instructions`]: typed builders for every program instruction
+//! - [`state`]: account deserializers
+//! - [`math`]: off-chain port of the program's reward math
+//! - [`merkle`]: Merkle trees and proofs of distributor epochs
+
+pub mod instructions;
+pub mod math;
+pub mod merkle;
+pub mod pda;
+pub mod state;
+
+pub use instructions::{CampaignKeys, PoolKeys};
+pub use memecoin_staking::{
+    self as program, Campaign, CampaignState, Checkpoint, CheckpointPage, CheckpointRing,
+    DistributorEpoch, DistributorState, FeeConfig, NftBoost, PoolStats, RateOracle, ReferrerStats,
+    RewardIndex, RewardRate, StakeCheckpoints, StakePool, Tier, TierTable, UserPosition, UserStake,
+    VoterWeightAction, VoterWeightConfig, VoterWeightRecord, ID,
+};
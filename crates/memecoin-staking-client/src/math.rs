@@ -0,0 +1,280 @@
+//! Off-chain port of the program's reward math
+//!
+//! Reward amounts use full 256-bit products rather than the program's split
+//! multiplication, so the tests below check two independent implementations
+//! against each other.
+
+use anchor_lang::prelude::Pubkey;
+
+use crate::{RewardRate, StakePool, UserStake};
+
+/// Fixed-point scale of `RewardRate`
+pub const RATE_SCALE: u128 = memecoin_staking::RATE_SCALE;
+
+/// Rewards `rate` pays `staked_amount` over `elapsed` seconds, or `None` if they exceed a u64
+pub fn rewards_for(rate: RewardRate, staked_amount: u64, elapsed: u64) -> Option<u64> {
+    let (high, low) = mul_wide(staked_amount as u128 * elapsed as u128, rate.0);
+    u64::try_from(div_wide(high, low, RATE_SCALE)?).ok()
+}
+
+/// Pool accrual clock at `now`: wall-clock time minus every second accrual was halted
+pub fn accrual_time(pool: &StakePool, now: i64) -> i64 {
+    let end = if pool.pause_flags & memecoin_staking::PAUSE_ACCRUAL != 0 {
+        pool.accrual_halted_at
+    } else {
+        now
+    };
+    end.saturating_sub(pool.accrual_halted_seconds)
+}
+
+/// Pool reward index at `now`: the settled index plus the current rate since it was settled
+pub fn reward_index(pool: &StakePool, now: i64) -> u128 {
+    let elapsed = accrual_time(pool, now).saturating_sub(pool.reward_index.updated_at);
+    let rise = pool.reward_rate.0.wrapping_mul(elapsed.max(0) as u128);
+    pool.reward_index.value.wrapping_add(rise)
+}
+
+/// Rewards a position could claim at `now`, or `None` if they exceed a u64
+pub fn claimable_rewards(pool: &StakePool, user_stake: &UserStake, now: i64) -> Option<u64> {
+    if user_stake.staked_amount == 0 {
+        return Some(user_stake.pending_rewards);
+    }
+
+    // The index rise is the rate integrated over time: that rate paid for one second
+    let rise = reward_index(pool, now).wrapping_sub(user_stake.reward_index_paid);
+    let accrued = rewards_for(RewardRate(rise), user_stake.staked_amount, 1)?;
+    let accrued = memecoin_staking::boosted(accrued, user_stake.tier_multiplier_bps)?;
+    let accrued = memecoin_staking::boosted(accrued, user_stake.boost_bps(&pool.nft_boost))?;
+    user_stake.pending_rewards.checked_add(accrued)
+}
+
+/// Referral rewards a position owes its referrer at `now`, or `None` if they exceed a u64
+pub fn referral_rewards(pool: &StakePool, user_stake: &UserStake, now: i64) -> Option<u64> {
+    if user_stake.referrer == Pubkey::default() {
+        return Some(user_stake.referral_pending);
+    }
+    let accrued = claimable_rewards(pool, user_stake, now)? - user_stake.pending_rewards;
+    user_stake
+        .referral_pending
+        .checked_add(memecoin_staking::referral_bonus(accrued, pool.referral_bps))
+}
+
+/// Receipt tokens minted for depositing `amount` into liquid staking
+pub fn receipts_for_deposit(
+    amount: u64,
+    total_underlying: u64,
+    receipt_supply: u64,
+) -> Option<u64> {
+    if receipt_supply == 0 || total_underlying == 0 {
+        return Some(amount);
+    }
+    u64::try_from(amount as u128 * receipt_supply as u128 / total_underlying as u128).ok()
+}
+
+/// Staked tokens returned for burning `receipts` of liquid staking
+pub fn underlying_for_receipts(
+    receipts: u64,
+    total_underlying: u64,
+    receipt_supply: u64,
+) -> Option<u64> {
+    if receipts > receipt_supply {
+        return None;
+    }
+    let amount =
+        (receipts as u128 * total_underlying as u128).checked_div(receipt_supply as u128)?;
+    u64::try_from(amount).ok()
+}
+
+/// Full 256-bit product of two u128s as `(high, low)` halves
+fn mul_wide(a: u128, b: u128) -> (u128, u128) {
+    const LOW_MASK: u128 = u64::MAX as u128;
+    let (a_high, a_low) = (a >> 64, a & LOW_MASK);
+    let (b_high, b_low) = (b >> 64, b & LOW_MASK);
+
+    let low_low = a_low * b_low;
+    let high_low = a_high * b_low;
+    let low_high = a_low * b_high;
+    let high_high = a_high * b_high;
+
+    let middle = (low_low >> 64) + (high_low & LOW_MASK) + (low_high & LOW_MASK);
+    let low = (middle << 64) | (low_low & LOW_MASK);
+    let high = high_high + (high_low >> 64) + (low_high >> 64) + (middle >> 64);
+    (high, low)
+}
+
+/// `(high * 2^128 + low) / divisor` by binary long division, or `None` if it exceeds a u128
+///
+/// `divisor` must be below 2^127.
+fn div_wide(high: u128, low: u128, divisor: u128) -> Option<u128> {
+    if high >= divisor {
+        return None;
+    }
+
+    let (mut quotient, mut remainder) = (0u128, high);
+    for bit in (0..128).rev() {
+        remainder = (remainder << 1) | ((low >> bit) & 1);
+        quotient <<= 1;
+        if remainder >= divisor {
+            remainder -= divisor;
+            quotient |= 1;
+        }
+    }
+    Some(quotient)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Tier, TierTable};
+    use proptest::prelude::*;
+
+    fn pool(reward_rate: u128, pause_flags: u8, halted_at: i64, halted_seconds: i64) -> StakePool {
+        StakePool {
+            authority: Pubkey::new_unique(),
+            staking_mint: Pubkey::new_unique(),
+            reward_mint: Pubkey::new_unique(),
+            pool_vault: Pubkey::new_unique(),
+            reward_vault: Pubkey::new_unique(),
+            reward_rate: RewardRate(reward_rate),
+            lock_period: 0,
+            total_staked: 0,
+            last_update_time: 0,
+            pause_flags,
+            accrual_halted_at: halted_at,
+            accrual_halted_seconds: halted_seconds,
+            emergency_mode: false,
+            transfers_disabled: false,
+            receipt_mint: Pubkey::default(),
+            crank_tip_bps: 0,
+            rate_oracle: Default::default(),
+            voter_weight: Default::default(),
+            distributor: Default::default(),
+            referral_bps: 0,
+            fees: Default::default(),
+            nft_boost: Default::default(),
+            tiers: Default::default(),
+            campaigns: Default::default(),
+            reward_index: Default::default(),
+            checkpoint_ring: Default::default(),
+            bump: 255,
+        }
+    }
+
+    fn position(staked_amount: u64, pending_rewards: u64, reward_index_paid: u128) -> UserStake {
+        UserStake {
+            owner: Pubkey::new_unique(),
+            pool: Pubkey::new_unique(),
+            staked_amount,
+            pending_rewards,
+            last_stake_time: 0,
+            last_accrual_time: 0,
+            stake_start_time: 0,
+            total_rewards_claimed: 0,
+            claim_delegate: Pubkey::default(),
+            reward_destination: Pubkey::default(),
+            referrer: Pubkey::default(),
+            referral_pending: 0,
+            boost_mint: Pubkey::default(),
+            tier: 0,
+            campaign_rewards: Default::default(),
+            reward_index_paid,
+            tier_multiplier_bps: memecoin_staking::BPS_DENOMINATOR,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn mul_wide_handles_the_extremes() {
+        assert_eq!(mul_wide(u128::MAX, u128::MAX), (u128::MAX - 1, 1));
+        assert_eq!(mul_wide(u128::MAX, 2), (1, u128::MAX - 1));
+        assert_eq!(mul_wide(0, u128::MAX), (0, 0));
+    }
+
+    #[test]
+    fn referrers_earn_their_share_on_top() {
+        let mut pool = pool(RATE_SCALE, 0, 0, 0);
+        pool.referral_bps = 1_000;
+        let mut referee = position(100, 5, 0);
+        assert_eq!(referral_rewards(&pool, &referee, 10), Some(0));
+
+        referee.referrer = Pubkey::new_unique();
+        referee.referral_pending = 3;
+        assert_eq!(claimable_rewards(&pool, &referee, 10), Some(1_005));
+        assert_eq!(referral_rewards(&pool, &referee, 10), Some(103));
+    }
+
+    proptest! {
+        #[test]
+        fn rewards_match_the_program(
+            rate in any::<u128>(),
+            staked in any::<u64>(),
+            elapsed in any::<u64>(),
+        ) {
+            prop_assert_eq!(
+                rewards_for(RewardRate(rate), staked, elapsed),
+                RewardRate(rate).rewards_for(staked, elapsed)
+            );
+        }
+
+        #[test]
+        fn claimable_rewards_match_the_program(
+            rate in any::<u128>(),
+            halted in any::<bool>(),
+            halted_at in any::<i64>(),
+            halted_seconds in 0..=i64::MAX,
+            index in any::<u128>(),
+            index_time in any::<i64>(),
+            staked in any::<u64>(),
+            pending in any::<u64>(),
+            index_paid in any::<u128>(),
+            now in any::<i64>(),
+            boost_bps in proptest::option::of(
+                memecoin_staking::BPS_DENOMINATOR..=memecoin_staking::MAX_NFT_BOOST_BPS
+            ),
+            tier_bps in proptest::option::of(
+                memecoin_staking::BPS_DENOMINATOR..=memecoin_staking::MAX_TIER_MULTIPLIER_BPS
+            ),
+        ) {
+            let flags = if halted { memecoin_staking::PAUSE_ACCRUAL } else { 0 };
+            let mut pool = pool(rate, flags, halted_at, halted_seconds);
+            pool.reward_index.value = index;
+            pool.reward_index.updated_at = index_time;
+            let mut user_stake = position(staked, pending, index_paid);
+            if let Some(multiplier_bps) = boost_bps {
+                pool.nft_boost.collection = Pubkey::new_unique();
+                pool.nft_boost.multiplier_bps = multiplier_bps;
+                user_stake.boost_mint = Pubkey::new_unique();
+            }
+            if let Some(multiplier_bps) = tier_bps {
+                let tier = Tier { min_stake: 0, multiplier_bps };
+                pool.tiers = TierTable::new(&[tier]).unwrap();
+                user_stake.tier = 1;
+                user_stake.tier_multiplier_bps = multiplier_bps;
+            }
+
+            prop_assert_eq!(accrual_time(&pool, now), pool.accrual_time(now));
+            prop_assert_eq!(reward_index(&pool, now), pool.reward_index_at(pool.accrual_time(now)));
+            prop_assert_eq!(
+                claimable_rewards(&pool, &user_stake, now),
+                memecoin_staking::claimable_rewards(&pool, &user_stake, pool.accrual_time(now)).ok()
+            );
+        }
+
+        #[test]
+        fn receipt_conversions_match_the_program(
+            amount in any::<u64>(),
+            total_underlying in any::<u64>(),
+            receipt_supply in any::<u64>(),
+        ) {
+            let (total, supply) = (total_underlying, receipt_supply);
+            prop_assert_eq!(
+                receipts_for_deposit(amount, total, supply),
+                memecoin_staking::receipts_for_deposit(amount, total, supply).ok()
+            );
+            prop_assert_eq!(
+                underlying_for_receipts(amount, total, supply),
+                memecoin_staking::underlying_for_receipts(amount, total, supply).ok()
+            );
+        }
+    }
+}
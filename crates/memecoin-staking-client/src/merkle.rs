@@ -0,0 +1,110 @@
+//! Merkle trees of distributor epochs
+//!
+//! Leaf `i` of a tree is `distributor_leaf(i, claimant, amount)` of the `i`th
+//! claim. An odd node out is carried up a layer unchanged, so it gets no
+//! sibling in its proofs.
+
+use anchor_lang::prelude::Pubkey;
+use memecoin_staking::{distributor_leaf, distributor_node};
+
+/// Tree over a distributor epoch's claims
+#[derive(Clone, Debug)]
+pub struct MerkleTree {
+    /// Leaves first, root last
+    layers: Vec<Vec<[u8; 32]>>,
+}
+
+impl MerkleTree {
+    /// Tree of `(claimant, amount)` claims in leaf index order
+    ///
+    /// # Panics
+    ///
+    /// If `claims` is empty or has more than `u32::MAX` entries.
+    pub fn new(claims: &[(Pubkey, u64)]) -> Self {
+        assert!(!claims.is_empty(), "a Merkle tree needs at least one leaf");
+        let leaves = claims
+            .iter()
+            .enumerate()
+            .map(|(index, (claimant, amount))| {
+                let index = u32::try_from(index).expect("more than u32::MAX leaves");
+                distributor_leaf(index, claimant, *amount)
+            })
+            .collect();
+
+        let mut layers: Vec<Vec<[u8; 32]>> = vec![leaves];
+        while let Some(layer) = layers.last().filter(|layer| layer.len() > 1) {
+            let parents = layer
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => distributor_node(left, right),
+                    [odd] => *odd,
+                    _ => unreachable!(),
+                })
+                .collect();
+            layers.push(parents);
+        }
+        Self { layers }
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.layers[self.layers.len() - 1][0]
+    }
+
+    /// Number of leaves, as posted with the epoch
+    pub fn num_leaves(&self) -> u32 {
+        self.layers[0].len() as u32
+    }
+
+    /// Sibling hashes linking leaf `index` to the root
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of range.
+    pub fn proof(&self, index: usize) -> Vec<[u8; 32]> {
+        assert!(index < self.layers[0].len(), "leaf {index} out of range");
+        let mut index = index;
+        let mut proof = Vec::new();
+        for layer in &self.layers[..self.layers.len() - 1] {
+            if let Some(sibling) = layer.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            index /= 2;
+        }
+        proof
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use memecoin_staking::verify_distributor_proof;
+
+    #[test]
+    fn every_proof_verifies_against_the_root() {
+        for size in 1..=9 {
+            let claims: Vec<_> = (0..size)
+                .map(|amount| (Pubkey::new_unique(), amount))
+                .collect();
+            let tree = MerkleTree::new(&claims);
+            let root = tree.root();
+            assert_eq!(tree.num_leaves(), size as u32);
+
+            for (index, (claimant, amount)) in claims.iter().enumerate() {
+                let proof = tree.proof(index);
+                let leaf = distributor_leaf(index as u32, claimant, *amount);
+                assert!(verify_distributor_proof(&proof, &root, leaf));
+
+                let wrong_amount = distributor_leaf(index as u32, claimant, amount + 1);
+                assert!(!verify_distributor_proof(&proof, &root, wrong_amount));
+            }
+        }
+    }
+
+    #[test]
+    fn single_leaf_is_the_root() {
+        let claimant = Pubkey::new_unique();
+        let tree = MerkleTree::new(&[(claimant, 5)]);
+        assert_eq!(tree.root(), distributor_leaf(0, &claimant, 5));
+        assert!(tree.proof(0).is_empty());
+    }
+}
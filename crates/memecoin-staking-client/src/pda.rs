@@ -0,0 +1,90 @@
+//! Program-derived addresses, mirroring the program's account seeds
+
+use anchor_lang::prelude::Pubkey;
+
+use crate::ID;
+
+fn find(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &ID).0
+}
+
+/// Stake pool of `staking_mint`
+pub fn pool(staking_mint: &Pubkey) -> Pubkey {
+    find(&[b"pool", staking_mint.as_ref()])
+}
+
+/// Vault holding a pool's staked tokens
+pub fn pool_vault(pool: &Pubkey) -> Pubkey {
+    find(&[b"pool_vault", pool.as_ref()])
+}
+
+/// Vault holding a pool's reward tokens
+pub fn reward_vault(pool: &Pubkey) -> Pubkey {
+    find(&[b"reward_vault", pool.as_ref()])
+}
+
+/// `owner`'s stake position in a pool
+pub fn user_stake(pool: &Pubkey, owner: &Pubkey) -> Pubkey {
+    find(&[b"user_stake", pool.as_ref(), owner.as_ref()])
+}
+
+/// Stake position backing a position NFT
+pub fn position_stake(position_mint: &Pubkey) -> Pubkey {
+    find(&[b"position", position_mint.as_ref()])
+}
+
+/// Liquid staking receipt mint of a pool
+pub fn receipt_mint(pool: &Pubkey) -> Pubkey {
+    find(&[b"receipt_mint", pool.as_ref()])
+}
+
+/// Pool-owned stake position shared by all liquid stakers
+pub fn liquid_stake(pool: &Pubkey) -> Pubkey {
+    find(&[b"liquid_stake", pool.as_ref()])
+}
+
+/// Stake balance history of `owner` in a pool
+pub fn checkpoints(pool: &Pubkey, owner: &Pubkey) -> Pubkey {
+    find(&[b"checkpoints", pool.as_ref(), owner.as_ref()])
+}
+
+/// Page `page` of a pool's total staked history
+pub fn pool_checkpoints(pool: &Pubkey, page: u32) -> Pubkey {
+    find(&[b"pool_checkpoints", pool.as_ref(), &page.to_le_bytes()])
+}
+
+/// spl-governance voter weight record of `voter` in a pool
+pub fn voter_weight_record(pool: &Pubkey, voter: &Pubkey) -> Pubkey {
+    find(&[b"voter_weight_record", pool.as_ref(), voter.as_ref()])
+}
+
+/// Referral stats of `referrer` in a pool
+pub fn referrer_stats(pool: &Pubkey, referrer: &Pubkey) -> Pubkey {
+    find(&[b"referrer", pool.as_ref(), referrer.as_ref()])
+}
+
+/// Escrow holding the boost NFT attached to a stake position
+pub fn boost_escrow(user_stake: &Pubkey) -> Pubkey {
+    find(&[b"boost_escrow", user_stake.as_ref()])
+}
+
+/// Metaplex metadata account of `mint`
+pub fn nft_metadata(mint: &Pubkey) -> Pubkey {
+    let program = memecoin_staking::TOKEN_METADATA_PROGRAM_ID;
+    Pubkey::find_program_address(&[b"metadata", program.as_ref(), mint.as_ref()], &program).0
+}
+
+/// Sponsored campaign `id` of a pool
+pub fn campaign(pool: &Pubkey, id: u64) -> Pubkey {
+    find(&[b"campaign", pool.as_ref(), &id.to_le_bytes()])
+}
+
+/// Vault holding a campaign's reward tokens
+pub fn campaign_vault(campaign: &Pubkey) -> Pubkey {
+    find(&[b"campaign_vault", campaign.as_ref()])
+}
+
+/// Merkle distributor epoch `epoch` of a pool
+pub fn distributor_epoch(pool: &Pubkey, epoch: u64) -> Pubkey {
+    find(&[b"distributor", pool.as_ref(), &epoch.to_le_bytes()])
+}
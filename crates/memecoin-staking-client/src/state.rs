@@ -0,0 +1,175 @@
+//! Account deserializers
+//!
+//! Each checks the account discriminator, so passing the wrong account type fails.
+
+use anchor_lang::{AccountDeserialize, Result};
+
+use crate::{
+    Campaign, CheckpointPage, DistributorEpoch, ReferrerStats, StakeCheckpoints, StakePool,
+    UserStake, VoterWeightRecord,
+};
+
+fn deserialize<T: AccountDeserialize>(mut data: &[u8]) -> Result<T> {
+    T::try_deserialize(&mut data)
+}
+
+/// Deserialize a `StakePool` account
+pub fn stake_pool(data: &[u8]) -> Result<StakePool> {
+    deserialize(data)
+}
+
+/// Deserialize a `UserStake` account (wallet, position NFT or liquid stake)
+pub fn user_stake(data: &[u8]) -> Result<UserStake> {
+    deserialize(data)
+}
+
+/// Deserialize a `StakeCheckpoints` account
+pub fn stake_checkpoints(data: &[u8]) -> Result<StakeCheckpoints> {
+    deserialize(data)
+}
+
+/// Deserialize a `CheckpointPage` account
+pub fn checkpoint_page(data: &[u8]) -> Result<CheckpointPage> {
+    deserialize(data)
+}
+
+/// Page of a pool's checkpoint ring to pass `get_pool_stake_at` for `timestamp`
+///
+/// Binary searches the pages' first changes in history order; `None` if the ring no
+/// longer reaches back to `timestamp`.
+pub fn checkpoint_page_at(pages: &[CheckpointPage], timestamp: i64) -> Option<u32> {
+    let mut written: Vec<_> = pages
+        .iter()
+        .filter(|page| !page.checkpoints.is_empty())
+        .collect();
+    written.sort_by_key(|page| page.sequence);
+
+    let index = written.partition_point(|page| page.checkpoints[0].timestamp < timestamp);
+    match index.checked_sub(1) {
+        Some(previous) => Some(written[previous].page),
+        // Times before the first change are only known while the first page is kept
+        None => match written.first() {
+            Some(first) => (first.page == 0 && first.sequence == 0).then_some(0),
+            None => Some(0),
+        },
+    }
+}
+
+/// Deserialize a `VoterWeightRecord` account
+pub fn voter_weight_record(data: &[u8]) -> Result<VoterWeightRecord> {
+    deserialize(data)
+}
+
+/// Deserialize a `ReferrerStats` account
+pub fn referrer_stats(data: &[u8]) -> Result<ReferrerStats> {
+    deserialize(data)
+}
+
+/// Deserialize a `Campaign` account
+pub fn campaign(data: &[u8]) -> Result<Campaign> {
+    deserialize(data)
+}
+
+/// Deserialize a `DistributorEpoch` account
+pub fn distributor_epoch(data: &[u8]) -> Result<DistributorEpoch> {
+    deserialize(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::prelude::Pubkey;
+    use anchor_lang::AccountSerialize;
+
+    use crate::Checkpoint;
+
+    fn sample_stake() -> UserStake {
+        UserStake {
+            owner: Pubkey::new_unique(),
+            pool: Pubkey::new_unique(),
+            staked_amount: 1_000,
+            pending_rewards: 7,
+            last_stake_time: 100,
+            last_accrual_time: 90,
+            stake_start_time: 50,
+            total_rewards_claimed: 3,
+            claim_delegate: Pubkey::default(),
+            reward_destination: Pubkey::default(),
+            referrer: Pubkey::default(),
+            referral_pending: 0,
+            boost_mint: Pubkey::default(),
+            tier: 0,
+            campaign_rewards: Default::default(),
+            reward_index_paid: 0,
+            tier_multiplier_bps: memecoin_staking::BPS_DENOMINATOR,
+            bump: 254,
+        }
+    }
+
+    fn page(page: u32, sequence: u64, timestamps: &[i64]) -> CheckpointPage {
+        CheckpointPage {
+            pool: Pubkey::default(),
+            page,
+            sequence,
+            checkpoints: timestamps
+                .iter()
+                .map(|&timestamp| Checkpoint {
+                    slot: timestamp as u64,
+                    timestamp,
+                    amount: timestamp as u64,
+                })
+                .collect(),
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn finds_the_page_holding_the_last_earlier_change() {
+        // Page 0 was overwritten after pages 1 and 2 filled up
+        let pages = [
+            page(0, 3, &[50, 60]),
+            page(1, 1, &[10, 20]),
+            page(2, 2, &[30, 40]),
+        ];
+        assert_eq!(checkpoint_page_at(&pages, 10), None);
+        assert_eq!(checkpoint_page_at(&pages, 11), Some(1));
+        assert_eq!(checkpoint_page_at(&pages, 30), Some(1));
+        assert_eq!(checkpoint_page_at(&pages, 31), Some(2));
+        assert_eq!(checkpoint_page_at(&pages, i64::MAX), Some(0));
+
+        // The program accepts every page found and reads the last change before the time
+        for timestamp in [11, 30, 31, 45, 61] {
+            let index = checkpoint_page_at(&pages, timestamp).unwrap() as usize;
+            let following = pages
+                .iter()
+                .find(|p| p.sequence == pages[index].sequence + 1);
+            let last_change = [10, 20, 30, 40, 50, 60]
+                .into_iter()
+                .rfind(|&t| t < timestamp)
+                .unwrap();
+            assert_eq!(
+                pages[index].amount_at(timestamp, following, 3).unwrap(),
+                last_change as u64
+            );
+        }
+
+        let fresh = [page(0, 0, &[]), page(1, 0, &[])];
+        assert_eq!(checkpoint_page_at(&fresh, 5), Some(0));
+        assert_eq!(fresh[0].amount_at(5, None, 0).unwrap(), 0);
+    }
+
+    #[test]
+    fn round_trips_accounts_and_checks_discriminators() {
+        let stake = sample_stake();
+        let mut data = Vec::new();
+        stake.try_serialize(&mut data).unwrap();
+
+        let parsed = user_stake(&data).unwrap();
+        assert_eq!(parsed.owner, stake.owner);
+        assert_eq!(parsed.staked_amount, stake.staked_amount);
+        assert_eq!(parsed.last_accrual_time, stake.last_accrual_time);
+
+        assert!(stake_pool(&data).is_err());
+        assert!(user_stake(&data[..data.len() - 1]).is_err());
+    }
+}
@@ -0,0 +1,829 @@
+//! Admin CLI for the memecoin staking program
+//!
+//! Amounts and rates are given in human units and converted with the mints'
+//! decimals, e.g.
+//!
+//! ```text
+//! staking-cli init-pool --staking-mint <MINT> --reward-mint <USDC> \
+//!     --rate "0.10 USDC per 1M tokens per 5 min" --lock-period 5m --min-stake 1M
+//! staking-cli fund --staking-mint <MINT> --amount 30
+//! ```
+//!
+//! `--dry-run` simulates the transaction and prints it instead of sending it.
+
+mod rpc;
+mod units;
+
+use std::path::{Path, PathBuf};
+
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use anchor_spl::token;
+use anyhow::{anyhow, ensure, Context as _, Result};
+use clap::{Args, Parser, Subcommand};
+use memecoin_staking_client::{
+    instructions, math, pda, program, state, CampaignKeys, FeeConfig, PoolKeys, RewardRate, Tier,
+};
+use solana_sdk::signature::{read_keypair_file, Signer};
+use staking_distributor::Distribution;
+
+use crate::rpc::Context;
+use crate::units::{format_amount, parse_amount, parse_duration, parse_rate};
+
+#[derive(Parser)]
+#[command(
+    name = "staking-cli",
+    version,
+    about = "Administer memecoin staking pools"
+)]
+struct Cli {
+    /// RPC endpoint
+    #[arg(
+        long,
+        short,
+        global = true,
+        env = "RPC_URL",
+        default_value = "https://api.mainnet-beta.solana.com"
+    )]
+    url: String,
+
+    /// Payer and authority keypair [default: ~/.config/solana/id.json]
+    #[arg(long, short, global = true, env = "ANCHOR_WALLET")]
+    keypair: Option<PathBuf>,
+
+    /// Simulate and print the transaction instead of sending it
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Args)]
+struct PoolArgs {
+    /// Mint staked in the pool (the pool is derived from it)
+    #[arg(long)]
+    staking_mint: Pubkey,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create a pool with the payer as its authority
+    InitPool {
+        #[command(flatten)]
+        pool: PoolArgs,
+        /// Mint rewards are paid in
+        #[arg(long)]
+        reward_mint: Pubkey,
+        /// Reward rate, e.g. "0.10 USDC per 1M tokens per 5 min"
+        #[arg(long)]
+        rate: String,
+        /// Minimum time between staking and unstaking, e.g. "5m"
+        #[arg(long, default_value = "0")]
+        lock_period: String,
+        /// Minimum stake in staking tokens, e.g. "1M" (the pool's only tier until `set-tiers`)
+        #[arg(long, default_value = "0")]
+        min_stake: String,
+    },
+    /// Deposit reward tokens into the pool's reward vault
+    Fund {
+        #[command(flatten)]
+        pool: PoolArgs,
+        /// Reward tokens to deposit, e.g. "30"
+        #[arg(long)]
+        amount: String,
+        /// Token account to fund from [default: the payer's associated token account]
+        #[arg(long)]
+        from: Option<Pubkey>,
+    },
+    /// Replace the pool's reward rate
+    SetRate {
+        #[command(flatten)]
+        pool: PoolArgs,
+        /// Reward rate, e.g. "0.10 USDC per 1M tokens per 5 min"
+        #[arg(long)]
+        rate: String,
+    },
+    /// Pause every pool operation
+    Pause {
+        #[command(flatten)]
+        pool: PoolArgs,
+    },
+    /// Resume every pool operation
+    Unpause {
+        #[command(flatten)]
+        pool: PoolArgs,
+    },
+    /// Set the share of referees' rewards paid to their referrers
+    SetReferralBps {
+        #[command(flatten)]
+        pool: PoolArgs,
+        /// Share in basis points, e.g. 500 for 5%
+        #[arg(long)]
+        bps: u16,
+    },
+    /// Boost the rewards of stakes escrowing an NFT of a verified collection
+    SetNftBoost {
+        #[command(flatten)]
+        pool: PoolArgs,
+        /// Metaplex collection mint [default: disable the boost]
+        #[arg(long)]
+        collection: Option<Pubkey>,
+        /// Reward weight multiplier in basis points, e.g. 15000 for 1.5x
+        #[arg(long, default_value = "10000")]
+        multiplier_bps: u16,
+    },
+    /// Replace the pool's staking tiers; the lowest threshold becomes the minimum stake
+    SetTiers {
+        #[command(flatten)]
+        pool: PoolArgs,
+        /// Tier as "<min stake>:<multiplier bps>" in ascending order, e.g.
+        /// --tier 1M:10000 --tier 10M:15000 --tier 100M:20000
+        #[arg(long = "tier", required = true)]
+        tiers: Vec<String>,
+    },
+    /// Set the deposit, withdrawal and performance fees in basis points
+    SetFees {
+        #[command(flatten)]
+        pool: PoolArgs,
+        #[arg(long, default_value = "0")]
+        deposit_bps: u16,
+        #[arg(long, default_value = "0")]
+        withdrawal_bps: u16,
+        #[arg(long, default_value = "0")]
+        performance_bps: u16,
+        /// Staking token account receiving deposit and withdrawal fees
+        /// [default: the payer's associated token account]
+        #[arg(long)]
+        staking_treasury: Option<Pubkey>,
+        /// Reward token account receiving performance fees
+        /// [default: the payer's associated token account]
+        #[arg(long)]
+        reward_treasury: Option<Pubkey>,
+    },
+    /// Sponsor a campaign paying the pool's stakers in any mint, funded by the payer
+    CreateCampaign {
+        #[command(flatten)]
+        pool: PoolArgs,
+        /// Mint the campaign pays rewards in
+        #[arg(long)]
+        reward_mint: Pubkey,
+        /// Reward tokens paid out over the campaign, e.g. "50k"
+        #[arg(long)]
+        amount: String,
+        /// Delay before the campaign starts, e.g. "1h"
+        #[arg(long, default_value = "1m")]
+        starts_in: String,
+        /// How long the campaign runs, e.g. "30d"
+        #[arg(long)]
+        duration: String,
+        /// Token account to fund from [default: the payer's associated token account]
+        #[arg(long)]
+        from: Option<Pubkey>,
+    },
+    /// Return an ended campaign's unallocated rewards to the payer, its funder
+    ReclaimCampaign {
+        #[command(flatten)]
+        pool: PoolArgs,
+        #[arg(long)]
+        id: u64,
+    },
+    /// Close a campaign after its claim period, returning everything left to the payer
+    CloseCampaign {
+        #[command(flatten)]
+        pool: PoolArgs,
+        #[arg(long)]
+        id: u64,
+    },
+    /// Print a pool's configuration and balances
+    ShowPool {
+        #[command(flatten)]
+        pool: PoolArgs,
+    },
+    /// Print a stake position [default owner: the payer]
+    ShowUser {
+        #[command(flatten)]
+        pool: PoolArgs,
+        #[arg(long)]
+        owner: Option<Pubkey>,
+    },
+    /// List every stake position in a pool, largest first
+    ListStakers {
+        #[command(flatten)]
+        pool: PoolArgs,
+    },
+    /// Open the next Merkle distributor epoch from a `staking-distributor build` file
+    PostDistribution {
+        #[command(flatten)]
+        pool: PoolArgs,
+        distribution: PathBuf,
+    },
+    /// Close a distributor epoch, returning its unclaimed rewards to stakers
+    CloseDistribution {
+        #[command(flatten)]
+        pool: PoolArgs,
+        #[arg(long)]
+        epoch: u64,
+    },
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let keypair_path = match cli.keypair {
+        Some(path) => path,
+        None => default_keypair_path()?,
+    };
+    let payer = read_keypair_file(&keypair_path)
+        .map_err(|err| anyhow!("failed to read keypair {}: {err}", keypair_path.display()))?;
+    let ctx = Context::new(cli.url, payer, cli.dry_run);
+
+    match cli.command {
+        Command::InitPool {
+            pool,
+            reward_mint,
+            rate,
+            lock_period,
+            min_stake,
+        } => init_pool(
+            &ctx,
+            &pool.staking_mint,
+            &reward_mint,
+            &rate,
+            &lock_period,
+            &min_stake,
+        ),
+        Command::Fund { pool, amount, from } => fund(&ctx, &pool.staking_mint, &amount, from),
+        Command::SetRate { pool, rate } => set_rate(&ctx, &pool.staking_mint, &rate),
+        Command::Pause { pool } => set_paused(&ctx, &pool.staking_mint, true),
+        Command::Unpause { pool } => set_paused(&ctx, &pool.staking_mint, false),
+        Command::SetReferralBps { pool, bps } => set_referral_bps(&ctx, &pool.staking_mint, bps),
+        Command::SetNftBoost {
+            pool,
+            collection,
+            multiplier_bps,
+        } => set_nft_boost(&ctx, &pool.staking_mint, collection, multiplier_bps),
+        Command::SetTiers { pool, tiers } => set_tiers(&ctx, &pool.staking_mint, &tiers),
+        Command::SetFees {
+            pool,
+            deposit_bps,
+            withdrawal_bps,
+            performance_bps,
+            staking_treasury,
+            reward_treasury,
+        } => set_fees(
+            &ctx,
+            &pool.staking_mint,
+            deposit_bps,
+            withdrawal_bps,
+            performance_bps,
+            staking_treasury,
+            reward_treasury,
+        ),
+        Command::CreateCampaign {
+            pool,
+            reward_mint,
+            amount,
+            starts_in,
+            duration,
+            from,
+        } => create_campaign(
+            &ctx,
+            &pool.staking_mint,
+            &reward_mint,
+            &amount,
+            &starts_in,
+            &duration,
+            from,
+        ),
+        Command::ReclaimCampaign { pool, id } => {
+            end_campaign(&ctx, &pool.staking_mint, id, CampaignEnd::Reclaim)
+        }
+        Command::CloseCampaign { pool, id } => {
+            end_campaign(&ctx, &pool.staking_mint, id, CampaignEnd::Close)
+        }
+        Command::ShowPool { pool } => show_pool(&ctx, &pool.staking_mint),
+        Command::ShowUser { pool, owner } => {
+            let owner = owner.unwrap_or_else(|| ctx.payer.pubkey());
+            show_user(&ctx, &pool.staking_mint, &owner)
+        }
+        Command::ListStakers { pool } => list_stakers(&ctx, &pool.staking_mint),
+        Command::PostDistribution { pool, distribution } => {
+            post_distribution(&ctx, &pool.staking_mint, &distribution)
+        }
+        Command::CloseDistribution { pool, epoch } => {
+            close_distribution(&ctx, &pool.staking_mint, epoch)
+        }
+    }
+}
+
+fn default_keypair_path() -> Result<PathBuf> {
+    let home =
+        std::env::var_os("HOME").ok_or_else(|| anyhow!("HOME is not set; pass --keypair"))?;
+    Ok(PathBuf::from(home).join(".config/solana/id.json"))
+}
+
+fn init_pool(
+    ctx: &Context,
+    staking_mint: &Pubkey,
+    reward_mint: &Pubkey,
+    rate: &str,
+    lock_period: &str,
+    min_stake: &str,
+) -> Result<()> {
+    let staking_token_program = ctx.account(staking_mint)?.owner;
+    ensure!(
+        ctx.account(reward_mint)?.owner == token::ID,
+        "reward mint {reward_mint} must belong to the Token program"
+    );
+    let staking_decimals = ctx.mint(staking_mint)?.decimals;
+    let reward_decimals = ctx.mint(reward_mint)?.decimals;
+
+    let spec = parse_rate(rate, reward_decimals, staking_decimals)?;
+    let reward_rate = spec.reward_rate()?;
+    let lock_period = i64::try_from(parse_duration(lock_period)?)?;
+    let min_stake_amount = parse_amount(min_stake, staking_decimals)?;
+
+    let keys = PoolKeys::new(*staking_mint, *reward_mint, staking_token_program);
+    println!("Pool: {}", keys.pool);
+    println!("Pool vault: {}", keys.pool_vault());
+    println!("Reward vault: {}", keys.reward_vault());
+    println!(
+        "Reward rate: {} per {} per {}s (scaled {})",
+        spec.reward_amount, spec.per_staked_amount, spec.period_seconds, reward_rate.0
+    );
+    println!("Lock period: {lock_period}s");
+    println!("Min stake: {min_stake_amount} base units");
+
+    let authority = ctx.payer.pubkey();
+    ctx.send(
+        &[instructions::initialize_pool(
+            &keys,
+            &authority,
+            reward_rate,
+            lock_period,
+            min_stake_amount,
+        )],
+        &[],
+    )
+}
+
+fn fund(ctx: &Context, staking_mint: &Pubkey, amount: &str, from: Option<Pubkey>) -> Result<()> {
+    let (keys, _) = ctx.pool(staking_mint)?;
+    let decimals = ctx.mint(&keys.reward_mint)?.decimals;
+    let amount = parse_amount(amount, decimals)?;
+    let funder = ctx.payer.pubkey();
+    let from = from.unwrap_or_else(|| {
+        get_associated_token_address_with_program_id(&funder, &keys.reward_mint, &token::ID)
+    });
+
+    let balance = ctx.token_balance(&from)?;
+    ensure!(
+        balance >= amount,
+        "{from} holds {} reward tokens, {} needed",
+        format_amount(balance, decimals),
+        format_amount(amount, decimals)
+    );
+
+    println!("Funding {} from {from}", format_amount(amount, decimals));
+    ctx.send(
+        &[instructions::fund_rewards(&keys, &funder, &from, amount)],
+        &[],
+    )
+}
+
+fn set_rate(ctx: &Context, staking_mint: &Pubkey, rate: &str) -> Result<()> {
+    let (keys, pool) = ctx.pool(staking_mint)?;
+    let spec = parse_rate(
+        rate,
+        ctx.mint(&keys.reward_mint)?.decimals,
+        ctx.mint(staking_mint)?.decimals,
+    )?;
+    let new_rate = spec.reward_rate()?;
+
+    println!("Reward rate: {} -> {}", pool.reward_rate.0, new_rate.0);
+    let authority = ctx.payer.pubkey();
+    ctx.send(
+        &[instructions::update_reward_rate(
+            &keys, &authority, new_rate,
+        )],
+        &[],
+    )
+}
+
+fn set_paused(ctx: &Context, staking_mint: &Pubkey, paused: bool) -> Result<()> {
+    let (keys, _) = ctx.pool(staking_mint)?;
+    let authority = ctx.payer.pubkey();
+    ctx.send(&[instructions::set_paused(&keys, &authority, paused)], &[])
+}
+
+fn set_referral_bps(ctx: &Context, staking_mint: &Pubkey, bps: u16) -> Result<()> {
+    let (keys, pool) = ctx.pool(staking_mint)?;
+    println!("Referral share: {} -> {bps} bps", pool.referral_bps);
+    let authority = ctx.payer.pubkey();
+    ctx.send(
+        &[instructions::set_referral_bps(&keys, &authority, bps)],
+        &[],
+    )
+}
+
+fn set_nft_boost(
+    ctx: &Context,
+    staking_mint: &Pubkey,
+    collection: Option<Pubkey>,
+    multiplier_bps: u16,
+) -> Result<()> {
+    let (keys, _) = ctx.pool(staking_mint)?;
+    let collection = collection.unwrap_or_default();
+    if collection == Pubkey::default() {
+        println!("Disabling the NFT boost");
+    } else {
+        println!("NFT boost: {multiplier_bps} bps for collection {collection}");
+    }
+    let authority = ctx.payer.pubkey();
+    ctx.send(
+        &[instructions::set_nft_boost(
+            &keys,
+            &authority,
+            &collection,
+            multiplier_bps,
+        )],
+        &[],
+    )
+}
+
+fn set_tiers(ctx: &Context, staking_mint: &Pubkey, tiers: &[String]) -> Result<()> {
+    let (keys, _) = ctx.pool(staking_mint)?;
+    let staking_decimals = ctx.mint(staking_mint)?.decimals;
+    let tiers = tiers
+        .iter()
+        .map(|tier| {
+            let (min_stake, multiplier_bps) = tier
+                .split_once(':')
+                .ok_or_else(|| anyhow!("tier {tier:?} is not <min stake>:<multiplier bps>"))?;
+            Ok(Tier {
+                min_stake: parse_amount(min_stake, staking_decimals)?,
+                multiplier_bps: multiplier_bps
+                    .parse()
+                    .with_context(|| format!("invalid multiplier in tier {tier:?}"))?,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    for (level, tier) in (1..).zip(&tiers) {
+        println!(
+            "Tier {level}: {}+ at {} bps",
+            format_amount(tier.min_stake, staking_decimals),
+            tier.multiplier_bps
+        );
+    }
+    let authority = ctx.payer.pubkey();
+    ctx.send(&[instructions::set_tiers(&keys, &authority, tiers)], &[])
+}
+
+fn set_fees(
+    ctx: &Context,
+    staking_mint: &Pubkey,
+    deposit_bps: u16,
+    withdrawal_bps: u16,
+    performance_bps: u16,
+    staking_treasury: Option<Pubkey>,
+    reward_treasury: Option<Pubkey>,
+) -> Result<()> {
+    let (keys, _) = ctx.pool(staking_mint)?;
+    let authority = ctx.payer.pubkey();
+    let staking_treasury = staking_treasury.unwrap_or_else(|| {
+        get_associated_token_address_with_program_id(
+            &authority,
+            staking_mint,
+            &keys.staking_token_program,
+        )
+    });
+    let reward_treasury = reward_treasury.unwrap_or_else(|| {
+        get_associated_token_address_with_program_id(&authority, &keys.reward_mint, &token::ID)
+    });
+
+    println!(
+        "Fees: {deposit_bps} bps deposit, {withdrawal_bps} bps withdrawal to {staking_treasury}, \
+         {performance_bps} bps performance to {reward_treasury}"
+    );
+    ctx.send(
+        &[instructions::set_fees(
+            &keys,
+            &authority,
+            &staking_treasury,
+            &reward_treasury,
+            deposit_bps,
+            withdrawal_bps,
+            performance_bps,
+        )],
+        &[],
+    )
+}
+
+fn create_campaign(
+    ctx: &Context,
+    staking_mint: &Pubkey,
+    reward_mint: &Pubkey,
+    amount: &str,
+    starts_in: &str,
+    duration: &str,
+    from: Option<Pubkey>,
+) -> Result<()> {
+    let (keys, pool) = ctx.pool(staking_mint)?;
+    let decimals = ctx.mint(reward_mint)?.decimals;
+    let amount = parse_amount(amount, decimals)?;
+    let start_time = ctx.now()? + duration_secs(starts_in)?;
+    let end_time = start_time + duration_secs(duration)?;
+    let campaign = CampaignKeys::new(
+        &keys.pool,
+        pool.campaigns.created,
+        *reward_mint,
+        ctx.account(reward_mint)?.owner,
+    );
+    let funder = ctx.payer.pubkey();
+    let from = from.unwrap_or_else(|| {
+        get_associated_token_address_with_program_id(
+            &funder,
+            reward_mint,
+            &campaign.reward_token_program,
+        )
+    });
+
+    println!(
+        "Campaign {} ({}): {} from {start_time} to {end_time}",
+        pool.campaigns.created,
+        campaign.campaign,
+        format_amount(amount, decimals)
+    );
+    ctx.send(
+        &[instructions::create_campaign(
+            &keys, &campaign, &funder, &from, amount, start_time, end_time,
+        )],
+        &[],
+    )
+}
+
+enum CampaignEnd {
+    Reclaim,
+    Close,
+}
+
+fn end_campaign(ctx: &Context, staking_mint: &Pubkey, id: u64, end: CampaignEnd) -> Result<()> {
+    let (keys, _) = ctx.pool(staking_mint)?;
+    let address = pda::campaign(&keys.pool, id);
+    let campaign = state::campaign(&ctx.account(&address)?.data)
+        .with_context(|| format!("{address} is not a campaign"))?;
+    let campaign = CampaignKeys::new(
+        &keys.pool,
+        id,
+        campaign.reward_mint,
+        ctx.account(&campaign.reward_mint)?.owner,
+    );
+    let funder = ctx.payer.pubkey();
+    let to = get_associated_token_address_with_program_id(
+        &funder,
+        &campaign.reward_mint,
+        &campaign.reward_token_program,
+    );
+
+    let instruction = match end {
+        CampaignEnd::Reclaim => {
+            instructions::reclaim_campaign_leftovers(&keys, &campaign, &funder, &to)
+        }
+        CampaignEnd::Close => instructions::close_campaign(&keys, &campaign, &funder, &to),
+    };
+    ctx.send(&[instruction], &[])
+}
+
+/// Whole seconds of a duration argument
+fn duration_secs(input: &str) -> Result<i64> {
+    i64::try_from(parse_duration(input)?).with_context(|| format!("duration {input:?} is too long"))
+}
+
+/// `rate` as rewards per million staked tokens per day
+fn describe_rate(rate: RewardRate, reward_decimals: u8, staking_decimals: u8) -> String {
+    let million = 1_000_000u64.checked_mul(10u64.pow(u32::from(staking_decimals)));
+    match million.and_then(|staked| math::rewards_for(rate, staked, 86_400)) {
+        Some(daily) => format!(
+            "{} per 1M tokens per day",
+            format_amount(daily, reward_decimals)
+        ),
+        None => "too large to display".to_string(),
+    }
+}
+
+fn show_pool(ctx: &Context, staking_mint: &Pubkey) -> Result<()> {
+    let (keys, pool) = ctx.pool(staking_mint)?;
+    let staking_decimals = ctx.mint(staking_mint)?.decimals;
+    let reward_decimals = ctx.mint(&keys.reward_mint)?.decimals;
+    let staked = |amount| format_amount(amount, staking_decimals);
+    let rewards = |amount| format_amount(amount, reward_decimals);
+
+    println!("Pool: {}", keys.pool);
+    println!("Authority: {}", pool.authority);
+    println!(
+        "Staking mint: {} ({})",
+        pool.staking_mint, keys.staking_token_program
+    );
+    println!("Reward mint: {}", pool.reward_mint);
+    println!(
+        "Reward rate: {} ({})",
+        pool.reward_rate.0,
+        describe_rate(pool.reward_rate, reward_decimals, staking_decimals)
+    );
+    println!("Lock period: {}s", pool.lock_period);
+    println!("Min stake: {}", staked(pool.tiers.min_stake()));
+    for (level, tier) in (1..).zip(pool.tiers.active()) {
+        println!(
+            "Tier {level}: {}+ at {} bps",
+            staked(tier.min_stake),
+            tier.multiplier_bps
+        );
+    }
+    println!("Total staked: {}", staked(pool.total_staked));
+    println!(
+        "Pool vault: {}",
+        staked(ctx.token_balance(&keys.pool_vault())?)
+    );
+    println!(
+        "Reward vault: {}",
+        rewards(ctx.token_balance(&keys.reward_vault())?)
+    );
+    println!("Pause flags: {:#010b}", pool.pause_flags);
+    println!("Emergency mode: {}", pool.emergency_mode);
+    println!("Transfers disabled: {}", pool.transfers_disabled);
+    println!("Crank tip: {} bps", pool.crank_tip_bps);
+    println!("Referral share: {} bps", pool.referral_bps);
+    if pool.fees != FeeConfig::default() {
+        println!(
+            "Fees: {} bps deposit, {} bps withdrawal, {} bps performance",
+            pool.fees.deposit_bps, pool.fees.withdrawal_bps, pool.fees.performance_bps
+        );
+        println!("Staking treasury: {}", pool.fees.staking_treasury);
+        println!("Reward treasury: {}", pool.fees.reward_treasury);
+    }
+    if pool.nft_boost.is_enabled() {
+        println!(
+            "NFT boost: {} bps for collection {}",
+            pool.nft_boost.multiplier_bps, pool.nft_boost.collection
+        );
+    }
+    if pool.rate_oracle.is_enabled() {
+        println!(
+            "Rate oracle: {} (target APR {} bps)",
+            pool.rate_oracle.price_account, pool.rate_oracle.target_apr_bps
+        );
+    }
+    if pool.receipt_mint != Pubkey::default() {
+        println!("Receipt mint: {}", pool.receipt_mint);
+    }
+    for slot in pool.campaigns.slots.iter().filter(|slot| slot.is_active()) {
+        println!(
+            "Campaign {}: {} base units from {} to {}, {} allocated",
+            slot.campaign, slot.amount, slot.start_time, slot.end_time, slot.allocated
+        );
+    }
+    if pool.distributor.epochs > 0 {
+        println!(
+            "Distributor: {} epochs, {} reserved",
+            pool.distributor.epochs,
+            rewards(pool.distributor.reserved)
+        );
+    }
+    Ok(())
+}
+
+fn show_user(ctx: &Context, staking_mint: &Pubkey, owner: &Pubkey) -> Result<()> {
+    let (keys, pool) = ctx.pool(staking_mint)?;
+    let user_stake = ctx.user_stake(&keys, owner)?;
+    let staking_decimals = ctx.mint(staking_mint)?.decimals;
+    let reward_decimals = ctx.mint(&keys.reward_mint)?.decimals;
+    let now = ctx.now()?;
+    let claimable = math::claimable_rewards(&pool, &user_stake, now)
+        .ok_or_else(|| anyhow!("claimable rewards overflow a u64"))?;
+
+    println!("Stake: {}", keys.user_stake(owner));
+    println!("Owner: {}", user_stake.owner);
+    println!(
+        "Staked: {}",
+        format_amount(user_stake.staked_amount, staking_decimals)
+    );
+    println!(
+        "Tier: {} ({} bps)",
+        user_stake.tier, user_stake.tier_multiplier_bps
+    );
+    println!("Claimable: {}", format_amount(claimable, reward_decimals));
+    println!(
+        "Claimed: {}",
+        format_amount(user_stake.total_rewards_claimed, reward_decimals)
+    );
+    println!("Staked since: {}", user_stake.stake_start_time);
+    let unlock_time = user_stake.unlock_time(pool.lock_period);
+    if unlock_time > now {
+        println!("Locked for: {}s", unlock_time - now);
+    }
+    if user_stake.claim_delegate != Pubkey::default() {
+        println!("Claim delegate: {}", user_stake.claim_delegate);
+    }
+    if user_stake.reward_destination != Pubkey::default() {
+        println!("Reward destination: {}", user_stake.reward_destination);
+    }
+    if user_stake.boost_mint != Pubkey::default() {
+        println!("Boost NFT: {}", user_stake.boost_mint);
+    }
+    for (index, slot) in pool.campaigns.slots.iter().enumerate() {
+        let claimable = program::campaign_claimable(
+            &pool.campaigns,
+            pool.campaign_staked(),
+            &user_stake,
+            index,
+            now,
+        )
+        .ok_or_else(|| anyhow!("campaign rewards overflow a u64"))?;
+        if claimable > 0 {
+            println!(
+                "Campaign {}: {claimable} base units claimable",
+                slot.campaign
+            );
+        }
+    }
+    if user_stake.referrer != Pubkey::default() {
+        let owed = math::referral_rewards(&pool, &user_stake, now)
+            .ok_or_else(|| anyhow!("referral rewards overflow a u64"))?;
+        println!(
+            "Referrer: {} (owed {})",
+            user_stake.referrer,
+            format_amount(owed, reward_decimals)
+        );
+    }
+    Ok(())
+}
+
+fn list_stakers(ctx: &Context, staking_mint: &Pubkey) -> Result<()> {
+    let snapshot = staking_snapshot::fetch(&ctx.rpc, staking_mint, None)?;
+    let (keys, _) = ctx.pool(staking_mint)?;
+    let staking_decimals = ctx.mint(staking_mint)?.decimals;
+    let reward_decimals = ctx.mint(&keys.reward_mint)?.decimals;
+
+    println!("{:<44} {:>24} {:>20}", "Owner", "Staked", "Claimable");
+    for row in &snapshot.stakes {
+        println!(
+            "{:<44} {:>24} {:>20}",
+            row.owner.to_string(),
+            format_amount(row.staked_amount, staking_decimals),
+            format_amount(row.pending_rewards, reward_decimals)
+        );
+    }
+    println!(
+        "{} positions, {} staked",
+        snapshot.stakes.len(),
+        format_amount(snapshot.total_staked, staking_decimals)
+    );
+    Ok(())
+}
+
+fn post_distribution(ctx: &Context, staking_mint: &Pubkey, path: &Path) -> Result<()> {
+    let (keys, pool) = ctx.pool(staking_mint)?;
+    let distribution = Distribution::read(path)?;
+    let decimals = ctx.mint(&keys.reward_mint)?.decimals;
+
+    let available = pool.available_rewards(ctx.token_balance(&keys.reward_vault())?);
+    ensure!(
+        available >= distribution.total_amount,
+        "the reward vault has {} unreserved, {} needed",
+        format_amount(available, decimals),
+        format_amount(distribution.total_amount, decimals)
+    );
+
+    let epoch = pool.distributor.epochs;
+    println!("Epoch: {epoch}");
+    println!("Merkle root: {}", hex::encode(distribution.merkle_root));
+    println!(
+        "Total: {} to {} claimants",
+        format_amount(distribution.total_amount, decimals),
+        distribution.num_leaves
+    );
+    let authority = ctx.payer.pubkey();
+    ctx.send(
+        &[instructions::post_distributor_epoch(
+            &keys,
+            &authority,
+            epoch,
+            distribution.merkle_root,
+            distribution.total_amount,
+            distribution.num_leaves,
+        )],
+        &[],
+    )
+}
+
+fn close_distribution(ctx: &Context, staking_mint: &Pubkey, epoch: u64) -> Result<()> {
+    let (keys, _) = ctx.pool(staking_mint)?;
+    let authority = ctx.payer.pubkey();
+    ctx.send(
+        &[instructions::close_distributor_epoch(
+            &keys, &authority, epoch,
+        )],
+        &[],
+    )
+}
@@ -0,0 +1,162 @@
+//! Cluster access: account fetching and transaction submission
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::AccountDeserialize;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+use anyhow::{anyhow, Context as _, Result};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use memecoin_staking_client::{pda, state, PoolKeys, StakePool, UserStake};
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_rpc_client_api::config::RpcSimulateTransactionConfig;
+use solana_sdk::account::{from_account, Account};
+use solana_sdk::clock::Clock;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::sysvar;
+use solana_sdk::transaction::Transaction;
+
+pub struct Context {
+    pub rpc: RpcClient,
+    pub payer: Keypair,
+    pub dry_run: bool,
+}
+
+impl Context {
+    pub fn new(url: String, payer: Keypair, dry_run: bool) -> Self {
+        Self {
+            rpc: RpcClient::new_with_commitment(url, CommitmentConfig::confirmed()),
+            payer,
+            dry_run,
+        }
+    }
+
+    pub fn account(&self, address: &Pubkey) -> Result<Account> {
+        self.rpc
+            .get_account(address)
+            .with_context(|| format!("failed to fetch account {address}"))
+    }
+
+    pub fn mint(&self, mint: &Pubkey) -> Result<Mint> {
+        let account = self.account(mint)?;
+        Mint::try_deserialize(&mut account.data.as_slice())
+            .with_context(|| format!("{mint} is not a token mint"))
+    }
+
+    /// Token balance of `address`, or zero if the account does not exist yet
+    pub fn token_balance(&self, address: &Pubkey) -> Result<u64> {
+        let Some(account) = self
+            .rpc
+            .get_account_with_commitment(address, self.rpc.commitment())?
+            .value
+        else {
+            return Ok(0);
+        };
+        let token_account = TokenAccount::try_deserialize(&mut account.data.as_slice())
+            .with_context(|| format!("{address} is not a token account"))?;
+        Ok(token_account.amount)
+    }
+
+    /// Keys and state of the pool staking `staking_mint`
+    pub fn pool(&self, staking_mint: &Pubkey) -> Result<(PoolKeys, StakePool)> {
+        let address = pda::pool(staking_mint);
+        let pool = state::stake_pool(&self.account(&address)?.data)
+            .with_context(|| format!("{address} is not a stake pool"))?;
+        let staking_token_program = self.account(staking_mint)?.owner;
+        let keys = PoolKeys::new(*staking_mint, pool.reward_mint, staking_token_program)
+            .with_fees(&pool.fees)
+            .with_rate_oracle(&pool.rate_oracle)
+            .with_checkpoint_ring(&pool.checkpoint_ring);
+        Ok((keys, pool))
+    }
+
+    pub fn user_stake(&self, keys: &PoolKeys, owner: &Pubkey) -> Result<UserStake> {
+        let address = keys.user_stake(owner);
+        let data = self
+            .rpc
+            .get_account_with_commitment(&address, self.rpc.commitment())?
+            .value
+            .ok_or_else(|| anyhow!("{owner} has no stake in pool {}", keys.pool))?
+            .data;
+        state::user_stake(&data).with_context(|| format!("{address} is not a user stake"))
+    }
+
+    /// Current cluster time
+    pub fn now(&self) -> Result<i64> {
+        let clock: Clock = from_account(&self.account(&sysvar::clock::ID)?)
+            .ok_or_else(|| anyhow!("failed to decode the clock sysvar"))?;
+        Ok(clock.unix_timestamp)
+    }
+
+    /// Sign and send `instructions` paid by the payer, or simulate and print them on a dry run
+    pub fn send(&self, instructions: &[Instruction], signers: &[&Keypair]) -> Result<()> {
+        let blockhash = self.rpc.get_latest_blockhash()?;
+        let mut all_signers = vec![&self.payer];
+        all_signers.extend_from_slice(signers);
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            &all_signers,
+            blockhash,
+        );
+
+        if self.dry_run {
+            return self.simulate(&transaction);
+        }
+
+        let signature = self
+            .rpc
+            .send_and_confirm_transaction_with_spinner(&transaction)?;
+        println!("Transaction: {signature}");
+        Ok(())
+    }
+
+    fn simulate(&self, transaction: &Transaction) -> Result<()> {
+        let message = &transaction.message;
+        println!("Dry run: simulating transaction");
+        println!("Fee payer: {}", self.payer.pubkey());
+        for (index, instruction) in message.instructions.iter().enumerate() {
+            let program = message.account_keys[usize::from(instruction.program_id_index)];
+            println!("Instruction {index}: program {program}");
+            for &account in &instruction.accounts {
+                let account = usize::from(account);
+                let signer = if message.is_signer(account) { "s" } else { "-" };
+                let writable = if message.is_maybe_writable(account, None) {
+                    "w"
+                } else {
+                    "-"
+                };
+                println!("  [{signer}{writable}] {}", message.account_keys[account]);
+            }
+            println!("  data: {}", BASE64_STANDARD.encode(&instruction.data));
+        }
+        let serialized = bincode::serialize(transaction)?;
+        println!(
+            "Transaction (base64): {}",
+            BASE64_STANDARD.encode(serialized)
+        );
+
+        let config = RpcSimulateTransactionConfig {
+            sig_verify: true,
+            commitment: Some(self.rpc.commitment()),
+            ..RpcSimulateTransactionConfig::default()
+        };
+        let result = self
+            .rpc
+            .simulate_transaction_with_config(transaction, config)?
+            .value;
+        for log in result.logs.unwrap_or_default() {
+            println!("  {log}");
+        }
+        if let Some(units) = result.units_consumed {
+            println!("Compute units: {units}");
+        }
+        match result.err {
+            Some(err) => Err(anyhow!("simulation failed: {err}")),
+            None => {
+                println!("Simulation succeeded; nothing was sent");
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,182 @@
+//! Human-readable token amounts, durations and reward rates
+//!
+//! Amounts accept decimals, `_`/`,` separators and `K`/`M`/`B` suffixes
+//! ("0.10", "1M", "1,000,000"). Durations take a unit ("300s", "5 min",
+//! "1 day"). Rates read "<reward> per <staked> per <period>", with optional
+//! token symbols that are ignored: "0.10 USDC per 1M tokens per 5 min".
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use memecoin_staking_client::RewardRate;
+
+/// A reward rate as written by a human, converted to base units
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateSpec {
+    pub reward_amount: u64,
+    pub per_staked_amount: u64,
+    pub period_seconds: u64,
+}
+
+impl RateSpec {
+    pub fn reward_rate(&self) -> Result<RewardRate> {
+        RewardRate::per_period(
+            self.reward_amount,
+            self.per_staked_amount,
+            self.period_seconds,
+        )
+        .ok_or_else(|| anyhow!("reward rate does not fit the program's fixed-point rate"))
+    }
+}
+
+/// Parse "<reward> per <staked> per <period>" given both mints' decimals
+pub fn parse_rate(input: &str, reward_decimals: u8, staking_decimals: u8) -> Result<RateSpec> {
+    let lower = input.to_ascii_lowercase();
+    let parts: Vec<&str> = lower.split(" per ").map(str::trim).collect();
+    let [reward, staked, period] = parts[..] else {
+        bail!("expected \"<reward> per <staked> per <period>\", got {input:?}");
+    };
+
+    let spec = RateSpec {
+        reward_amount: parse_amount(leading_word(reward), reward_decimals)
+            .with_context(|| format!("invalid reward amount {reward:?}"))?,
+        per_staked_amount: parse_amount(leading_word(staked), staking_decimals)
+            .with_context(|| format!("invalid staked amount {staked:?}"))?,
+        period_seconds: parse_duration(period)?,
+    };
+    ensure!(spec.per_staked_amount > 0, "staked amount must be positive");
+    Ok(spec)
+}
+
+/// The amount in "0.10 USDC", dropping the token symbol
+fn leading_word(input: &str) -> &str {
+    input.split_whitespace().next().unwrap_or_default()
+}
+
+/// Parse a token amount into base units of a mint with `decimals`
+pub fn parse_amount(input: &str, decimals: u8) -> Result<u64> {
+    let cleaned: String = input.chars().filter(|c| !matches!(c, '_' | ',')).collect();
+    let (number, multiplier) = match cleaned.char_indices().last() {
+        Some((i, 'k' | 'K')) => (&cleaned[..i], 1_000u128),
+        Some((i, 'm' | 'M')) => (&cleaned[..i], 1_000_000),
+        Some((i, 'b' | 'B')) => (&cleaned[..i], 1_000_000_000),
+        _ => (&cleaned[..], 1),
+    };
+
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    let digits = format!("{whole}{fraction}");
+    ensure!(
+        !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()),
+        "invalid amount {input:?}"
+    );
+
+    let too_large = || anyhow!("amount {input:?} is too large");
+    let mantissa: u128 = digits.parse().map_err(|_| too_large())?;
+    let fraction_scale = 10u128
+        .checked_pow(fraction.len() as u32)
+        .ok_or_else(too_large)?;
+    let scaled = mantissa
+        .checked_mul(multiplier)
+        .and_then(|value| value.checked_mul(10u128.pow(u32::from(decimals))))
+        .ok_or_else(too_large)?;
+
+    ensure!(
+        scaled % fraction_scale == 0,
+        "amount {input:?} has more than {decimals} decimals"
+    );
+    u64::try_from(scaled / fraction_scale).map_err(|_| too_large())
+}
+
+/// Parse a duration such as "300", "300s", "5 min", "1h" or "day" into seconds
+pub fn parse_duration(input: &str) -> Result<u64> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    let (count, unit) = input.split_at(split);
+    let count: u64 = if count.is_empty() {
+        1
+    } else {
+        count
+            .parse()
+            .with_context(|| format!("invalid duration {input:?}"))?
+    };
+
+    let seconds = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "s" | "sec" | "secs" | "second" | "seconds" => 1,
+        "m" | "min" | "mins" | "minute" | "minutes" => 60,
+        "h" | "hr" | "hrs" | "hour" | "hours" => 3_600,
+        "d" | "day" | "days" => 86_400,
+        "w" | "week" | "weeks" => 604_800,
+        unit => bail!("unknown duration unit {unit:?} in {input:?}"),
+    };
+    count
+        .checked_mul(seconds)
+        .ok_or_else(|| anyhow!("duration {input:?} is too long"))
+}
+
+/// Format base units of a mint with `decimals`, trimming trailing zeros
+pub fn format_amount(amount: u64, decimals: u8) -> String {
+    let scale = 10u128.pow(u32::from(decimals));
+    let (whole, fraction) = (amount as u128 / scale, amount as u128 % scale);
+    if fraction == 0 {
+        return whole.to_string();
+    }
+    let fraction = format!("{fraction:0width$}", width = usize::from(decimals));
+    format!("{whole}.{}", fraction.trim_end_matches('0'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_amounts() {
+        assert_eq!(parse_amount("0.10", 6).unwrap(), 100_000);
+        assert_eq!(parse_amount("1M", 6).unwrap(), 1_000_000_000_000);
+        assert_eq!(parse_amount("1,000,000", 6).unwrap(), 1_000_000_000_000);
+        assert_eq!(parse_amount("2.5k", 0).unwrap(), 2_500);
+        assert_eq!(parse_amount("30", 6).unwrap(), 30_000_000);
+
+        assert!(parse_amount("0.0000001", 6).is_err());
+        assert!(parse_amount("1.2.3", 6).is_err());
+        assert!(parse_amount("", 6).is_err());
+        assert!(parse_amount("-1", 6).is_err());
+        assert!(parse_amount("20B", 9).is_err());
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("300").unwrap(), 300);
+        assert_eq!(parse_duration("5 min").unwrap(), 300);
+        assert_eq!(parse_duration("5m").unwrap(), 300);
+        assert_eq!(parse_duration("1h").unwrap(), 3_600);
+        assert_eq!(parse_duration("day").unwrap(), 86_400);
+        assert!(parse_duration("5 fortnights").is_err());
+    }
+
+    #[test]
+    fn parses_the_mainnet_rate() {
+        let spec = parse_rate("0.10 USDC per 1M tokens per 5 min", 6, 6).unwrap();
+        assert_eq!(
+            spec,
+            RateSpec {
+                reward_amount: 100_000,
+                per_staked_amount: 1_000_000_000_000,
+                period_seconds: 300,
+            }
+        );
+        // The scaled rate rounds down, losing at most one base unit per period
+        let rate = spec.reward_rate().unwrap();
+        assert_eq!(rate.rewards_for(1_000_000_000_000, 300), Some(99_999));
+
+        assert!(parse_rate("0.10 USDC per 5 min", 6, 6).is_err());
+        assert!(parse_rate("1 per 0 per 1s", 6, 6).is_err());
+    }
+
+    #[test]
+    fn formats_amounts() {
+        assert_eq!(format_amount(100_000, 6), "0.1");
+        assert_eq!(format_amount(30_000_000, 6), "30");
+        assert_eq!(format_amount(1_234_500, 3), "1234.5");
+        assert_eq!(format_amount(7, 0), "7");
+    }
+}
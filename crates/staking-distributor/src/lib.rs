@@ -0,0 +1,244 @@
+//! Merkle distributor epochs built from CSV claim lists
+//!
+//! A [`Distribution`] holds everything needed to post an epoch (root, total
+//! and leaf count) and every claimant's proof. It is saved as JSON for the
+//! admin posting the epoch and for the app serving proofs to claimants.
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufReader, Read, Write};
+use std::path::Path;
+
+use anchor_lang::prelude::Pubkey;
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use memecoin_staking_client::merkle::MerkleTree;
+use memecoin_staking_client::program::MAX_DISTRIBUTOR_LEAVES;
+use serde::{Deserialize, Serialize};
+
+/// Pubkeys as base58 strings
+mod base58 {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(key: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(key)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pubkey, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Hashes as hex strings
+mod hex32 {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(hash: &[u8; 32], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(hash))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 32], D::Error> {
+        let mut hash = [0; 32];
+        hex::decode_to_slice(String::deserialize(deserializer)?, &mut hash)
+            .map_err(serde::de::Error::custom)?;
+        Ok(hash)
+    }
+
+    pub mod vec {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            hashes: &[[u8; 32]],
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(hashes.iter().map(hex::encode))
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Vec<[u8; 32]>, D::Error> {
+            Vec::<String>::deserialize(deserializer)?
+                .into_iter()
+                .map(|encoded| {
+                    let mut hash = [0; 32];
+                    hex::decode_to_slice(encoded, &mut hash).map(|()| hash)
+                })
+                .collect::<Result<_, _>>()
+                .map_err(serde::de::Error::custom)
+        }
+    }
+}
+
+/// One claimant's leaf and its proof
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Claim {
+    pub index: u32,
+    #[serde(with = "base58")]
+    pub claimant: Pubkey,
+    pub amount: u64,
+    #[serde(with = "hex32::vec")]
+    pub proof: Vec<[u8; 32]>,
+}
+
+/// A distributor epoch's tree, ready to post and claim from
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Distribution {
+    #[serde(with = "hex32")]
+    pub merkle_root: [u8; 32],
+    pub total_amount: u64,
+    pub num_leaves: u32,
+    /// In leaf index order
+    pub claims: Vec<Claim>,
+}
+
+#[derive(Deserialize)]
+struct CsvRow {
+    claimant: String,
+    amount: u64,
+}
+
+impl Distribution {
+    /// Tree of `(claimant, amount)` claims, indexed in the given order
+    pub fn new(claims: &[(Pubkey, u64)]) -> Result<Self> {
+        ensure!(!claims.is_empty(), "no claims to distribute");
+        ensure!(
+            claims.len() <= MAX_DISTRIBUTOR_LEAVES as usize,
+            "{} claims exceed the {MAX_DISTRIBUTOR_LEAVES} an epoch can hold",
+            claims.len()
+        );
+
+        let mut seen = HashSet::new();
+        let mut total_amount: u64 = 0;
+        for (claimant, amount) in claims {
+            ensure!(
+                seen.insert(*claimant),
+                "{claimant} is listed more than once"
+            );
+            ensure!(*amount > 0, "{claimant} has a zero amount");
+            total_amount = total_amount
+                .checked_add(*amount)
+                .ok_or_else(|| anyhow!("the claims' total overflows a u64"))?;
+        }
+
+        let tree = MerkleTree::new(claims);
+        Ok(Self {
+            merkle_root: tree.root(),
+            total_amount,
+            num_leaves: tree.num_leaves(),
+            claims: claims
+                .iter()
+                .enumerate()
+                .map(|(index, &(claimant, amount))| Claim {
+                    index: index as u32,
+                    claimant,
+                    amount,
+                    proof: tree.proof(index),
+                })
+                .collect(),
+        })
+    }
+
+    /// Tree of a CSV with `claimant,amount` columns (amounts in base units)
+    pub fn from_csv(reader: impl Read) -> Result<Self> {
+        let claims = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(reader)
+            .deserialize()
+            .enumerate()
+            .map(|(row, record)| {
+                // Line 1 is the header
+                let line = row + 2;
+                let record: CsvRow = record.with_context(|| format!("line {line}"))?;
+                let claimant = record
+                    .claimant
+                    .parse()
+                    .with_context(|| format!("line {line}: invalid claimant"))?;
+                Ok((claimant, record.amount))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Self::new(&claims)
+    }
+
+    /// Read a distribution saved by [`Distribution::write_json`], rebuilding its tree to check it
+    pub fn read(path: &Path) -> Result<Self> {
+        let file =
+            File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+        let distribution: Self = serde_json::from_reader(BufReader::new(file))
+            .with_context(|| format!("{} is not a distribution", path.display()))?;
+
+        let claims: Vec<_> = distribution
+            .claims
+            .iter()
+            .map(|claim| (claim.claimant, claim.amount))
+            .collect();
+        if Self::new(&claims)? != distribution {
+            bail!("{} does not match the tree of its claims", path.display());
+        }
+        Ok(distribution)
+    }
+
+    pub fn write_json(&self, writer: impl Write) -> Result<()> {
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+
+    /// `claimant`'s leaf, if they have one
+    pub fn claim(&self, claimant: &Pubkey) -> Option<&Claim> {
+        self.claims.iter().find(|claim| claim.claimant == *claimant)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use memecoin_staking_client::program::{distributor_leaf, verify_distributor_proof};
+
+    #[test]
+    fn builds_verifiable_proofs_from_csv() {
+        let claimants: Vec<_> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let csv = format!(
+            "claimant,amount\n{},100\n {} , 250\n{},1\n",
+            claimants[0], claimants[1], claimants[2]
+        );
+        let distribution = Distribution::from_csv(csv.as_bytes()).unwrap();
+        assert_eq!(distribution.total_amount, 351);
+        assert_eq!(distribution.num_leaves, 3);
+
+        let claim = distribution.claim(&claimants[1]).unwrap();
+        assert_eq!((claim.index, claim.amount), (1, 250));
+        let leaf = distributor_leaf(claim.index, &claim.claimant, claim.amount);
+        assert!(verify_distributor_proof(
+            &claim.proof,
+            &distribution.merkle_root,
+            leaf
+        ));
+    }
+
+    #[test]
+    fn rejects_duplicate_and_zero_claims() {
+        let claimant = Pubkey::new_unique();
+        assert!(Distribution::new(&[(claimant, 1), (claimant, 2)]).is_err());
+        assert!(Distribution::new(&[(claimant, 0)]).is_err());
+        assert!(Distribution::new(&[]).is_err());
+        assert!(Distribution::new(&[(claimant, u64::MAX), (Pubkey::new_unique(), 1)]).is_err());
+
+        let err = Distribution::from_csv("claimant,amount\nnot-a-key,5\n".as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+    }
+
+    #[test]
+    fn json_round_trips() {
+        let distribution =
+            Distribution::new(&[(Pubkey::new_unique(), 7), (Pubkey::new_unique(), 9)]).unwrap();
+        let mut json = Vec::new();
+        distribution.write_json(&mut json).unwrap();
+
+        let parsed: Distribution = serde_json::from_slice(&json).unwrap();
+        assert_eq!(parsed, distribution);
+        let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(value["merkle_root"], hex::encode(distribution.merkle_root));
+    }
+}
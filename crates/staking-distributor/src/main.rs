@@ -0,0 +1,87 @@
+//! Build Merkle distributor epochs and look up claimants' proofs
+//!
+//! ```text
+//! staking-distributor build rewards.csv --output epoch.json
+//! staking-distributor proof epoch.json <CLAIMANT>
+//! ```
+//!
+//! The CSV has `claimant,amount` columns with amounts in reward token base
+//! units. Post the resulting file with `staking-cli post-distribution`.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+use anchor_lang::prelude::Pubkey;
+use anyhow::{anyhow, Context, Result};
+use clap::{Parser, Subcommand};
+use staking_distributor::Distribution;
+
+#[derive(Parser)]
+#[command(
+    name = "staking-distributor",
+    version,
+    about = "Build memecoin staking Merkle distributor epochs"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Build the tree of a `claimant,amount` CSV and write it with every proof as JSON
+    Build {
+        csv: PathBuf,
+        /// File to write [default: stdout]
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Print a claimant's leaf and proof from a built distribution
+    Proof {
+        distribution: PathBuf,
+        claimant: Pubkey,
+    },
+}
+
+fn main() -> Result<()> {
+    match Cli::parse().command {
+        Command::Build { csv, output } => {
+            let file =
+                File::open(&csv).with_context(|| format!("failed to open {}", csv.display()))?;
+            let distribution = Distribution::from_csv(file)?;
+            eprintln!(
+                "{} claims totalling {}, root {}",
+                distribution.num_leaves,
+                distribution.total_amount,
+                hex::encode(distribution.merkle_root)
+            );
+
+            let mut writer: Box<dyn Write> = match &output {
+                Some(path) => {
+                    Box::new(BufWriter::new(File::create(path).with_context(|| {
+                        format!("failed to create {}", path.display())
+                    })?))
+                }
+                None => Box::new(io::stdout().lock()),
+            };
+            distribution.write_json(&mut writer)?;
+            writer.flush()?;
+        }
+        Command::Proof {
+            distribution,
+            claimant,
+        } => {
+            let distribution = Distribution::read(&distribution)?;
+            let claim = distribution
+                .claim(&claimant)
+                .ok_or_else(|| anyhow!("{claimant} has no claim in this distribution"))?;
+            println!("Index: {}", claim.index);
+            println!("Amount: {}", claim.amount);
+            for node in &claim.proof {
+                println!("Proof: {}", hex::encode(node));
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,406 @@
+//! SQLite storage of indexed transactions and their events
+//!
+//! Events are keyed by `(signature, instruction_index, event_index)` and
+//! transactions by signature, so ingesting a transaction again is a no-op.
+//!
+//! The schema version lives in SQLite's `user_version`. Opening an older
+//! database adds the event columns introduced since, filled with the value
+//! they had before the program logged them.
+
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+
+use crate::events::{Table, TABLES};
+use crate::logs::LoggedEvent;
+
+/// Schema version this build creates and migrates to
+const SCHEMA_VERSION: i64 = 2;
+
+/// `(schema version, table, column, value of older rows)` of event columns added
+/// after their table was first created
+const ADDED_COLUMNS: &[(i64, &str, &str, &str)] = &[
+    (1, "stakes", "fee", "0"),
+    (1, "stakes_for", "fee", "0"),
+    (1, "unstakes", "fee", "0"),
+    (1, "claims", "fee", "0"),
+    (1, "crank_claims", "total_fee", "0"),
+    (2, "liquid_stakes", "fee", "0"),
+    (2, "liquid_unstakes", "fee", "0"),
+];
+
+/// A transaction's logs and where it landed
+#[derive(Clone, Debug)]
+pub struct IndexedTransaction {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    /// Failed transactions are recorded but their events are discarded
+    pub failed: bool,
+    pub logs: Vec<String>,
+}
+
+pub struct Database {
+    conn: Connection,
+}
+
+impl Database {
+    pub fn open(path: &Path) -> Result<Self> {
+        Self::init(Connection::open(path)?)
+    }
+
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(mut conn: Connection) -> Result<Self> {
+        let db = conn.transaction()?;
+        let version: i64 = db.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version > SCHEMA_VERSION {
+            bail!("database schema version {version} is newer than this indexer's");
+        }
+
+        db.execute_batch(
+            "CREATE TABLE IF NOT EXISTS transactions (
+                signature TEXT PRIMARY KEY,
+                slot INTEGER NOT NULL,
+                block_time INTEGER,
+                failed INTEGER NOT NULL
+            );",
+        )?;
+        for table in TABLES {
+            let columns: String = table
+                .columns
+                .iter()
+                .map(|(name, sql_type)| format!("\"{name}\" {sql_type} NOT NULL, "))
+                .collect();
+            db.execute_batch(&format!(
+                "CREATE TABLE IF NOT EXISTS {name} (
+                    signature TEXT NOT NULL REFERENCES transactions (signature),
+                    instruction_index INTEGER NOT NULL,
+                    event_index INTEGER NOT NULL,
+                    slot INTEGER NOT NULL,
+                    block_time INTEGER,
+                    {columns}
+                    PRIMARY KEY (signature, instruction_index, event_index)
+                );
+                CREATE INDEX IF NOT EXISTS {name}_slot ON {name} (slot);",
+                name = table.name,
+            ))?;
+        }
+        migrate(&db, version)?;
+        db.execute_batch(&format!("PRAGMA user_version = {SCHEMA_VERSION}"))?;
+        db.commit()?;
+
+        Ok(Self { conn })
+    }
+
+    /// Store a transaction and its decoded events, returning how many events were new
+    pub fn ingest(
+        &mut self,
+        transaction: &IndexedTransaction,
+        events: &[LoggedEvent],
+    ) -> Result<usize> {
+        let db = self.conn.transaction()?;
+        db.execute(
+            "INSERT OR IGNORE INTO transactions (signature, slot, block_time, failed)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                transaction.signature,
+                transaction.slot,
+                transaction.block_time,
+                transaction.failed
+            ],
+        )?;
+
+        let mut inserted = 0;
+        if !transaction.failed {
+            for logged in events {
+                let sql = insert_sql(logged.event.layout());
+                let location = [
+                    transaction.signature.clone().into(),
+                    i64::from(logged.instruction_index).into(),
+                    i64::from(logged.event_index).into(),
+                    i64::try_from(transaction.slot)?.into(),
+                    transaction.block_time.into(),
+                ];
+                let values = logged.event.values()?;
+                inserted += db
+                    .prepare_cached(&sql)?
+                    .execute(params_from_iter(location.into_iter().chain(values)))?;
+            }
+        }
+
+        db.commit()?;
+        Ok(inserted)
+    }
+
+    /// Signature of the most recent transaction ingested
+    pub fn latest_signature(&self) -> Result<Option<String>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT signature FROM transactions ORDER BY slot DESC LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    #[cfg(test)]
+    fn columns(&self, table: &str) -> Vec<String> {
+        let mut statement = self
+            .conn
+            .prepare(&format!("SELECT name FROM pragma_table_info('{table}')"))
+            .unwrap();
+        let names = statement.query_map([], |row| row.get(0)).unwrap();
+        names.map(Result::unwrap).collect()
+    }
+
+    #[cfg(test)]
+    fn count(&self, table: &str) -> i64 {
+        self.conn
+            .query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+    }
+}
+
+/// Add the columns introduced after schema `version`
+///
+/// Tables created by this build already have them, as do some created before
+/// schema versions were recorded, so only missing columns are added.
+fn migrate(db: &Connection, version: i64) -> Result<()> {
+    for &(added_in, table, column, default) in ADDED_COLUMNS {
+        if added_in <= version || has_column(db, table, column)? {
+            continue;
+        }
+        let sql_type = TABLES
+            .iter()
+            .find(|layout| layout.name == table)
+            .and_then(|layout| layout.sql_type(column))
+            .with_context(|| format!("no column {table}.{column}"))?;
+        db.execute_batch(&format!(
+            "ALTER TABLE {table} ADD COLUMN \"{column}\" {sql_type} NOT NULL DEFAULT {default}"
+        ))?;
+    }
+    Ok(())
+}
+
+fn has_column(db: &Connection, table: &str, column: &str) -> Result<bool> {
+    Ok(db.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info(?1) WHERE name = ?2",
+        params![table, column],
+        |row| row.get(0),
+    )?)
+}
+
+/// Insert statement naming every column, so it holds whatever order migrations left them in
+fn insert_sql(table: &Table) -> String {
+    let columns: Vec<_> = [
+        "signature",
+        "instruction_index",
+        "event_index",
+        "slot",
+        "block_time",
+    ]
+    .into_iter()
+    .map(str::to_string)
+    .chain(table.columns.iter().map(|(name, _)| format!("\"{name}\"")))
+    .collect();
+    let placeholders = vec!["?"; columns.len()].join(", ");
+    format!(
+        "INSERT OR IGNORE INTO {} ({}) VALUES ({placeholders})",
+        table.name,
+        columns.join(", ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::Event;
+    use anchor_lang::prelude::Pubkey;
+    use memecoin_staking_client::program::{ClaimEvent, RewardsFunded};
+
+    fn transaction(signature: &str, failed: bool) -> IndexedTransaction {
+        IndexedTransaction {
+            signature: signature.to_string(),
+            slot: 42,
+            block_time: Some(1_700_000_000),
+            failed,
+            logs: Vec::new(),
+        }
+    }
+
+    fn events() -> Vec<LoggedEvent> {
+        let pool = Pubkey::new_unique();
+        vec![
+            LoggedEvent {
+                instruction_index: 0,
+                event_index: 0,
+                event: Event::RewardsFunded(RewardsFunded {
+                    pool,
+                    funder: Pubkey::new_unique(),
+                    amount: 1_000_000,
+                    timestamp: 1_700_000_000,
+                }),
+            },
+            LoggedEvent {
+                instruction_index: 1,
+                event_index: 0,
+                event: Event::ClaimEvent(ClaimEvent {
+                    user: Pubkey::new_unique(),
+                    pool,
+                    amount: 5,
+                    timestamp: 1_700_000_000,
+                    fee: 0,
+                }),
+            },
+        ]
+    }
+
+    #[test]
+    fn reingesting_is_idempotent() {
+        let mut db = Database::open_in_memory().unwrap();
+        let events = events();
+
+        assert_eq!(db.ingest(&transaction("a", false), &events).unwrap(), 2);
+        assert_eq!(db.ingest(&transaction("a", false), &events).unwrap(), 0);
+        assert_eq!(db.count("transactions"), 1);
+        assert_eq!(db.count("rewards_funded"), 1);
+        assert_eq!(db.count("claims"), 1);
+        assert_eq!(db.latest_signature().unwrap().as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn unrepresentable_events_roll_back_their_transaction() {
+        let mut db = Database::open_in_memory().unwrap();
+        let mut events = events();
+        if let Event::ClaimEvent(claim) = &mut events[1].event {
+            claim.amount = u64::MAX;
+        }
+
+        assert!(db.ingest(&transaction("c", false), &events).is_err());
+        assert_eq!(db.count("transactions"), 0);
+        assert_eq!(db.count("rewards_funded"), 0);
+    }
+
+    #[test]
+    fn failed_transactions_record_no_events() {
+        let mut db = Database::open_in_memory().unwrap();
+        assert_eq!(db.ingest(&transaction("b", true), &events()).unwrap(), 0);
+        assert_eq!(db.count("transactions"), 1);
+        assert_eq!(db.count("claims"), 0);
+    }
+
+    const TRANSACTIONS: &str = "CREATE TABLE transactions (
+        signature TEXT PRIMARY KEY,
+        slot INTEGER NOT NULL,
+        block_time INTEGER,
+        failed INTEGER NOT NULL
+    );";
+
+    fn claims_table(fields: &str) -> String {
+        format!(
+            "{TRANSACTIONS}
+            CREATE TABLE claims (
+                signature TEXT NOT NULL REFERENCES transactions (signature),
+                instruction_index INTEGER NOT NULL,
+                event_index INTEGER NOT NULL,
+                slot INTEGER NOT NULL,
+                block_time INTEGER,
+                {fields},
+                PRIMARY KEY (signature, instruction_index, event_index)
+            );"
+        )
+    }
+
+    #[test]
+    fn migrates_tables_from_before_fees() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(&claims_table(
+            "\"user\" TEXT NOT NULL, \"pool\" TEXT NOT NULL, \"amount\" INTEGER NOT NULL, \
+             \"timestamp\" INTEGER NOT NULL",
+        ))
+        .unwrap();
+        conn.execute_batch(
+            "INSERT INTO transactions VALUES ('old', 1, NULL, 0);
+             INSERT INTO claims VALUES ('old', 0, 0, 1, NULL, 'u', 'p', 7, 1);",
+        )
+        .unwrap();
+
+        let mut db = Database::init(conn).unwrap();
+        assert_eq!(
+            db.columns("claims")[5..],
+            ["user", "pool", "amount", "timestamp", "fee"]
+        );
+        let fee: i64 = db
+            .conn
+            .query_row(
+                "SELECT fee FROM claims WHERE signature = 'old'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(fee, 0);
+        assert_eq!(db.ingest(&transaction("a", false), &events()).unwrap(), 2);
+        assert_eq!(db.count("claims"), 2);
+    }
+
+    #[test]
+    fn inserts_by_name_into_unversioned_tables_with_fees_in_between() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(&claims_table(
+            "\"user\" TEXT NOT NULL, \"pool\" TEXT NOT NULL, \"amount\" INTEGER NOT NULL, \
+             \"fee\" INTEGER NOT NULL, \"timestamp\" INTEGER NOT NULL",
+        ))
+        .unwrap();
+
+        let mut db = Database::init(conn).unwrap();
+        assert_eq!(db.ingest(&transaction("a", false), &events()).unwrap(), 2);
+        let (amount, timestamp): (i64, i64) = db
+            .conn
+            .query_row("SELECT amount, timestamp FROM claims", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!((amount, timestamp), (5, 1_700_000_000));
+    }
+
+    #[test]
+    fn migrates_liquid_tables_from_schema_version_one() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(&format!(
+            "{TRANSACTIONS}
+            CREATE TABLE liquid_stakes (
+                signature TEXT NOT NULL REFERENCES transactions (signature),
+                instruction_index INTEGER NOT NULL,
+                event_index INTEGER NOT NULL,
+                slot INTEGER NOT NULL,
+                block_time INTEGER,
+                \"user\" TEXT NOT NULL, \"pool\" TEXT NOT NULL, \"amount\" INTEGER NOT NULL,
+                \"receipts\" INTEGER NOT NULL, \"total_underlying\" INTEGER NOT NULL,
+                \"receipt_supply\" INTEGER NOT NULL, \"timestamp\" INTEGER NOT NULL,
+                PRIMARY KEY (signature, instruction_index, event_index)
+            );
+            PRAGMA user_version = 1;"
+        ))
+        .unwrap();
+
+        let db = Database::init(conn).unwrap();
+        assert_eq!(db.columns("liquid_stakes").last().unwrap(), "fee");
+        assert_eq!(db.columns("liquid_unstakes").last().unwrap(), "fee");
+    }
+
+    #[test]
+    fn refuses_newer_schema_versions() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(&format!("PRAGMA user_version = {}", SCHEMA_VERSION + 1))
+            .unwrap();
+        assert!(Database::init(conn).is_err());
+    }
+}
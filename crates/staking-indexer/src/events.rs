@@ -0,0 +1,454 @@
+//! Decoding of every program event and its SQLite table layout
+//!
+//! Each event type gets its own table, with one column per event field after
+//! the columns locating the event in its transaction.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use anyhow::{Context, Result};
+use memecoin_staking_client::program;
+use memecoin_staking_client::{RewardRate, TierTable};
+use rusqlite::types::Value;
+
+/// A Rust field type as an SQLite column
+pub trait Column {
+    const SQL_TYPE: &'static str;
+
+    fn value(&self) -> Result<Value>;
+}
+
+impl Column for Pubkey {
+    const SQL_TYPE: &'static str = "TEXT";
+
+    fn value(&self) -> Result<Value> {
+        Ok(Value::Text(self.to_string()))
+    }
+}
+
+/// SQLite integers are signed, so amounts beyond `i64::MAX` are rejected
+impl Column for u64 {
+    const SQL_TYPE: &'static str = "INTEGER";
+
+    fn value(&self) -> Result<Value> {
+        let value = i64::try_from(*self).context("amount does not fit an SQLite integer")?;
+        Ok(Value::Integer(value))
+    }
+}
+
+impl Column for i64 {
+    const SQL_TYPE: &'static str = "INTEGER";
+
+    fn value(&self) -> Result<Value> {
+        Ok(Value::Integer(*self))
+    }
+}
+
+macro_rules! small_integer_column {
+    ($($ty:ty),*) => {$(
+        impl Column for $ty {
+            const SQL_TYPE: &'static str = "INTEGER";
+
+            fn value(&self) -> Result<Value> {
+                Ok(Value::Integer(i64::from(*self)))
+            }
+        }
+    )*};
+}
+
+small_integer_column!(u8, u16, u32, bool);
+
+/// Scaled rates exceed SQLite integers, so they are stored as decimal text
+impl Column for RewardRate {
+    const SQL_TYPE: &'static str = "TEXT";
+
+    fn value(&self) -> Result<Value> {
+        Ok(Value::Text(self.0.to_string()))
+    }
+}
+
+/// Merkle roots are stored as hex text
+impl Column for [u8; 32] {
+    const SQL_TYPE: &'static str = "TEXT";
+
+    fn value(&self) -> Result<Value> {
+        Ok(Value::Text(
+            self.iter().map(|byte| format!("{byte:02x}")).collect(),
+        ))
+    }
+}
+
+/// Tier tables are stored as `min_stake:multiplier_bps` pairs, comma separated
+impl Column for TierTable {
+    const SQL_TYPE: &'static str = "TEXT";
+
+    fn value(&self) -> Result<Value> {
+        let tiers: Vec<_> = self
+            .active()
+            .iter()
+            .map(|tier| format!("{}:{}", tier.min_stake, tier.multiplier_bps))
+            .collect();
+        Ok(Value::Text(tiers.join(",")))
+    }
+}
+
+/// Table layout of one event type
+pub struct Table {
+    pub name: &'static str,
+    /// `(name, SQL type)` of each event field
+    pub columns: &'static [(&'static str, &'static str)],
+}
+
+impl Table {
+    /// SQL type of the column for field `name`
+    pub fn sql_type(&self, name: &str) -> Option<&'static str> {
+        self.columns
+            .iter()
+            .find(|(column, _)| *column == name)
+            .map(|(_, sql_type)| *sql_type)
+    }
+}
+
+macro_rules! events {
+    ($($event:ident => $table:literal { $($field:ident: $ty:ty),* $(,)? })*) => {
+        /// A decoded program event
+        pub enum Event {
+            $($event(program::$event),)*
+        }
+
+        /// Tables of every event type
+        pub const TABLES: &[Table] = &[
+            $(Table {
+                name: $table,
+                columns: &[$((stringify!($field), <$ty as Column>::SQL_TYPE)),*],
+            },)*
+        ];
+
+        impl Event {
+            /// Decode a whole `emit!` payload, or `None` if it is not a program event
+            pub fn decode(data: &[u8]) -> Option<std::io::Result<Self>> {
+                $(
+                    if let Some(fields) =
+                        data.strip_prefix(<program::$event as Discriminator>::DISCRIMINATOR)
+                    {
+                        return Some(program::$event::try_from_slice(fields).map(Event::$event));
+                    }
+                )*
+                None
+            }
+
+            /// Layout of the table this event is stored in
+            pub fn layout(&self) -> &'static Table {
+                match self {
+                    $(Event::$event(_) => &Table {
+                        name: $table,
+                        columns: &[$((stringify!($field), <$ty as Column>::SQL_TYPE)),*],
+                    },)*
+                }
+            }
+
+            /// Field values in the order of the table's columns
+            pub fn values(&self) -> Result<Vec<Value>> {
+                match self {
+                    $(Event::$event(event) => Ok(vec![
+                        $(<$ty as Column>::value(&event.$field)
+                            .with_context(|| stringify!($event.$field))?),*
+                    ]),)*
+                }
+            }
+        }
+    };
+}
+
+events! {
+    PoolInitialized => "pool_initialized" {
+        pool: Pubkey,
+        staking_mint: Pubkey,
+        reward_mint: Pubkey,
+        reward_rate: RewardRate,
+        lock_period: i64,
+        min_stake_amount: u64,
+        timestamp: i64,
+    }
+    StakeEvent => "stakes" {
+        user: Pubkey,
+        pool: Pubkey,
+        amount: u64,
+        total_staked: u64,
+        timestamp: i64,
+        fee: u64,
+    }
+    StakeForEvent => "stakes_for" {
+        funder: Pubkey,
+        beneficiary: Pubkey,
+        pool: Pubkey,
+        amount: u64,
+        total_staked: u64,
+        timestamp: i64,
+        fee: u64,
+    }
+    UnstakeEvent => "unstakes" {
+        user: Pubkey,
+        pool: Pubkey,
+        amount: u64,
+        remaining_stake: u64,
+        timestamp: i64,
+        fee: u64,
+    }
+    ClaimEvent => "claims" {
+        user: Pubkey,
+        pool: Pubkey,
+        amount: u64,
+        timestamp: i64,
+        fee: u64,
+    }
+    CrankClaimed => "crank_claims" {
+        cranker: Pubkey,
+        pool: Pubkey,
+        claimed_count: u32,
+        total_claimed: u64,
+        total_tip: u64,
+        timestamp: i64,
+        total_fee: u64,
+    }
+    CrankTipUpdated => "crank_tip_updates" {
+        pool: Pubkey,
+        old_tip_bps: u16,
+        new_tip_bps: u16,
+        timestamp: i64,
+    }
+    ClaimDelegateSet => "claim_delegates" {
+        user: Pubkey,
+        pool: Pubkey,
+        delegate: Pubkey,
+        timestamp: i64,
+    }
+    RewardDestinationSet => "reward_destinations" {
+        user: Pubkey,
+        pool: Pubkey,
+        destination: Pubkey,
+        timestamp: i64,
+    }
+    StakeTransferred => "stake_transfers" {
+        pool: Pubkey,
+        from: Pubkey,
+        to: Pubkey,
+        amount: u64,
+        pending_rewards: u64,
+        timestamp: i64,
+    }
+    StakeTokenized => "stakes_tokenized" {
+        pool: Pubkey,
+        owner: Pubkey,
+        position_mint: Pubkey,
+        amount: u64,
+        timestamp: i64,
+    }
+    PositionRedeemed => "positions_redeemed" {
+        pool: Pubkey,
+        owner: Pubkey,
+        position_mint: Pubkey,
+        amount: u64,
+        timestamp: i64,
+    }
+    LiquidStakingInitialized => "liquid_staking_initialized" {
+        pool: Pubkey,
+        receipt_mint: Pubkey,
+        timestamp: i64,
+    }
+    LiquidStakeEvent => "liquid_stakes" {
+        user: Pubkey,
+        pool: Pubkey,
+        amount: u64,
+        receipts: u64,
+        total_underlying: u64,
+        receipt_supply: u64,
+        timestamp: i64,
+        fee: u64,
+    }
+    LiquidUnstakeEvent => "liquid_unstakes" {
+        user: Pubkey,
+        pool: Pubkey,
+        amount: u64,
+        receipts: u64,
+        total_underlying: u64,
+        receipt_supply: u64,
+        timestamp: i64,
+        fee: u64,
+    }
+    LiquidCompounded => "liquid_compounds" {
+        pool: Pubkey,
+        amount: u64,
+        total_underlying: u64,
+        timestamp: i64,
+    }
+    UserStakeClosed => "user_stakes_closed" {
+        user: Pubkey,
+        pool: Pubkey,
+        total_rewards_claimed: u64,
+        timestamp: i64,
+    }
+    RewardRateUpdated => "reward_rate_updates" {
+        pool: Pubkey,
+        old_rate: RewardRate,
+        new_rate: RewardRate,
+        timestamp: i64,
+    }
+    RateOracleSet => "rate_oracle_updates" {
+        pool: Pubkey,
+        price_account: Pubkey,
+        target_apr_bps: u32,
+        max_staleness: i64,
+        max_confidence_bps: u16,
+        max_rate_change_bps: u16,
+        timestamp: i64,
+    }
+    VoterWeightConfigSet => "voter_weight_config_updates" {
+        pool: Pubkey,
+        realm: Pubkey,
+        governing_token_mint: Pubkey,
+        max_lock_boost_bps: u16,
+        lock_boost_saturation: i64,
+        timestamp: i64,
+    }
+    PoolPausedEvent => "pause_updates" {
+        pool: Pubkey,
+        pause_flags: u8,
+        timestamp: i64,
+    }
+    EmergencyModeSet => "emergency_mode_updates" {
+        pool: Pubkey,
+        enabled: bool,
+        timestamp: i64,
+    }
+    EmergencyWithdrawEvent => "emergency_withdrawals" {
+        user: Pubkey,
+        pool: Pubkey,
+        amount: u64,
+        forfeited_rewards: u64,
+        timestamp: i64,
+    }
+    TransfersDisabledSet => "transfers_disabled_updates" {
+        pool: Pubkey,
+        disabled: bool,
+        timestamp: i64,
+    }
+    RewardsFunded => "rewards_funded" {
+        pool: Pubkey,
+        funder: Pubkey,
+        amount: u64,
+        timestamp: i64,
+    }
+    ReferralBpsUpdated => "referral_bps_updates" {
+        pool: Pubkey,
+        old_bps: u16,
+        new_bps: u16,
+        timestamp: i64,
+    }
+    FeesSet => "fee_updates" {
+        pool: Pubkey,
+        deposit_bps: u16,
+        withdrawal_bps: u16,
+        performance_bps: u16,
+        staking_treasury: Pubkey,
+        reward_treasury: Pubkey,
+        timestamp: i64,
+    }
+    NftBoostSet => "nft_boost_updates" {
+        pool: Pubkey,
+        collection: Pubkey,
+        multiplier_bps: u16,
+        timestamp: i64,
+    }
+    TiersSet => "tier_updates" {
+        pool: Pubkey,
+        tiers: TierTable,
+        timestamp: i64,
+    }
+    TierChanged => "tier_changes" {
+        pool: Pubkey,
+        user: Pubkey,
+        old_tier: u8,
+        new_tier: u8,
+        staked_amount: u64,
+        timestamp: i64,
+    }
+    BoostNftAttached => "boost_nft_attachments" {
+        pool: Pubkey,
+        user: Pubkey,
+        nft_mint: Pubkey,
+        multiplier_bps: u16,
+        timestamp: i64,
+    }
+    BoostNftDetached => "boost_nft_detachments" {
+        pool: Pubkey,
+        user: Pubkey,
+        nft_mint: Pubkey,
+        timestamp: i64,
+    }
+    CampaignCreated => "campaigns" {
+        pool: Pubkey,
+        campaign: Pubkey,
+        funder: Pubkey,
+        reward_mint: Pubkey,
+        amount: u64,
+        start_time: i64,
+        end_time: i64,
+        timestamp: i64,
+    }
+    CampaignRewardsClaimed => "campaign_claims" {
+        pool: Pubkey,
+        campaign: Pubkey,
+        user: Pubkey,
+        amount: u64,
+        timestamp: i64,
+    }
+    CampaignLeftoversReclaimed => "campaign_reclaims" {
+        pool: Pubkey,
+        campaign: Pubkey,
+        funder: Pubkey,
+        amount: u64,
+        timestamp: i64,
+    }
+    CampaignClosed => "campaign_closures" {
+        pool: Pubkey,
+        campaign: Pubkey,
+        funder: Pubkey,
+        returned: u64,
+        timestamp: i64,
+    }
+    ReferralRegistered => "referrals" {
+        pool: Pubkey,
+        referrer: Pubkey,
+        referee: Pubkey,
+        timestamp: i64,
+    }
+    ReferralRewardsPaid => "referral_payouts" {
+        pool: Pubkey,
+        referrer: Pubkey,
+        referee_count: u32,
+        amount: u64,
+        timestamp: i64,
+    }
+    DistributorEpochPosted => "distributor_epochs_posted" {
+        pool: Pubkey,
+        epoch: u64,
+        merkle_root: [u8; 32],
+        total_amount: u64,
+        num_leaves: u32,
+        timestamp: i64,
+    }
+    DistributorClaimed => "distributor_claims" {
+        pool: Pubkey,
+        epoch: u64,
+        index: u32,
+        claimant: Pubkey,
+        amount: u64,
+        timestamp: i64,
+    }
+    DistributorEpochClosed => "distributor_epochs_closed" {
+        pool: Pubkey,
+        epoch: u64,
+        unclaimed: u64,
+        timestamp: i64,
+    }
+}
@@ -0,0 +1,347 @@
+//! Extraction of program events from transaction log messages
+//!
+//! Anchor's `emit!` logs `Program data: <base64>` while the program is the
+//! innermost running one. The runtime's `invoke [depth]`, `success` and
+//! `failed` lines track which program that is and which top-level
+//! instruction it runs under.
+//!
+//! Every event ends with its timestamp, or with the fields added to it since.
+//! The few emitted before they carried a timestamp decode once the block time
+//! is appended, and those emitted before fees once a zero fee is.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::Discriminator;
+use anyhow::{Context, Result};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use memecoin_staking_client::program::{
+    ClaimEvent, CrankClaimed, LiquidStakeEvent, LiquidUnstakeEvent, PoolPausedEvent,
+    RewardRateUpdated, RewardsFunded, StakeEvent, StakeForEvent, UnstakeEvent,
+};
+
+use crate::events::Event;
+
+/// Discriminator and field length of the events first emitted without a timestamp
+const UNTIMESTAMPED_EVENTS: [(&[u8], usize); 3] = [
+    (RewardsFunded::DISCRIMINATOR, 32 + 32 + 8),
+    (RewardRateUpdated::DISCRIMINATOR, 32 + 16 + 16),
+    (PoolPausedEvent::DISCRIMINATOR, 32 + 1),
+];
+
+/// Discriminator and field length of the events first emitted without their trailing fee
+const FEELESS_EVENTS: [(&[u8], usize); 7] = [
+    (StakeEvent::DISCRIMINATOR, 32 + 32 + 8 + 8 + 8),
+    (StakeForEvent::DISCRIMINATOR, 32 + 32 + 32 + 8 + 8 + 8),
+    (UnstakeEvent::DISCRIMINATOR, 32 + 32 + 8 + 8 + 8),
+    (ClaimEvent::DISCRIMINATOR, 32 + 32 + 8 + 8),
+    (CrankClaimed::DISCRIMINATOR, 32 + 32 + 4 + 8 + 8 + 8),
+    (LiquidStakeEvent::DISCRIMINATOR, 32 + 32 + 8 + 8 + 8 + 8 + 8),
+    (
+        LiquidUnstakeEvent::DISCRIMINATOR,
+        32 + 32 + 8 + 8 + 8 + 8 + 8,
+    ),
+];
+
+/// An event together with its position in the transaction
+pub struct LoggedEvent {
+    /// Index of the top-level instruction that emitted it
+    pub instruction_index: u32,
+    /// Index among the events of that instruction
+    pub event_index: u32,
+    pub event: Event,
+}
+
+/// Decode every event `program_id` logged in a transaction's `logs`
+pub fn events(
+    logs: &[String],
+    program_id: &Pubkey,
+    block_time: Option<i64>,
+) -> Result<Vec<LoggedEvent>> {
+    let program_id = program_id.to_string();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut instruction_index: Option<u32> = None;
+    let mut event_index = 0;
+    let mut events = Vec::new();
+
+    for line in logs {
+        if let Some((program, depth)) = invoked_program(line) {
+            if depth == "1" {
+                stack.clear();
+                instruction_index = Some(instruction_index.map_or(0, |index| index + 1));
+                event_index = 0;
+            }
+            stack.push(program);
+        } else if finished_program(line) {
+            stack.pop();
+        } else if let Some(payload) = line.strip_prefix("Program data: ") {
+            let (Some(&program), Some(instruction_index)) = (stack.last(), instruction_index)
+            else {
+                continue;
+            };
+            if program != program_id {
+                continue;
+            }
+
+            let data = BASE64_STANDARD
+                .decode(payload.trim())
+                .with_context(|| format!("invalid event payload {payload:?}"))?;
+            let event = match Event::decode(&data) {
+                None => continue,
+                Some(Ok(event)) => event,
+                Some(Err(err)) => block_time
+                    .and_then(|block_time| decode_untimestamped(&data, block_time))
+                    .or_else(|| decode_feeless(&data))
+                    .ok_or(err)
+                    .with_context(|| {
+                        format!("malformed event in instruction {instruction_index}")
+                    })?,
+            };
+            events.push(LoggedEvent {
+                instruction_index,
+                event_index,
+                event,
+            });
+            event_index += 1;
+        }
+    }
+    Ok(events)
+}
+
+/// Decode an event from before it carried a timestamp, stamping it with `block_time`
+///
+/// Only the events in `UNTIMESTAMPED_EVENTS` whose payload is exactly their old length qualify.
+fn decode_untimestamped(data: &[u8], block_time: i64) -> Option<Event> {
+    decode_extended(data, &UNTIMESTAMPED_EVENTS, block_time.to_le_bytes())
+}
+
+/// Decode an event from before it carried a fee, with a fee of zero
+///
+/// Only the events in `FEELESS_EVENTS` whose payload is exactly their old length qualify.
+fn decode_feeless(data: &[u8]) -> Option<Event> {
+    decode_extended(data, &FEELESS_EVENTS, 0u64.to_le_bytes())
+}
+
+/// Decode `data` with `field` appended if it is one of `legacy` at exactly its old length
+fn decode_extended(data: &[u8], legacy: &[(&[u8], usize)], field: [u8; 8]) -> Option<Event> {
+    let matches = legacy.iter().any(|&(discriminator, len)| {
+        data.strip_prefix(discriminator)
+            .is_some_and(|fields| fields.len() == len)
+    });
+    if !matches {
+        return None;
+    }
+
+    let mut extended = data.to_vec();
+    extended.extend_from_slice(&field);
+    Event::decode(&extended)?.ok()
+}
+
+/// `(program, depth)` of a "Program <id> invoke [<depth>]" line
+fn invoked_program(line: &str) -> Option<(&str, &str)> {
+    let rest = line.strip_prefix("Program ")?;
+    let (program, depth) = rest.split_once(" invoke [")?;
+    Some((program, depth.strip_suffix(']')?))
+}
+
+/// Whether `line` ends the innermost running program
+fn finished_program(line: &str) -> bool {
+    let Some(rest) = line.strip_prefix("Program ") else {
+        return false;
+    };
+    match rest.split_once(' ') {
+        Some((_, status)) => status == "success" || status.starts_with("failed"),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::system_program::ID as SYSTEM_PROGRAM_ID;
+    use anchor_lang::Event as _;
+    use memecoin_staking_client::{RewardRate, ID};
+
+    fn data_line(data: Vec<u8>) -> String {
+        format!("Program data: {}", BASE64_STANDARD.encode(data))
+    }
+
+    fn invoke(program: &Pubkey, depth: u8) -> String {
+        format!("Program {program} invoke [{depth}]")
+    }
+
+    fn success(program: &Pubkey) -> String {
+        format!("Program {program} success")
+    }
+
+    fn stake(amount: u64) -> StakeEvent {
+        StakeEvent {
+            user: Pubkey::new_unique(),
+            pool: Pubkey::new_unique(),
+            amount,
+            total_staked: amount,
+            timestamp: 1_700_000_000,
+            fee: 0,
+        }
+    }
+
+    #[test]
+    fn attributes_events_to_top_level_instructions() {
+        let other = Pubkey::new_unique();
+        let rate_update = RewardRateUpdated {
+            pool: Pubkey::new_unique(),
+            old_rate: RewardRate(1),
+            new_rate: RewardRate(2),
+            timestamp: 5,
+        };
+        let claim = ClaimEvent {
+            user: Pubkey::new_unique(),
+            pool: rate_update.pool,
+            amount: 9,
+            timestamp: 5,
+            fee: 0,
+        };
+        let logs = vec![
+            invoke(&other, 1),
+            success(&other),
+            invoke(&ID, 1),
+            "Program log: Instruction: Stake".to_string(),
+            invoke(&SYSTEM_PROGRAM_ID, 2),
+            // Another program logging data inside the CPI is not ours
+            data_line(stake(1).data()),
+            success(&SYSTEM_PROGRAM_ID),
+            data_line(stake(7).data()),
+            format!("Program {ID} consumed 100 of 200000 compute units"),
+            success(&ID),
+            invoke(&ID, 1),
+            data_line(rate_update.data()),
+            data_line(claim.data()),
+            success(&ID),
+        ];
+
+        let events = events(&logs, &ID, None).unwrap();
+        let positions: Vec<_> = events
+            .iter()
+            .map(|e| (e.instruction_index, e.event_index, e.event.layout().name))
+            .collect();
+        assert_eq!(
+            positions,
+            [
+                (1, 0, "stakes"),
+                (2, 0, "reward_rate_updates"),
+                (2, 1, "claims")
+            ]
+        );
+        let Event::StakeEvent(staked) = &events[0].event else {
+            panic!("expected a stake event");
+        };
+        assert_eq!(staked.amount, 7);
+    }
+
+    #[test]
+    fn skips_foreign_payloads_and_rejects_malformed_events() {
+        let foreign = vec![invoke(&ID, 1), data_line(vec![1, 2, 3]), success(&ID)];
+        assert!(events(&foreign, &ID, None).unwrap().is_empty());
+
+        let mut truncated = stake(1).data();
+        truncated.pop();
+        let malformed = vec![invoke(&ID, 1), data_line(truncated), success(&ID)];
+        assert!(events(&malformed, &ID, Some(5)).is_err());
+    }
+
+    fn without_last(data: Vec<u8>, bytes: usize) -> Vec<String> {
+        let legacy = data[..data.len() - bytes].to_vec();
+        vec![invoke(&ID, 1), data_line(legacy), success(&ID)]
+    }
+
+    fn without_timestamp(data: Vec<u8>) -> Vec<String> {
+        without_last(data, 8)
+    }
+
+    #[test]
+    fn stamps_untimestamped_events_with_the_block_time() {
+        let funded = RewardsFunded {
+            pool: Pubkey::new_unique(),
+            funder: Pubkey::new_unique(),
+            amount: 3,
+            timestamp: 0,
+        };
+        let logs = without_timestamp(funded.data());
+
+        assert!(events(&logs, &ID, None).is_err());
+        let decoded = events(&logs, &ID, Some(1_650_000_000)).unwrap();
+        let Event::RewardsFunded(funded) = &decoded[0].event else {
+            panic!("expected a rewards funded event");
+        };
+        assert_eq!((funded.amount, funded.timestamp), (3, 1_650_000_000));
+
+        let paused = PoolPausedEvent {
+            pool: Pubkey::new_unique(),
+            pause_flags: 1,
+            timestamp: 0,
+        };
+        assert!(events(&without_timestamp(paused.data()), &ID, Some(5)).is_ok());
+    }
+
+    #[test]
+    fn decodes_events_from_before_fees_with_a_zero_fee() {
+        let claim = ClaimEvent {
+            user: Pubkey::new_unique(),
+            pool: Pubkey::new_unique(),
+            amount: 4,
+            timestamp: 1_650_000_000,
+            fee: 9,
+        };
+        let decoded = events(&without_last(claim.data(), 8), &ID, None).unwrap();
+        let Event::ClaimEvent(legacy) = &decoded[0].event else {
+            panic!("expected a claim event");
+        };
+        assert_eq!(
+            (legacy.amount, legacy.timestamp, legacy.fee),
+            (4, 1_650_000_000, 0)
+        );
+
+        let crank = CrankClaimed {
+            cranker: Pubkey::new_unique(),
+            pool: Pubkey::new_unique(),
+            claimed_count: 2,
+            total_claimed: 10,
+            total_tip: 1,
+            timestamp: 5,
+            total_fee: 3,
+        };
+        assert!(events(&without_last(crank.data(), 8), &ID, None).is_ok());
+
+        let unstake = LiquidUnstakeEvent {
+            user: Pubkey::new_unique(),
+            pool: Pubkey::new_unique(),
+            amount: 6,
+            receipts: 5,
+            total_underlying: 60,
+            receipt_supply: 50,
+            timestamp: 7,
+            fee: 1,
+        };
+        let decoded = events(&without_last(unstake.data(), 8), &ID, None).unwrap();
+        let Event::LiquidUnstakeEvent(legacy) = &decoded[0].event else {
+            panic!("expected a liquid unstake event");
+        };
+        assert_eq!((legacy.amount, legacy.fee), (6, 0));
+    }
+
+    #[test]
+    fn only_stamps_events_that_once_lacked_a_timestamp() {
+        // Stake events always carried a timestamp, so one without it or its fee is malformed
+        assert!(events(&without_last(stake(3).data(), 16), &ID, Some(5)).is_err());
+
+        // As is a rate update short of anything but exactly its timestamp
+        let rate_update = RewardRateUpdated {
+            pool: Pubkey::new_unique(),
+            old_rate: RewardRate(1),
+            new_rate: RewardRate(2),
+            timestamp: 0,
+        };
+        let mut short = rate_update.data();
+        short.truncate(short.len() - 9);
+        let logs = vec![invoke(&ID, 1), data_line(short), success(&ID)];
+        assert!(events(&logs, &ID, Some(5)).is_err());
+    }
+}
@@ -0,0 +1,86 @@
+//! Indexes memecoin staking program events into SQLite
+//!
+//! ```text
+//! staking-indexer --db events.sqlite dump transactions.json
+//! staking-indexer --db events.sqlite rpc --url http://127.0.0.1:8899
+//! ```
+//!
+//! Each event type gets its own table (see `events.rs`). Ingestion is
+//! idempotent, so dumps can be replayed and RPC runs resume where the last
+//! one stopped.
+
+mod db;
+mod events;
+mod logs;
+mod source;
+
+use std::path::PathBuf;
+
+use anchor_lang::prelude::Pubkey;
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use memecoin_staking_client::ID;
+
+use crate::db::{Database, IndexedTransaction};
+
+#[derive(Parser)]
+#[command(
+    name = "staking-indexer",
+    version,
+    about = "Index memecoin staking events into SQLite"
+)]
+struct Cli {
+    /// SQLite database to write to (created if missing)
+    #[arg(long, default_value = "staking-events.sqlite")]
+    db: PathBuf,
+
+    #[command(subcommand)]
+    source: Source,
+}
+
+#[derive(Subcommand)]
+enum Source {
+    /// Ingest a JSON array of `getTransaction` results
+    Dump { path: PathBuf },
+    /// Ingest every transaction since the last run from an RPC node
+    Rpc {
+        /// RPC endpoint
+        #[arg(long, short, env = "RPC_URL", default_value = "http://127.0.0.1:8899")]
+        url: String,
+        /// Address whose transactions to index, e.g. a single pool [default: the program]
+        #[arg(long)]
+        address: Option<Pubkey>,
+    },
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let mut db = Database::open(&cli.db)?;
+
+    let transactions = match cli.source {
+        Source::Dump { path } => source::read_dump(&path)?,
+        Source::Rpc { url, address } => {
+            let until = db
+                .latest_signature()?
+                .map(|signature| signature.parse())
+                .transpose()?;
+            source::fetch_rpc(&url, &address.unwrap_or(ID), until)?
+        }
+    };
+
+    let mut inserted = 0;
+    for transaction in &transactions {
+        inserted += ingest(&mut db, transaction)?;
+    }
+    println!(
+        "Ingested {} transactions, {inserted} new events",
+        transactions.len()
+    );
+    Ok(())
+}
+
+fn ingest(db: &mut Database, transaction: &IndexedTransaction) -> Result<usize> {
+    let events = logs::events(&transaction.logs, &ID, transaction.block_time)
+        .map_err(|err| err.context(format!("transaction {}", transaction.signature)))?;
+    db.ingest(transaction, &events)
+}
@@ -0,0 +1,146 @@
+//! Transaction sources: `getTransaction` JSON dumps and a live RPC node
+
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use anchor_lang::prelude::Pubkey;
+use anyhow::{anyhow, Context, Result};
+use solana_rpc_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_rpc_client_api::config::RpcTransactionConfig;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::Signature;
+use solana_transaction_status_client_types::option_serializer::OptionSerializer;
+use solana_transaction_status_client_types::{
+    EncodedConfirmedTransactionWithStatusMeta, EncodedTransaction, UiTransactionEncoding,
+};
+
+use crate::db::IndexedTransaction;
+
+/// Signatures fetched per `getSignaturesForAddress` page
+const SIGNATURE_PAGE: usize = 1_000;
+
+/// Convert a `getTransaction` result
+fn indexed(transaction: EncodedConfirmedTransactionWithStatusMeta) -> Result<IndexedTransaction> {
+    let signature = match &transaction.transaction.transaction {
+        EncodedTransaction::Json(ui) => ui.signatures.first().cloned(),
+        encoded => encoded
+            .decode()
+            .and_then(|decoded| decoded.signatures.first().map(ToString::to_string)),
+    }
+    .ok_or_else(|| anyhow!("transaction in slot {} has no signature", transaction.slot))?;
+
+    let meta = transaction
+        .transaction
+        .meta
+        .ok_or_else(|| anyhow!("transaction {signature} has no status meta"))?;
+    let logs = match meta.log_messages {
+        OptionSerializer::Some(logs) => logs,
+        _ => Vec::new(),
+    };
+
+    Ok(IndexedTransaction {
+        signature,
+        slot: transaction.slot,
+        block_time: transaction.block_time,
+        failed: meta.err.is_some(),
+        logs,
+    })
+}
+
+/// Read a JSON array of `getTransaction` results in any encoding
+pub fn read_dump(path: &Path) -> Result<Vec<IndexedTransaction>> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let transactions: Vec<EncodedConfirmedTransactionWithStatusMeta> =
+        serde_json::from_reader(BufReader::new(file))
+            .with_context(|| format!("{} is not a getTransaction dump", path.display()))?;
+    transactions.into_iter().map(indexed).collect()
+}
+
+/// Fetch every transaction mentioning `address` after `until`, oldest first
+pub fn fetch_rpc(
+    url: &str,
+    address: &Pubkey,
+    until: Option<Signature>,
+) -> Result<Vec<IndexedTransaction>> {
+    let rpc = RpcClient::new_with_commitment(url.to_string(), CommitmentConfig::confirmed());
+
+    let mut signatures = Vec::new();
+    let mut before = None;
+    loop {
+        let config = GetConfirmedSignaturesForAddress2Config {
+            before,
+            until,
+            limit: Some(SIGNATURE_PAGE),
+            commitment: Some(CommitmentConfig::confirmed()),
+        };
+        let page = rpc.get_signatures_for_address_with_config(address, config)?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        before = Some(last.signature.parse()?);
+        let full_page = page.len() == SIGNATURE_PAGE;
+        signatures.extend(page.into_iter().map(|status| status.signature));
+        if !full_page {
+            break;
+        }
+    }
+
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+    signatures
+        .iter()
+        .rev()
+        .map(|signature| {
+            let transaction = rpc.get_transaction_with_config(&signature.parse()?, config)?;
+            indexed(transaction)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_json_encoded_transactions() {
+        let signature = Signature::new_unique().to_string();
+        let dump = serde_json::json!({
+            "slot": 7,
+            "blockTime": 1_700_000_000,
+            "transaction": {
+                "signatures": [signature],
+                "message": {
+                    "header": {
+                        "numRequiredSignatures": 1,
+                        "numReadonlySignedAccounts": 0,
+                        "numReadonlyUnsignedAccounts": 0
+                    },
+                    "accountKeys": [],
+                    "recentBlockhash": "11111111111111111111111111111111",
+                    "instructions": []
+                }
+            },
+            "meta": {
+                "err": { "InstructionError": [0, "InvalidArgument"] },
+                "status": { "Err": { "InstructionError": [0, "InvalidArgument"] } },
+                "fee": 5000,
+                "preBalances": [],
+                "postBalances": [],
+                "logMessages": ["Program log: hello"]
+            }
+        });
+
+        let transaction = indexed(serde_json::from_value(dump).unwrap()).unwrap();
+        assert_eq!(transaction.signature, signature);
+        assert_eq!(
+            (transaction.slot, transaction.block_time),
+            (7, Some(1_700_000_000))
+        );
+        assert!(transaction.failed);
+        assert_eq!(transaction.logs, ["Program log: hello"]);
+    }
+}
@@ -0,0 +1,363 @@
+//! Point-in-time export of every stake in a pool
+//!
+//! [`fetch`] reads a pool, its vault and all of its `UserStake` accounts and
+//! values them at a timestamp. [`Snapshot::check`] verifies the stakes add
+//! up to both `StakePool::total_staked` and the vault balance before the
+//! snapshot is exported as CSV or JSON.
+
+use std::io::Write;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountDeserialize, Space};
+use anchor_spl::token_interface::TokenAccount;
+use anyhow::{anyhow, ensure, Context, Result};
+use memecoin_staking_client::{math, pda, state, StakePool, UserStake, ID};
+use serde::{Serialize, Serializer};
+use solana_account_decoder_client_types::UiAccountEncoding;
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_rpc_client_api::config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_rpc_client_api::filter::{Memcmp, RpcFilterType};
+use solana_sdk::account::from_account;
+use solana_sdk::clock::Clock;
+use solana_sdk::sysvar;
+
+/// Byte offset of `UserStake::pool` (after the discriminator and owner)
+const USER_STAKE_POOL_OFFSET: usize = 8 + 32;
+
+fn display<S: Serializer>(key: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(key)
+}
+
+/// Who a stake position belongs to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StakeKind {
+    /// Owned by the wallet in `owner`
+    Wallet,
+    /// Backs the position NFT whose mint is `owner`
+    Position,
+    /// Shared by the holders of the receipt mint in `owner`
+    Liquid,
+}
+
+/// One stake position at the snapshot's timestamp
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct StakeRow {
+    #[serde(serialize_with = "display")]
+    pub address: Pubkey,
+    #[serde(serialize_with = "display")]
+    pub owner: Pubkey,
+    pub kind: StakeKind,
+    pub staked_amount: u64,
+    /// Rewards claimable at the snapshot's timestamp
+    pub pending_rewards: u64,
+    /// When the position can be unstaked (0 if nothing is staked)
+    pub lock_expiry: i64,
+    pub total_rewards_claimed: u64,
+}
+
+/// Every stake position of a pool, largest first
+#[derive(Clone, Debug, Serialize)]
+pub struct Snapshot {
+    #[serde(serialize_with = "display")]
+    pub pool: Pubkey,
+    pub timestamp: i64,
+    pub total_staked: u64,
+    pub vault_balance: u64,
+    pub stakes: Vec<StakeRow>,
+}
+
+impl Snapshot {
+    /// Value `stakes` of `pool` at `timestamp`
+    pub fn new(
+        pool_address: Pubkey,
+        pool: &StakePool,
+        vault_balance: u64,
+        stakes: Vec<(Pubkey, UserStake)>,
+        timestamp: i64,
+    ) -> Result<Self> {
+        let mut rows = stakes
+            .into_iter()
+            .map(|(address, stake)| {
+                let kind =
+                    if pool.receipt_mint != Pubkey::default() && stake.owner == pool.receipt_mint {
+                        StakeKind::Liquid
+                    } else if address == pda::position_stake(&stake.owner) {
+                        StakeKind::Position
+                    } else {
+                        StakeKind::Wallet
+                    };
+                let pending_rewards = math::claimable_rewards(pool, &stake, timestamp)
+                    .ok_or_else(|| anyhow!("pending rewards of {address} overflow a u64"))?;
+                Ok(StakeRow {
+                    address,
+                    owner: stake.owner,
+                    kind,
+                    staked_amount: stake.staked_amount,
+                    pending_rewards,
+                    lock_expiry: stake.unlock_time(pool.lock_period),
+                    total_rewards_claimed: stake.total_rewards_claimed,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        rows.sort_by(|a, b| {
+            b.staked_amount
+                .cmp(&a.staked_amount)
+                .then(a.address.cmp(&b.address))
+        });
+
+        Ok(Self {
+            pool: pool_address,
+            timestamp,
+            total_staked: pool.total_staked,
+            vault_balance,
+            stakes: rows,
+        })
+    }
+
+    /// Sum of every position's stake
+    pub fn staked_sum(&self) -> u128 {
+        self.stakes
+            .iter()
+            .map(|row| u128::from(row.staked_amount))
+            .sum()
+    }
+
+    /// Verify the positions add up to the pool's `total_staked` and vault balance
+    pub fn check(&self) -> Result<()> {
+        let sum = self.staked_sum();
+        ensure!(
+            sum == u128::from(self.total_staked),
+            "positions hold {sum} but the pool records {} staked",
+            self.total_staked
+        );
+        ensure!(
+            sum == u128::from(self.vault_balance),
+            "positions hold {sum} but the pool vault holds {}",
+            self.vault_balance
+        );
+        Ok(())
+    }
+
+    /// One CSV row per position
+    pub fn write_csv(&self, writer: impl Write) -> Result<()> {
+        let mut csv = csv::Writer::from_writer(writer);
+        for row in &self.stakes {
+            csv.serialize(row)?;
+        }
+        csv.flush()?;
+        Ok(())
+    }
+
+    /// The whole snapshot, pool totals included
+    pub fn write_json(&self, writer: impl Write) -> Result<()> {
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+}
+
+/// Every `UserStake` of `pool`, including position NFT and liquid stakes
+pub fn user_stakes(rpc: &RpcClient, pool: &Pubkey) -> Result<Vec<(Pubkey, UserStake)>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![
+            RpcFilterType::DataSize((8 + UserStake::INIT_SPACE) as u64),
+            RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                USER_STAKE_POOL_OFFSET,
+                pool.to_bytes().to_vec(),
+            )),
+        ]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+
+    rpc.get_program_accounts_with_config(&ID, config)?
+        .into_iter()
+        .map(|(address, account)| Ok((address, state::user_stake(&account.data)?)))
+        .collect()
+}
+
+/// Snapshot the pool staking `staking_mint` at `timestamp` [default: the cluster time]
+///
+/// Positions are valued from their current state, so a past `timestamp` only
+/// makes sense for positions untouched since then.
+pub fn fetch(rpc: &RpcClient, staking_mint: &Pubkey, timestamp: Option<i64>) -> Result<Snapshot> {
+    let pool_address = pda::pool(staking_mint);
+    let vault = pda::pool_vault(&pool_address);
+
+    // Read the pool, vault and clock at the same slot
+    let accounts = rpc.get_multiple_accounts(&[pool_address, vault, sysvar::clock::ID])?;
+    let [pool, vault, clock] = [0, 1, 2].map(|i| accounts[i].as_ref());
+    let pool = state::stake_pool(
+        &pool
+            .with_context(|| format!("no pool at {pool_address}"))?
+            .data,
+    )?;
+    let vault =
+        TokenAccount::try_deserialize(&mut vault.context("missing pool vault")?.data.as_slice())?;
+    let clock: Clock = from_account(clock.context("missing clock sysvar")?)
+        .context("failed to decode the clock sysvar")?;
+
+    let stakes = user_stakes(rpc, &pool_address)?;
+    Snapshot::new(
+        pool_address,
+        &pool,
+        vault.amount,
+        stakes,
+        timestamp.unwrap_or(clock.unix_timestamp),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use memecoin_staking_client::{RewardIndex, RewardRate};
+
+    fn pool(total_staked: u64, receipt_mint: Pubkey) -> StakePool {
+        StakePool {
+            authority: Pubkey::new_unique(),
+            staking_mint: Pubkey::new_unique(),
+            reward_mint: Pubkey::new_unique(),
+            pool_vault: Pubkey::new_unique(),
+            reward_vault: Pubkey::new_unique(),
+            reward_rate: RewardRate(memecoin_staking_client::program::RATE_SCALE),
+            lock_period: 100,
+            total_staked,
+            last_update_time: 0,
+            pause_flags: 0,
+            accrual_halted_at: 0,
+            accrual_halted_seconds: 0,
+            emergency_mode: false,
+            transfers_disabled: false,
+            receipt_mint,
+            crank_tip_bps: 0,
+            rate_oracle: Default::default(),
+            voter_weight: Default::default(),
+            distributor: Default::default(),
+            referral_bps: 0,
+            fees: Default::default(),
+            nft_boost: Default::default(),
+            tiers: Default::default(),
+            campaigns: Default::default(),
+            // Settled when the stakes below last accrued
+            reward_index: RewardIndex {
+                value: 0,
+                updated_at: 1_000,
+            },
+            checkpoint_ring: Default::default(),
+            bump: 255,
+        }
+    }
+
+    fn stake(owner: Pubkey, staked_amount: u64) -> UserStake {
+        UserStake {
+            owner,
+            pool: Pubkey::new_unique(),
+            staked_amount,
+            pending_rewards: 3,
+            last_stake_time: 1_000,
+            last_accrual_time: 1_000,
+            stake_start_time: 1_000,
+            total_rewards_claimed: 7,
+            claim_delegate: Pubkey::default(),
+            reward_destination: Pubkey::default(),
+            referrer: Pubkey::default(),
+            referral_pending: 0,
+            boost_mint: Pubkey::default(),
+            tier: 0,
+            campaign_rewards: Default::default(),
+            reward_index_paid: 0,
+            tier_multiplier_bps: memecoin_staking_client::program::BPS_DENOMINATOR,
+            bump: 255,
+        }
+    }
+
+    fn snapshot(vault_balance: u64) -> Snapshot {
+        let receipt_mint = Pubkey::new_unique();
+        let position_mint = Pubkey::new_unique();
+        let wallet = Pubkey::new_unique();
+        let stakes = vec![
+            (Pubkey::new_unique(), stake(wallet, 10)),
+            (
+                pda::position_stake(&position_mint),
+                stake(position_mint, 30),
+            ),
+            (Pubkey::new_unique(), stake(receipt_mint, 20)),
+        ];
+        Snapshot::new(
+            Pubkey::new_unique(),
+            &pool(60, receipt_mint),
+            vault_balance,
+            stakes,
+            1_005,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn values_and_classifies_positions() {
+        let snapshot = snapshot(60);
+        let rows: Vec<_> = snapshot
+            .stakes
+            .iter()
+            .map(|row| {
+                (
+                    row.kind,
+                    row.staked_amount,
+                    row.pending_rewards,
+                    row.lock_expiry,
+                )
+            })
+            .collect();
+        // One reward per staked token per second on top of the 3 already pending
+        assert_eq!(
+            rows,
+            [
+                (StakeKind::Position, 30, 153, 1_100),
+                (StakeKind::Liquid, 20, 103, 1_100),
+                (StakeKind::Wallet, 10, 53, 1_100),
+            ]
+        );
+        snapshot.check().unwrap();
+    }
+
+    #[test]
+    fn check_catches_mismatched_totals() {
+        assert!(snapshot(61).check().is_err());
+
+        let mut snapshot = snapshot(60);
+        snapshot.total_staked = 59;
+        assert!(snapshot.check().is_err());
+    }
+
+    #[test]
+    fn exports_csv_and_json() {
+        let snapshot = snapshot(60);
+
+        let mut csv = Vec::new();
+        snapshot.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some(
+                "address,owner,kind,staked_amount,pending_rewards,lock_expiry,total_rewards_claimed"
+            )
+        );
+        let first = &snapshot.stakes[0];
+        assert_eq!(
+            lines.next(),
+            Some(format!("{},{},position,30,153,1100,7", first.address, first.owner).as_str())
+        );
+        assert_eq!(lines.count(), 2);
+
+        let mut json = Vec::new();
+        snapshot.write_json(&mut json).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(json["total_staked"], 60);
+        assert_eq!(json["stakes"][0]["owner"], first.owner.to_string());
+        assert_eq!(json["stakes"].as_array().unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,97 @@
+//! Export every stake position of a pool as CSV or JSON
+//!
+//! ```text
+//! staking-snapshot --staking-mint <MINT> --format csv --output stakers.csv
+//! ```
+//!
+//! The export is refused when the positions do not add up to the pool's
+//! `total_staked` and vault balance, which usually means the pool changed
+//! while it was being read; run it again or pass `--skip-check`.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+use anchor_lang::prelude::Pubkey;
+use anyhow::{Context, Result};
+use clap::{Parser, ValueEnum};
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Csv,
+    Json,
+}
+
+#[derive(Parser)]
+#[command(
+    name = "staking-snapshot",
+    version,
+    about = "Export every stake position of a pool"
+)]
+struct Cli {
+    /// RPC endpoint
+    #[arg(
+        long,
+        short,
+        env = "RPC_URL",
+        default_value = "https://api.mainnet-beta.solana.com"
+    )]
+    url: String,
+
+    /// Mint staked in the pool (the pool is derived from it)
+    #[arg(long)]
+    staking_mint: Pubkey,
+
+    /// Unix timestamp to value pending rewards at [default: the cluster time]
+    #[arg(long)]
+    at: Option<i64>,
+
+    #[arg(long, value_enum, default_value = "csv")]
+    format: Format,
+
+    /// File to write [default: stdout]
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+
+    /// Export even if the positions do not add up to the pool's totals
+    #[arg(long)]
+    skip_check: bool,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let rpc = RpcClient::new_with_commitment(cli.url, CommitmentConfig::confirmed());
+    let snapshot = staking_snapshot::fetch(&rpc, &cli.staking_mint, cli.at)?;
+
+    eprintln!(
+        "{} positions holding {} at {} (pool total {}, vault {})",
+        snapshot.stakes.len(),
+        snapshot.staked_sum(),
+        snapshot.timestamp,
+        snapshot.total_staked,
+        snapshot.vault_balance
+    );
+    if let Err(err) = snapshot.check() {
+        if !cli.skip_check {
+            return Err(err.context("inconsistent snapshot"));
+        }
+        eprintln!("warning: {err}");
+    }
+
+    let mut writer: Box<dyn Write> = match &cli.output {
+        Some(path) => {
+            Box::new(BufWriter::new(File::create(path).with_context(|| {
+                format!("failed to create {}", path.display())
+            })?))
+        }
+        None => Box::new(io::stdout().lock()),
+    };
+    match cli.format {
+        Format::Csv => snapshot.write_csv(&mut writer)?,
+        Format::Json => snapshot.write_json(&mut writer)?,
+    }
+    writer.flush()?;
+    Ok(())
+}
@@ -1,6 +1,17 @@
 use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use anchor_spl::token_2022::spl_token_2022::instruction::AuthorityType;
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_2022_extensions::spl_pod::optional_keys::OptionalNonZeroPubkey;
+use anchor_spl::token_2022_extensions::spl_token_metadata_interface::state::{Field, TokenMetadata};
+use anchor_spl::token_2022_extensions::{
+    token_metadata_initialize, token_metadata_update_field, TokenMetadataInitialize,
+    TokenMetadataUpdateField,
+};
 use anchor_spl::token_interface::{Mint as MintInterface, TokenAccount as TokenAccountInterface, TokenInterface};
+use solana_sha256_hasher::hashv;
 
 declare_id!("2RoYimfnkSHZTFrjzLNYt5DSJKPm6VHRbg2k3sfmyCDB");
 
@@ -11,10 +22,11 @@ pub mod memecoin_staking {
     /// Initialize a new staking pool for a memecoin
     pub fn initialize_pool(
         ctx: Context<InitializePool>,
-        reward_rate: u64,    // Rewards per second per token (scaled by 1e18)
+        reward_rate: RewardRate, // Rewards per second per staked token (36-decimal fixed point)
         lock_period: i64,    // Minimum stake duration in seconds
-        min_stake_amount: u64, // Minimum tokens required to stake (in base units)
+        min_stake_amount: u64, // Minimum tokens required to stake (in base units), as one 1x tier
     ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
         let pool = &mut ctx.accounts.pool;
         pool.authority = ctx.accounts.authority.key();
         pool.staking_mint = ctx.accounts.staking_mint.key();
@@ -22,13 +34,48 @@ pub mod memecoin_staking {
         pool.pool_vault = ctx.accounts.pool_vault.key();
         pool.reward_vault = ctx.accounts.reward_vault.key();
         pool.reward_rate = reward_rate;
+        pool.reward_index = RewardIndex {
+            value: 0,
+            updated_at: now,
+        };
         pool.lock_period = lock_period;
-        pool.min_stake_amount = min_stake_amount;
+        pool.tiers = TierTable::new(&[Tier {
+            min_stake: min_stake_amount,
+            multiplier_bps: BPS_DENOMINATOR,
+        }])?;
+        pool.campaigns = CampaignState::default();
         pool.total_staked = 0;
-        pool.last_update_time = Clock::get()?.unix_timestamp;
-        pool.paused = false;
+        pool.last_update_time = now;
+        pool.pause_flags = 0;
+        pool.accrual_halted_at = 0;
+        pool.accrual_halted_seconds = 0;
+        pool.emergency_mode = false;
+        pool.transfers_disabled = false;
+        pool.receipt_mint = Pubkey::default();
+        pool.crank_tip_bps = 0;
+        pool.rate_oracle = RateOracle::default();
+        pool.voter_weight = VoterWeightConfig::default();
+        pool.distributor = DistributorState::default();
+        pool.referral_bps = 0;
+        pool.fees = FeeConfig::default();
+        pool.nft_boost = NftBoost::default();
+        pool.checkpoint_ring = CheckpointRing::INITIAL;
         pool.bump = ctx.bumps.pool;
 
+        let pool_key = ctx.accounts.pool.key();
+        init_checkpoint_page(
+            &mut ctx.accounts.pool_checkpoints,
+            pool_key,
+            0,
+            ctx.bumps.pool_checkpoints,
+        );
+        init_checkpoint_page(
+            &mut ctx.accounts.next_pool_checkpoints,
+            pool_key,
+            1,
+            ctx.bumps.next_pool_checkpoints,
+        );
+
         emit!(PoolInitialized {
             pool: ctx.accounts.pool.key(),
             staking_mint: ctx.accounts.staking_mint.key(),
@@ -36,49 +83,27 @@ pub mod memecoin_staking {
             reward_rate,
             lock_period,
             min_stake_amount,
+            timestamp: now,
         });
 
         Ok(())
     }
 
     /// Stake tokens into the pool
+    ///
+    /// The deposit fee is taken out of `amount` and the rest is staked. In oracle rate mode
+    /// the pool's price account must be the first remaining account.
     pub fn stake(ctx: Context<Stake>, amount: u64) -> Result<()> {
         require!(amount > 0, StakingError::InvalidAmount);
-        require!(!ctx.accounts.pool.paused, StakingError::PoolPaused);
-        
-        // Check minimum stake requirement
-        let new_total = ctx.accounts.user_stake.staked_amount
-            .checked_add(amount)
-            .ok_or(StakingError::MathOverflow)?;
-        require!(
-            new_total >= ctx.accounts.pool.min_stake_amount,
-            StakingError::BelowMinimumStake
-        );
-
-        let pool = &mut ctx.accounts.pool;
-        let user_stake = &mut ctx.accounts.user_stake;
+        require!(!ctx.accounts.pool.is_paused(PAUSE_STAKE), StakingError::PoolPaused);
         let clock = Clock::get()?;
-
-        // Initialize user stake account if first time
-        if user_stake.owner == Pubkey::default() {
-            user_stake.owner = ctx.accounts.user.key();
-            user_stake.pool = pool.key();
-            user_stake.bump = ctx.bumps.user_stake;
-        }
-
-        // Calculate pending rewards before updating stake
-        if user_stake.staked_amount > 0 {
-            let pending = calculate_pending_rewards(
-                user_stake.staked_amount,
-                user_stake.last_stake_time,
-                clock.unix_timestamp,
-                pool.reward_rate,
-            );
-            user_stake.pending_rewards = user_stake
-                .pending_rewards
-                .checked_add(pending)
-                .ok_or(StakingError::MathOverflow)?;
-        }
+        refresh_oracle_rate(
+            &mut ctx.accounts.pool,
+            ctx.remaining_accounts,
+            clock.unix_timestamp,
+        )?;
+        let fee = fee_for(amount, ctx.accounts.pool.fees.deposit_bps);
+        let staked = amount - fee;
 
         // Transfer tokens to vault using token interface
         let cpi_accounts = anchor_spl::token_interface::TransferChecked {
@@ -92,69 +117,169 @@ pub mod memecoin_staking {
         let decimals = ctx.accounts.staking_mint.decimals;
         anchor_spl::token_interface::transfer_checked(
             CpiContext::new(cpi_program, cpi_accounts),
-            amount,
+            staked,
             decimals,
         )?;
+        pay_staking_fee(
+            ctx.accounts.user_token_account.to_account_info(),
+            ctx.accounts.user.to_account_info(),
+            ctx.accounts.staking_treasury.as_ref().map(|treasury| treasury.to_account_info()),
+            &ctx.accounts.staking_mint,
+            ctx.accounts.staking_token_program.to_account_info(),
+            &[],
+            fee,
+        )?;
 
-        // Update state
-        user_stake.staked_amount = user_stake
-            .staked_amount
-            .checked_add(amount)
-            .ok_or(StakingError::MathOverflow)?;
-        user_stake.last_stake_time = clock.unix_timestamp;
-        
-        // Only set start time if this is a new stake
-        if user_stake.stake_start_time == 0 {
-            user_stake.stake_start_time = clock.unix_timestamp;
-        }
+        credit_stake(
+            &mut ctx.accounts.pool,
+            &mut ctx.accounts.user_stake,
+            ctx.accounts.user.key(),
+            ctx.bumps.user_stake,
+            staked,
+            clock.unix_timestamp,
+        )?;
 
-        pool.total_staked = pool
-            .total_staked
-            .checked_add(amount)
-            .ok_or(StakingError::MathOverflow)?;
-        pool.last_update_time = clock.unix_timestamp;
+        init_checkpoints(
+            &mut ctx.accounts.user_checkpoints,
+            ctx.accounts.pool.key(),
+            ctx.accounts.user.key(),
+            ctx.bumps.user_checkpoints,
+            ctx.accounts.user_stake.staked_amount - staked,
+        );
+        ctx.accounts
+            .user_checkpoints
+            .record(ctx.accounts.user_stake.staked_amount, &clock);
+        record_pool_total(
+            &mut ctx.accounts.pool,
+            &mut ctx.accounts.pool_checkpoints,
+            &mut ctx.accounts.next_pool_checkpoints,
+            &clock,
+        );
 
         emit!(StakeEvent {
             user: ctx.accounts.user.key(),
-            pool: pool.key(),
+            pool: ctx.accounts.pool.key(),
             amount,
-            total_staked: user_stake.staked_amount,
+            total_staked: ctx.accounts.user_stake.staked_amount,
             timestamp: clock.unix_timestamp,
+            fee,
         });
 
         Ok(())
     }
 
-    /// Unstake tokens from the pool
-    pub fn unstake(ctx: Context<Unstake>, amount: u64) -> Result<()> {
-        let pool = &mut ctx.accounts.pool;
-        let user_stake = &mut ctx.accounts.user_stake;
+    /// Stake the funder's tokens into a position owned by `beneficiary`
+    ///
+    /// Only the beneficiary can later unstake or claim from the position. In oracle rate
+    /// mode the pool's price account must be the first remaining account.
+    pub fn stake_for(ctx: Context<StakeFor>, beneficiary: Pubkey, amount: u64) -> Result<()> {
+        require!(amount > 0, StakingError::InvalidAmount);
+        require!(beneficiary != Pubkey::default(), StakingError::InvalidOwner);
+        require!(!ctx.accounts.pool.is_paused(PAUSE_STAKE), StakingError::PoolPaused);
         let clock = Clock::get()?;
+        refresh_oracle_rate(
+            &mut ctx.accounts.pool,
+            ctx.remaining_accounts,
+            clock.unix_timestamp,
+        )?;
+        let fee = fee_for(amount, ctx.accounts.pool.fees.deposit_bps);
+        let staked = amount - fee;
 
-        require!(amount > 0, StakingError::InvalidAmount);
-        require!(
-            user_stake.staked_amount >= amount,
-            StakingError::InsufficientStake
+        // Transfer funder's tokens to vault using token interface
+        let cpi_accounts = anchor_spl::token_interface::TransferChecked {
+            from: ctx.accounts.funder_token_account.to_account_info(),
+            mint: ctx.accounts.staking_mint.to_account_info(),
+            to: ctx.accounts.pool_vault.to_account_info(),
+            authority: ctx.accounts.funder.to_account_info(),
+        };
+        let decimals = ctx.accounts.staking_mint.decimals;
+        anchor_spl::token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.staking_token_program.to_account_info(),
+                cpi_accounts,
+            ),
+            staked,
+            decimals,
+        )?;
+        pay_staking_fee(
+            ctx.accounts.funder_token_account.to_account_info(),
+            ctx.accounts.funder.to_account_info(),
+            ctx.accounts.staking_treasury.as_ref().map(|treasury| treasury.to_account_info()),
+            &ctx.accounts.staking_mint,
+            ctx.accounts.staking_token_program.to_account_info(),
+            &[],
+            fee,
+        )?;
+
+        credit_stake(
+            &mut ctx.accounts.pool,
+            &mut ctx.accounts.user_stake,
+            beneficiary,
+            ctx.bumps.user_stake,
+            staked,
+            clock.unix_timestamp,
+        )?;
+
+        init_checkpoints(
+            &mut ctx.accounts.user_checkpoints,
+            ctx.accounts.pool.key(),
+            beneficiary,
+            ctx.bumps.user_checkpoints,
+            ctx.accounts.user_stake.staked_amount - staked,
+        );
+        ctx.accounts
+            .user_checkpoints
+            .record(ctx.accounts.user_stake.staked_amount, &clock);
+        record_pool_total(
+            &mut ctx.accounts.pool,
+            &mut ctx.accounts.pool_checkpoints,
+            &mut ctx.accounts.next_pool_checkpoints,
+            &clock,
         );
 
-        // Check lock period
-        let time_staked = clock
-            .unix_timestamp
-            .checked_sub(user_stake.stake_start_time)
-            .ok_or(StakingError::MathOverflow)?;
-        require!(time_staked >= pool.lock_period, StakingError::StillLocked);
+        emit!(StakeForEvent {
+            funder: ctx.accounts.funder.key(),
+            beneficiary,
+            pool: ctx.accounts.pool.key(),
+            amount,
+            total_staked: ctx.accounts.user_stake.staked_amount,
+            timestamp: clock.unix_timestamp,
+            fee,
+        });
+
+        Ok(())
+    }
 
-        // Calculate and store pending rewards
-        let pending = calculate_pending_rewards(
-            user_stake.staked_amount,
-            user_stake.last_stake_time,
+    /// Unstake tokens from the pool
+    ///
+    /// The withdrawal fee is taken out of the `amount` unstaked. In oracle rate mode the
+    /// pool's price account must be the first remaining account.
+    pub fn unstake(ctx: Context<Unstake>, amount: u64) -> Result<()> {
+        let clock = Clock::get()?;
+        refresh_oracle_rate(
+            &mut ctx.accounts.pool,
+            ctx.remaining_accounts,
             clock.unix_timestamp,
-            pool.reward_rate,
+        )?;
+        init_checkpoints(
+            &mut ctx.accounts.user_checkpoints,
+            ctx.accounts.pool.key(),
+            ctx.accounts.user.key(),
+            ctx.bumps.user_checkpoints,
+            ctx.accounts.user_stake.staked_amount,
+        );
+        let pool = &mut ctx.accounts.pool;
+        let user_stake = &mut ctx.accounts.user_stake;
+
+        require!(!pool.is_paused(PAUSE_UNSTAKE), StakingError::PoolPaused);
+        debit_stake(pool, user_stake, amount, clock.unix_timestamp)?;
+        ctx.accounts.user_checkpoints.record(user_stake.staked_amount, &clock);
+        record_pool_total(
+            pool,
+            &mut ctx.accounts.pool_checkpoints,
+            &mut ctx.accounts.next_pool_checkpoints,
+            &clock,
         );
-        user_stake.pending_rewards = user_stake
-            .pending_rewards
-            .checked_add(pending)
-            .ok_or(StakingError::MathOverflow)?;
 
         // Transfer tokens back to user using PDA signer
         let staking_mint_key = pool.staking_mint;
@@ -165,6 +290,7 @@ pub mod memecoin_staking {
         ];
         let signer = &[&seeds[..]];
 
+        let fee = fee_for(amount, pool.fees.withdrawal_bps);
         let cpi_accounts = anchor_spl::token_interface::TransferChecked {
             from: ctx.accounts.pool_vault.to_account_info(),
             mint: ctx.accounts.staking_mint.to_account_info(),
@@ -178,27 +304,18 @@ pub mod memecoin_staking {
                 cpi_accounts,
                 signer,
             ),
-            amount,
+            amount - fee,
             decimals,
         )?;
-
-        // Update state
-        user_stake.staked_amount = user_stake
-            .staked_amount
-            .checked_sub(amount)
-            .ok_or(StakingError::MathOverflow)?;
-        user_stake.last_stake_time = clock.unix_timestamp;
-
-        pool.total_staked = pool
-            .total_staked
-            .checked_sub(amount)
-            .ok_or(StakingError::MathOverflow)?;
-        pool.last_update_time = clock.unix_timestamp;
-
-        // Reset stake start time if fully unstaked
-        if user_stake.staked_amount == 0 {
-            user_stake.stake_start_time = 0;
-        }
+        pay_staking_fee(
+            ctx.accounts.pool_vault.to_account_info(),
+            pool.to_account_info(),
+            ctx.accounts.staking_treasury.as_ref().map(|treasury| treasury.to_account_info()),
+            &ctx.accounts.staking_mint,
+            ctx.accounts.staking_token_program.to_account_info(),
+            signer,
+            fee,
+        )?;
 
         emit!(UnstakeEvent {
             user: ctx.accounts.user.key(),
@@ -206,34 +323,32 @@ pub mod memecoin_staking {
             amount,
             remaining_stake: user_stake.staked_amount,
             timestamp: clock.unix_timestamp,
+            fee,
         });
 
         Ok(())
     }
 
     /// Claim accumulated rewards
+    ///
+    /// In oracle rate mode the pool's price account must be the first remaining account.
     pub fn claim_rewards(ctx: Context<ClaimRewards>) -> Result<()> {
-        let pool = &ctx.accounts.pool;
-        let user_stake = &mut ctx.accounts.user_stake;
         let clock = Clock::get()?;
-
-        // Calculate total rewards
-        let pending = calculate_pending_rewards(
-            user_stake.staked_amount,
-            user_stake.last_stake_time,
+        refresh_oracle_rate(
+            &mut ctx.accounts.pool,
+            ctx.remaining_accounts,
             clock.unix_timestamp,
-            pool.reward_rate,
-        );
-        let total_rewards = user_stake
-            .pending_rewards
-            .checked_add(pending)
-            .ok_or(StakingError::MathOverflow)?;
+        )?;
+
+        let pool = &ctx.accounts.pool;
+        let user_stake = &mut ctx.accounts.user_stake;
 
-        require!(total_rewards > 0, StakingError::NoRewards);
+        require!(!pool.is_paused(PAUSE_CLAIM), StakingError::PoolPaused);
+        let total_rewards = take_rewards(pool, user_stake, clock.unix_timestamp)?;
 
-        // Check reward vault balance
+        // Check reward vault balance, excluding rewards reserved for distributor epochs
         require!(
-            ctx.accounts.reward_vault.amount >= total_rewards,
+            pool.available_rewards(ctx.accounts.reward_vault.amount) >= total_rewards,
             StakingError::InsufficientRewardBalance
         );
 
@@ -246,6 +361,7 @@ pub mod memecoin_staking {
         ];
         let signer = &[&seeds[..]];
 
+        let fee = fee_for(total_rewards, pool.fees.performance_bps);
         let cpi_accounts = Transfer {
             from: ctx.accounts.reward_vault.to_account_info(),
             to: ctx.accounts.user_reward_account.to_account_info(),
@@ -257,434 +373,6500 @@ pub mod memecoin_staking {
                 cpi_accounts,
                 signer,
             ),
-            total_rewards,
+            total_rewards - fee,
+        )?;
+        pay_reward_fee(
+            pool,
+            ctx.accounts.reward_vault.to_account_info(),
+            ctx.accounts.reward_treasury.as_ref().map(|treasury| treasury.to_account_info()),
+            ctx.accounts.token_program.to_account_info(),
+            signer,
+            fee,
         )?;
-
-        // Reset pending rewards and update timestamp
-        user_stake.pending_rewards = 0;
-        user_stake.last_stake_time = clock.unix_timestamp;
-        user_stake.total_rewards_claimed = user_stake
-            .total_rewards_claimed
-            .checked_add(total_rewards)
-            .ok_or(StakingError::MathOverflow)?;
 
         emit!(ClaimEvent {
             user: ctx.accounts.user.key(),
             pool: pool.key(),
             amount: total_rewards,
             timestamp: clock.unix_timestamp,
+            fee,
         });
 
         Ok(())
     }
 
-    /// Update pool reward rate (admin only)
-    pub fn update_reward_rate(ctx: Context<AdminAction>, new_rate: u64) -> Result<()> {
-        let pool = &mut ctx.accounts.pool;
-        let old_rate = pool.reward_rate;
-        pool.reward_rate = new_rate;
-        pool.last_update_time = Clock::get()?.unix_timestamp;
-
-        emit!(RewardRateUpdated {
-            pool: pool.key(),
-            old_rate,
-            new_rate,
-        });
-
-        Ok(())
-    }
+    /// Claim a position's rewards as its owner or claim delegate, paying its reward destination
+    ///
+    /// Rewards go to the configured `reward_destination`, or any reward account owned by
+    /// the position owner if none is configured. In oracle rate mode the pool's price
+    /// account must be the first remaining account.
+    pub fn claim_rewards_for(ctx: Context<ClaimRewardsFor>) -> Result<()> {
+        let clock = Clock::get()?;
+        refresh_oracle_rate(
+            &mut ctx.accounts.pool,
+            ctx.remaining_accounts,
+            clock.unix_timestamp,
+        )?;
 
-    /// Pause/unpause the pool (admin only)
-    pub fn set_paused(ctx: Context<AdminAction>, paused: bool) -> Result<()> {
-        ctx.accounts.pool.paused = paused;
+        let pool = &ctx.accounts.pool;
+        let user_stake = &mut ctx.accounts.user_stake;
 
-        emit!(PoolPausedEvent {
-            pool: ctx.accounts.pool.key(),
-            paused,
-        });
+        require!(!pool.is_paused(PAUSE_CLAIM), StakingError::PoolPaused);
+        let total_rewards = take_rewards(pool, user_stake, clock.unix_timestamp)?;
 
-        Ok(())
-    }
+        // Check reward vault balance, excluding rewards reserved for distributor epochs
+        require!(
+            pool.available_rewards(ctx.accounts.reward_vault.amount) >= total_rewards,
+            StakingError::InsufficientRewardBalance
+        );
 
-    /// Fund the reward vault (anyone can fund)
-    pub fn fund_rewards(ctx: Context<FundRewards>, amount: u64) -> Result<()> {
-        require!(amount > 0, StakingError::InvalidAmount);
+        // Transfer rewards using PDA signer
+        let staking_mint = pool.staking_mint;
+        let seeds = &[
+            b"pool".as_ref(),
+            staking_mint.as_ref(),
+            &[pool.bump],
+        ];
+        let signer = &[&seeds[..]];
 
+        let fee = fee_for(total_rewards, pool.fees.performance_bps);
         let cpi_accounts = Transfer {
-            from: ctx.accounts.funder_token_account.to_account_info(),
-            to: ctx.accounts.reward_vault.to_account_info(),
-            authority: ctx.accounts.funder.to_account_info(),
+            from: ctx.accounts.reward_vault.to_account_info(),
+            to: ctx.accounts.reward_destination.to_account_info(),
+            authority: pool.to_account_info(),
         };
         token::transfer(
-            CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
-            amount,
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer,
+            ),
+            total_rewards - fee,
+        )?;
+        pay_reward_fee(
+            pool,
+            ctx.accounts.reward_vault.to_account_info(),
+            ctx.accounts.reward_treasury.as_ref().map(|treasury| treasury.to_account_info()),
+            ctx.accounts.token_program.to_account_info(),
+            signer,
+            fee,
         )?;
 
-        emit!(RewardsFunded {
-            pool: ctx.accounts.pool.key(),
-            funder: ctx.accounts.funder.key(),
-            amount,
+        emit!(ClaimEvent {
+            user: user_stake.owner,
+            pool: pool.key(),
+            amount: total_rewards,
+            timestamp: clock.unix_timestamp,
+            fee,
         });
 
         Ok(())
     }
-}
 
-// ============ HELPER FUNCTIONS ============
+    /// Claim for many positions at once, paying each to its registered reward destination
+    ///
+    /// `remaining_accounts` holds `(user_stake, reward_destination)` pairs, after the pool's
+    /// price account in oracle rate mode. Positions the crank can't claim for are skipped:
+    /// tokenized and liquid positions, other pools' positions, and positions without a
+    /// registered destination or with nothing claimable. The cranker keeps `crank_tip_bps`
+    /// of every claim as a tip, on top of the performance fee.
+    pub fn crank_claim<'info>(ctx: Context<'_, '_, 'info, 'info, CrankClaim<'info>>) -> Result<()> {
+        let clock = Clock::get()?;
+        let claims = refresh_oracle_rate(
+            &mut ctx.accounts.pool,
+            ctx.remaining_accounts,
+            clock.unix_timestamp,
+        )?;
+        let pool = &ctx.accounts.pool;
 
-/// Scaling factor for reward rate precision (1e18)
-const REWARD_SCALE: u128 = 1_000_000_000_000_000_000;
+        require!(!pool.is_paused(PAUSE_CLAIM), StakingError::PoolPaused);
+        require!(
+            !claims.is_empty() && claims.chunks_exact(2).remainder().is_empty(),
+            StakingError::InvalidCrankAccounts
+        );
 
-fn calculate_pending_rewards(
-    staked_amount: u64,
-    last_stake_time: i64,
-    current_time: i64,
-    reward_rate: u64,
-) -> u64 {
-    if staked_amount == 0 || last_stake_time >= current_time {
-        return 0;
-    }
+        let staking_mint = pool.staking_mint;
+        let seeds = &[
+            b"pool".as_ref(),
+            staking_mint.as_ref(),
+            &[pool.bump],
+        ];
+        let signer = &[&seeds[..]];
 
-    let time_elapsed = (current_time - last_stake_time) as u128;
-    let staked = staked_amount as u128;
-    let rate = reward_rate as u128;
+        let mut vault_balance = pool.available_rewards(ctx.accounts.reward_vault.amount);
+        let mut claimed_count: u32 = 0;
+        let mut total_claimed: u64 = 0;
+        let mut total_tip: u64 = 0;
+        let mut total_fee: u64 = 0;
 
-    // rewards = staked_amount * reward_rate * time_elapsed / 1e18
-    staked
-        .checked_mul(rate)
-        .and_then(|v| v.checked_mul(time_elapsed))
-        .and_then(|v| v.checked_div(REWARD_SCALE))
-        .map(|v| v as u64)
-        .unwrap_or(0)
-}
+        for pair in claims.chunks_exact(2) {
+            let (stake_info, destination_info) = (&pair[0], &pair[1]);
+            require!(
+                stake_info.is_writable && destination_info.is_writable,
+                StakingError::InvalidCrankAccounts
+            );
 
-// ============ ACCOUNT CONTEXTS ============
+            let mut user_stake = Account::<UserStake>::try_from(stake_info)?;
+            let expected = Pubkey::create_program_address(
+                &[
+                    b"user_stake",
+                    pool.key().as_ref(),
+                    user_stake.owner.as_ref(),
+                    &[user_stake.bump],
+                ],
+                ctx.program_id,
+            );
+            // Not a wallet stake of this pool, so it has no reward destination to pay
+            if expected != Ok(stake_info.key()) {
+                continue;
+            }
 
-#[derive(Accounts)]
-pub struct InitializePool<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
+            if user_stake.reward_destination == Pubkey::default() {
+                continue;
+            }
+            require_keys_eq!(
+                user_stake.reward_destination,
+                destination_info.key(),
+                StakingError::InvalidRewardDestination
+            );
 
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + StakePool::INIT_SPACE,
-        seeds = [b"pool", staking_mint.key().as_ref()],
-        bump
-    )]
-    pub pool: Account<'info, StakePool>,
+            let accrual_time = pool.accrual_time(clock.unix_timestamp);
+            accrue_rewards(pool, &mut user_stake, accrual_time)?;
+            user_stake.last_accrual_time = accrual_time;
+            let amount = user_stake.pending_rewards;
+            if amount == 0 || amount > vault_balance {
+                continue;
+            }
+            let total_rewards = take_rewards(pool, &mut user_stake, clock.unix_timestamp)?;
 
-    /// The staking token mint (can be Token or Token-2022)
-    pub staking_mint: InterfaceAccount<'info, MintInterface>,
-    /// The reward token mint (USDC - regular Token program)
-    pub reward_mint: Account<'info, Mint>,
+            let tip = (total_rewards as u128)
+                .checked_mul(pool.crank_tip_bps as u128)
+                .map(|v| v / BPS_DENOMINATOR as u128)
+                .ok_or(StakingError::MathOverflow)? as u64;
+            let fee = fee_for(total_rewards, pool.fees.performance_bps);
+            let payout = total_rewards
+                .checked_sub(tip)
+                .and_then(|rest| rest.checked_sub(fee))
+                .ok_or(StakingError::MathOverflow)?;
 
-    #[account(
-        init,
-        payer = authority,
-        token::mint = staking_mint,
-        token::authority = pool,
-        token::token_program = staking_token_program,
-        seeds = [b"pool_vault", pool.key().as_ref()],
-        bump
-    )]
-    pub pool_vault: InterfaceAccount<'info, TokenAccountInterface>,
+            if payout > 0 {
+                token::transfer(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.token_program.to_account_info(),
+                        Transfer {
+                            from: ctx.accounts.reward_vault.to_account_info(),
+                            to: destination_info.clone(),
+                            authority: pool.to_account_info(),
+                        },
+                        signer,
+                    ),
+                    payout,
+                )?;
+            }
+            user_stake.exit(ctx.program_id)?;
 
-    #[account(
-        init,
-        payer = authority,
-        token::mint = reward_mint,
-        token::authority = pool,
-        seeds = [b"reward_vault", pool.key().as_ref()],
-        bump
-    )]
-    pub reward_vault: Account<'info, TokenAccount>,
+            vault_balance -= total_rewards;
+            claimed_count += 1;
+            total_claimed = total_claimed
+                .checked_add(total_rewards)
+                .ok_or(StakingError::MathOverflow)?;
+            total_tip = total_tip.checked_add(tip).ok_or(StakingError::MathOverflow)?;
+            total_fee = total_fee.checked_add(fee).ok_or(StakingError::MathOverflow)?;
 
-    pub system_program: Program<'info, System>,
-    /// Token program for staking token (Token or Token-2022)
-    pub staking_token_program: Interface<'info, TokenInterface>,
-    /// Token program for reward token (regular Token)
-    pub token_program: Program<'info, Token>,
-    pub rent: Sysvar<'info, Rent>,
-}
+            emit!(ClaimEvent {
+                user: user_stake.owner,
+                pool: pool.key(),
+                amount: total_rewards,
+                timestamp: clock.unix_timestamp,
+                fee,
+            });
+        }
 
-#[derive(Accounts)]
-pub struct Stake<'info> {
-    #[account(mut)]
-    pub user: Signer<'info>,
+        if total_tip > 0 {
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.reward_vault.to_account_info(),
+                        to: ctx.accounts.cranker_reward_account.to_account_info(),
+                        authority: pool.to_account_info(),
+                    },
+                    signer,
+                ),
+                total_tip,
+            )?;
+        }
+        pay_reward_fee(
+            pool,
+            ctx.accounts.reward_vault.to_account_info(),
+            ctx.accounts.reward_treasury.as_ref().map(|treasury| treasury.to_account_info()),
+            ctx.accounts.token_program.to_account_info(),
+            signer,
+            total_fee,
+        )?;
 
-    #[account(
-        mut,
-        seeds = [b"pool", pool.staking_mint.as_ref()],
-        bump = pool.bump,
-    )]
-    pub pool: Account<'info, StakePool>,
+        emit!(CrankClaimed {
+            cranker: ctx.accounts.cranker.key(),
+            pool: pool.key(),
+            claimed_count,
+            total_claimed,
+            total_tip,
+            timestamp: clock.unix_timestamp,
+            total_fee,
+        });
 
-    #[account(
-        init_if_needed,
+        Ok(())
+    }
+
+    /// Name the wallet that referred the caller, before their first stake
+    ///
+    /// Send it in the same transaction as the first `stake`. From then on the referrer
+    /// earns `referral_bps` of the caller's rewards on top of them, paid out by
+    /// `claim_referral_rewards`. The referrer must itself stake at least the lowest tier's
+    /// minimum (or anything, without tiers), so a fresh second wallet can't refer its owner.
+    pub fn register_referrer(ctx: Context<RegisterReferrer>, referrer: Pubkey) -> Result<()> {
+        let user = ctx.accounts.user.key();
+        let pool = ctx.accounts.pool.key();
+        require!(
+            referrer != user && referrer != Pubkey::default(),
+            StakingError::InvalidReferrer
+        );
+
+        let user_stake = &mut ctx.accounts.user_stake;
+        require!(user_stake.owner == Pubkey::default(), StakingError::ReferrerLocked);
+        init_position(user_stake, user, pool, ctx.bumps.user_stake);
+        user_stake.referrer = referrer;
+
+        let referrer_stats = &mut ctx.accounts.referrer_stats;
+        if referrer_stats.referrer == Pubkey::default() {
+            referrer_stats.pool = pool;
+            referrer_stats.referrer = referrer;
+            referrer_stats.bump = ctx.bumps.referrer_stats;
+        }
+        referrer_stats.referral_count = referrer_stats
+            .referral_count
+            .checked_add(1)
+            .ok_or(StakingError::MathOverflow)?;
+
+        emit!(ReferralRegistered {
+            pool,
+            referrer,
+            referee: user,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Pay a referrer the referral rewards accrued by their referees (anyone can call)
+    ///
+    /// Remaining accounts: writable `UserStake`s of this pool referred by the referrer.
+    pub fn claim_referral_rewards<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimReferralRewards<'info>>,
+    ) -> Result<()> {
+        let pool = &ctx.accounts.pool;
+        let referrer = ctx.accounts.referrer_stats.referrer;
+        let now = Clock::get()?.unix_timestamp;
+
+        require!(!pool.is_paused(PAUSE_CLAIM), StakingError::PoolPaused);
+        require!(
+            !ctx.remaining_accounts.is_empty(),
+            StakingError::InvalidReferralAccounts
+        );
+
+        let accrual_time = pool.accrual_time(now);
+        let mut amount: u64 = 0;
+        for stake_info in ctx.remaining_accounts {
+            require!(stake_info.is_writable, StakingError::InvalidReferralAccounts);
+            let mut user_stake = Account::<UserStake>::try_from(stake_info)?;
+            require!(
+                user_stake.pool == pool.key() && user_stake.referrer == referrer,
+                StakingError::InvalidReferralAccounts
+            );
+
+            accrue_rewards(pool, &mut user_stake, accrual_time)?;
+            user_stake.last_accrual_time = accrual_time;
+            amount = amount
+                .checked_add(user_stake.referral_pending)
+                .ok_or(StakingError::MathOverflow)?;
+            user_stake.referral_pending = 0;
+            user_stake.exit(ctx.program_id)?;
+        }
+
+        require!(amount > 0, StakingError::NoRewards);
+        require!(
+            pool.available_rewards(ctx.accounts.reward_vault.amount) >= amount,
+            StakingError::InsufficientRewardBalance
+        );
+
+        let staking_mint = pool.staking_mint;
+        let seeds = &[
+            b"pool".as_ref(),
+            staking_mint.as_ref(),
+            &[pool.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.reward_vault.to_account_info(),
+            to: ctx.accounts.referrer_reward_account.to_account_info(),
+            authority: pool.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer,
+            ),
+            amount,
+        )?;
+
+        let referrer_stats = &mut ctx.accounts.referrer_stats;
+        referrer_stats.total_rewards_paid = referrer_stats
+            .total_rewards_paid
+            .checked_add(amount)
+            .ok_or(StakingError::MathOverflow)?;
+
+        emit!(ReferralRewardsPaid {
+            pool: pool.key(),
+            referrer,
+            referee_count: ctx.remaining_accounts.len() as u32,
+            amount,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Let `delegate` trigger claims for the caller's position (default key clears it)
+    pub fn set_claim_delegate(ctx: Context<UpdateUserStake>, delegate: Pubkey) -> Result<()> {
+        ctx.accounts.user_stake.claim_delegate = delegate;
+
+        emit!(ClaimDelegateSet {
+            user: ctx.accounts.user.key(),
+            pool: ctx.accounts.pool.key(),
+            delegate,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Pin the reward account that delegated claims pay into (omit the account to clear it)
+    pub fn set_reward_destination(ctx: Context<SetRewardDestination>) -> Result<()> {
+        let destination = ctx
+            .accounts
+            .reward_destination
+            .as_ref()
+            .map(|account| account.key())
+            .unwrap_or_default();
+        ctx.accounts.user_stake.reward_destination = destination;
+
+        emit!(RewardDestinationSet {
+            user: ctx.accounts.user.key(),
+            pool: ctx.accounts.pool.key(),
+            destination,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Escrow an NFT of the pool's boost collection to multiply the caller's reward weight
+    ///
+    /// Rewards accrued so far are settled unboosted. Only non-programmable NFTs with a
+    /// verified Metaplex collection can be escrowed.
+    pub fn attach_boost_nft(ctx: Context<AttachBoostNft>) -> Result<()> {
+        let pool = &ctx.accounts.pool;
+        let user_stake = &mut ctx.accounts.user_stake;
+        let clock = Clock::get()?;
+
+        require!(pool.nft_boost.is_enabled(), StakingError::NftBoostDisabled);
+        require!(!pool.is_paused(PAUSE_STAKE), StakingError::PoolPaused);
+        require!(
+            user_stake.boost_mint == Pubkey::default(),
+            StakingError::BoostNftAttached
+        );
+        let collection = verified_collection(&ctx.accounts.nft_metadata.try_borrow_data()?)?;
+        require!(
+            collection == Some(pool.nft_boost.collection),
+            StakingError::InvalidBoostNft
+        );
+
+        let accrual_time = pool.accrual_time(clock.unix_timestamp);
+        accrue_rewards(pool, user_stake, accrual_time)?;
+        user_stake.last_accrual_time = accrual_time;
+        user_stake.boost_mint = ctx.accounts.nft_mint.key();
+
+        anchor_spl::token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.nft_token_program.to_account_info(),
+                anchor_spl::token_interface::TransferChecked {
+                    from: ctx.accounts.user_nft_account.to_account_info(),
+                    mint: ctx.accounts.nft_mint.to_account_info(),
+                    to: ctx.accounts.boost_escrow.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            1,
+            0,
+        )?;
+
+        emit!(BoostNftAttached {
+            pool: pool.key(),
+            user: ctx.accounts.user.key(),
+            nft_mint: user_stake.boost_mint,
+            multiplier_bps: pool.nft_boost.multiplier_bps,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Return the caller's escrowed boost NFT, settling the boosted rewards accrued so far
+    ///
+    /// Not gated by pause flags or the boost being enabled so NFTs can always be recovered.
+    pub fn detach_boost_nft(ctx: Context<DetachBoostNft>) -> Result<()> {
+        let pool = &ctx.accounts.pool;
+        let user_stake = &mut ctx.accounts.user_stake;
+        let clock = Clock::get()?;
+
+        let accrual_time = pool.accrual_time(clock.unix_timestamp);
+        accrue_rewards(pool, user_stake, accrual_time)?;
+        user_stake.last_accrual_time = accrual_time;
+        user_stake.boost_mint = Pubkey::default();
+
+        let staking_mint = pool.staking_mint;
+        let seeds = &[
+            b"pool".as_ref(),
+            staking_mint.as_ref(),
+            &[pool.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        anchor_spl::token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.nft_token_program.to_account_info(),
+                anchor_spl::token_interface::TransferChecked {
+                    from: ctx.accounts.boost_escrow.to_account_info(),
+                    mint: ctx.accounts.nft_mint.to_account_info(),
+                    to: ctx.accounts.user_nft_account.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                signer,
+            ),
+            1,
+            0,
+        )?;
+        anchor_spl::token_interface::close_account(CpiContext::new_with_signer(
+            ctx.accounts.nft_token_program.to_account_info(),
+            anchor_spl::token_interface::CloseAccount {
+                account: ctx.accounts.boost_escrow.to_account_info(),
+                destination: ctx.accounts.user.to_account_info(),
+                authority: pool.to_account_info(),
+            },
+            signer,
+        ))?;
+
+        emit!(BoostNftDetached {
+            pool: pool.key(),
+            user: ctx.accounts.user.key(),
+            nft_mint: ctx.accounts.nft_mint.key(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Move the caller's whole position, pending rewards and lock progress to `new_owner`
+    ///
+    /// If `new_owner` already has a position the two are merged, keeping the later
+    /// stake start so neither lock is shortened.
+    pub fn transfer_stake(ctx: Context<TransferStake>, new_owner: Pubkey) -> Result<()> {
+        init_checkpoints(
+            &mut ctx.accounts.user_checkpoints,
+            ctx.accounts.pool.key(),
+            ctx.accounts.user.key(),
+            ctx.bumps.user_checkpoints,
+            ctx.accounts.user_stake.staked_amount,
+        );
+        let pool = &mut ctx.accounts.pool;
+        let clock = Clock::get()?;
+
+        require!(!pool.transfers_disabled, StakingError::TransfersDisabled);
+        require!(
+            !pool.is_paused(PAUSE_STAKE | PAUSE_UNSTAKE),
+            StakingError::PoolPaused
+        );
+        require!(
+            new_owner != ctx.accounts.user.key() && new_owner != Pubkey::default(),
+            StakingError::InvalidOwner
+        );
+
+        require!(
+            ctx.accounts.user_stake.staked_amount > 0,
+            StakingError::InsufficientStake
+        );
+
+        let destination = &mut ctx.accounts.destination_stake;
+        if destination.owner == Pubkey::default() {
+            init_position(destination, new_owner, pool.key(), ctx.bumps.destination_stake);
+        }
+        let (amount, pending_rewards) = move_position(
+            pool,
+            &mut ctx.accounts.user_stake,
+            destination,
+            clock.unix_timestamp,
+        )?;
+        pool.last_update_time = clock.unix_timestamp;
+
+        init_checkpoints(
+            &mut ctx.accounts.destination_checkpoints,
+            pool.key(),
+            new_owner,
+            ctx.bumps.destination_checkpoints,
+            destination.staked_amount - amount,
+        );
+        ctx.accounts
+            .destination_checkpoints
+            .record(destination.staked_amount, &clock);
+        ctx.accounts
+            .user_checkpoints
+            .record(ctx.accounts.user_stake.staked_amount, &clock);
+
+        emit!(StakeTransferred {
+            pool: pool.key(),
+            from: ctx.accounts.user.key(),
+            to: new_owner,
+            amount,
+            pending_rewards,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Move the caller's whole position into a new position NFT minted to the caller
+    ///
+    /// The NFT is a Token-2022 mint with on-chain metadata; whoever holds it can
+    /// unstake, claim or redeem the position.
+    pub fn tokenize_stake(ctx: Context<TokenizeStake>) -> Result<()> {
+        let clock = Clock::get()?;
+        {
+            let pool = &ctx.accounts.pool;
+            require!(!pool.transfers_disabled, StakingError::TransfersDisabled);
+            require!(
+                !pool.is_paused(PAUSE_STAKE | PAUSE_UNSTAKE),
+                StakingError::PoolPaused
+            );
+            require!(
+                ctx.accounts.user_stake.staked_amount > 0,
+                StakingError::InsufficientStake
+            );
+        }
+
+        init_checkpoints(
+            &mut ctx.accounts.user_checkpoints,
+            ctx.accounts.pool.key(),
+            ctx.accounts.user.key(),
+            ctx.bumps.user_checkpoints,
+            ctx.accounts.user_stake.staked_amount,
+        );
+        let position_mint = ctx.accounts.position_mint.key();
+        init_position(
+            &mut ctx.accounts.position_stake,
+            position_mint,
+            ctx.accounts.pool.key(),
+            ctx.bumps.position_stake,
+        );
+        let (amount, _) = move_position(
+            &mut ctx.accounts.pool,
+            &mut ctx.accounts.user_stake,
+            &mut ctx.accounts.position_stake,
+            clock.unix_timestamp,
+        )?;
+        ctx.accounts
+            .user_checkpoints
+            .record(ctx.accounts.user_stake.staked_amount, &clock);
+
+        let pool = &ctx.accounts.pool;
+        let staking_mint_key = pool.staking_mint;
+        let seeds = &[
+            b"pool".as_ref(),
+            staking_mint_key.as_ref(),
+            &[pool.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        // Fund the mint for metadata sized to the widest field values, so later
+        // field updates never need more rent
+        let metadata = TokenMetadata {
+            update_authority: OptionalNonZeroPubkey(pool.key()),
+            mint: position_mint,
+            name: POSITION_NAME.to_string(),
+            symbol: POSITION_SYMBOL.to_string(),
+            uri: String::new(),
+            additional_metadata: vec![
+                (POSITION_STAKED_AMOUNT_KEY.to_string(), u64::MAX.to_string()),
+                (POSITION_UNLOCK_TIME_KEY.to_string(), i64::MIN.to_string()),
+            ],
+        };
+        let mint_info = ctx.accounts.position_mint.to_account_info();
+        let required_len = mint_info
+            .data_len()
+            .checked_add(metadata.tlv_size_of()?)
+            .ok_or(StakingError::MathOverflow)?;
+        let required_lamports = Rent::get()?
+            .minimum_balance(required_len)
+            .saturating_sub(mint_info.lamports());
+        if required_lamports > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.user.to_account_info(),
+                        to: mint_info.clone(),
+                    },
+                ),
+                required_lamports,
+            )?;
+        }
+
+        let token_program = ctx.accounts.position_token_program.to_account_info();
+        token_metadata_initialize(
+            CpiContext::new_with_signer(
+                token_program.clone(),
+                TokenMetadataInitialize {
+                    program_id: token_program.clone(),
+                    metadata: mint_info.clone(),
+                    update_authority: pool.to_account_info(),
+                    mint_authority: pool.to_account_info(),
+                    mint: mint_info.clone(),
+                },
+                signer,
+            ),
+            POSITION_NAME.to_string(),
+            POSITION_SYMBOL.to_string(),
+            String::new(),
+        )?;
+        update_position_metadata(pool, mint_info.clone(), token_program.clone(), &ctx.accounts.position_stake)?;
+
+        // Mint the single position token, then drop the mint authority for a fixed supply
+        anchor_spl::token_interface::mint_to(
+            CpiContext::new_with_signer(
+                token_program.clone(),
+                anchor_spl::token_interface::MintTo {
+                    mint: mint_info.clone(),
+                    to: ctx.accounts.user_position_account.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                signer,
+            ),
+            1,
+        )?;
+        anchor_spl::token_interface::set_authority(
+            CpiContext::new_with_signer(
+                token_program,
+                anchor_spl::token_interface::SetAuthority {
+                    current_authority: pool.to_account_info(),
+                    account_or_mint: mint_info,
+                },
+                signer,
+            ),
+            AuthorityType::MintTokens,
+            None,
+        )?;
+
+        emit!(StakeTokenized {
+            pool: pool.key(),
+            owner: ctx.accounts.user.key(),
+            position_mint,
+            amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Unstake tokens from a tokenized position (position NFT holder only)
+    ///
+    /// Emergency mode lifts `PAUSE_UNSTAKE` but not the lock; `redeem_position` followed by
+    /// `emergency_withdraw` skips both. In oracle rate mode the pool's price account must be
+    /// the first remaining account.
+    pub fn unstake_position(ctx: Context<UnstakePosition>, amount: u64) -> Result<()> {
+        let clock = Clock::get()?;
+        refresh_oracle_rate(
+            &mut ctx.accounts.pool,
+            ctx.remaining_accounts,
+            clock.unix_timestamp,
+        )?;
+        let pool = &mut ctx.accounts.pool;
+        let position_stake = &mut ctx.accounts.position_stake;
+
+        require!(!pool.unstake_paused(), StakingError::PoolPaused);
+        debit_stake(pool, position_stake, amount, clock.unix_timestamp)?;
+        record_pool_total(
+            pool,
+            &mut ctx.accounts.pool_checkpoints,
+            &mut ctx.accounts.next_pool_checkpoints,
+            &clock,
+        );
+
+        // Transfer tokens to the holder using PDA signer
+        let staking_mint_key = pool.staking_mint;
+        let seeds = &[
+            b"pool".as_ref(),
+            staking_mint_key.as_ref(),
+            &[pool.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        let fee = fee_for(amount, pool.fees.withdrawal_bps);
+        let cpi_accounts = anchor_spl::token_interface::TransferChecked {
+            from: ctx.accounts.pool_vault.to_account_info(),
+            mint: ctx.accounts.staking_mint.to_account_info(),
+            to: ctx.accounts.holder_token_account.to_account_info(),
+            authority: pool.to_account_info(),
+        };
+        let decimals = ctx.accounts.staking_mint.decimals;
+        anchor_spl::token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.staking_token_program.to_account_info(),
+                cpi_accounts,
+                signer,
+            ),
+            amount - fee,
+            decimals,
+        )?;
+        pay_staking_fee(
+            ctx.accounts.pool_vault.to_account_info(),
+            pool.to_account_info(),
+            ctx.accounts.staking_treasury.as_ref().map(|treasury| treasury.to_account_info()),
+            &ctx.accounts.staking_mint,
+            ctx.accounts.staking_token_program.to_account_info(),
+            signer,
+            fee,
+        )?;
+
+        update_position_metadata(
+            pool,
+            ctx.accounts.position_mint.to_account_info(),
+            ctx.accounts.position_token_program.to_account_info(),
+            position_stake,
+        )?;
+
+        emit!(UnstakeEvent {
+            user: ctx.accounts.holder.key(),
+            pool: pool.key(),
+            amount,
+            remaining_stake: position_stake.staked_amount,
+            timestamp: clock.unix_timestamp,
+            fee,
+        });
+
+        Ok(())
+    }
+
+    /// Claim accumulated rewards of a tokenized position (position NFT holder only)
+    ///
+    /// In oracle rate mode the pool's price account must be the first remaining account.
+    pub fn claim_position_rewards(ctx: Context<ClaimPositionRewards>) -> Result<()> {
+        let clock = Clock::get()?;
+        refresh_oracle_rate(
+            &mut ctx.accounts.pool,
+            ctx.remaining_accounts,
+            clock.unix_timestamp,
+        )?;
+        let pool = &ctx.accounts.pool;
+        let position_stake = &mut ctx.accounts.position_stake;
+
+        require!(!pool.is_paused(PAUSE_CLAIM), StakingError::PoolPaused);
+        let total_rewards = take_rewards(pool, position_stake, clock.unix_timestamp)?;
+
+        // Check reward vault balance, excluding rewards reserved for distributor epochs
+        require!(
+            pool.available_rewards(ctx.accounts.reward_vault.amount) >= total_rewards,
+            StakingError::InsufficientRewardBalance
+        );
+
+        // Transfer rewards using PDA signer
+        let staking_mint = pool.staking_mint;
+        let seeds = &[
+            b"pool".as_ref(),
+            staking_mint.as_ref(),
+            &[pool.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        let fee = fee_for(total_rewards, pool.fees.performance_bps);
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.reward_vault.to_account_info(),
+            to: ctx.accounts.holder_reward_account.to_account_info(),
+            authority: pool.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer,
+            ),
+            total_rewards - fee,
+        )?;
+        pay_reward_fee(
+            pool,
+            ctx.accounts.reward_vault.to_account_info(),
+            ctx.accounts.reward_treasury.as_ref().map(|treasury| treasury.to_account_info()),
+            ctx.accounts.token_program.to_account_info(),
+            signer,
+            fee,
+        )?;
+
+        emit!(ClaimEvent {
+            user: ctx.accounts.holder.key(),
+            pool: pool.key(),
+            amount: total_rewards,
+            timestamp: clock.unix_timestamp,
+            fee,
+        });
+
+        Ok(())
+    }
+
+    /// Burn a position NFT and move its position back into the holder's wallet stake
+    ///
+    /// Not gated by pause flags so holders can always reach `emergency_withdraw`.
+    pub fn redeem_position(ctx: Context<RedeemPosition>) -> Result<()> {
+        let clock = Clock::get()?;
+
+        let holder = ctx.accounts.holder.key();
+        if ctx.accounts.user_stake.owner == Pubkey::default() {
+            init_position(
+                &mut ctx.accounts.user_stake,
+                holder,
+                ctx.accounts.pool.key(),
+                ctx.bumps.user_stake,
+            );
+        }
+        let (amount, _) = move_position(
+            &mut ctx.accounts.pool,
+            &mut ctx.accounts.position_stake,
+            &mut ctx.accounts.user_stake,
+            clock.unix_timestamp,
+        )?;
+        init_checkpoints(
+            &mut ctx.accounts.user_checkpoints,
+            ctx.accounts.pool.key(),
+            holder,
+            ctx.bumps.user_checkpoints,
+            ctx.accounts.user_stake.staked_amount - amount,
+        );
+        ctx.accounts
+            .user_checkpoints
+            .record(ctx.accounts.user_stake.staked_amount, &clock);
+
+        anchor_spl::token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.position_token_program.to_account_info(),
+                anchor_spl::token_interface::Burn {
+                    mint: ctx.accounts.position_mint.to_account_info(),
+                    from: ctx.accounts.holder_position_account.to_account_info(),
+                    authority: ctx.accounts.holder.to_account_info(),
+                },
+            ),
+            1,
+        )?;
+
+        emit!(PositionRedeemed {
+            pool: ctx.accounts.pool.key(),
+            owner: holder,
+            position_mint: ctx.accounts.position_mint.key(),
+            amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Enable liquid staking by creating the pool's receipt mint (admin only)
+    ///
+    /// Liquid stakes share a single pool-owned position whose rewards are compounded
+    /// back into stake, so the pool must pay rewards in the staking token.
+    pub fn initialize_liquid_staking(ctx: Context<InitializeLiquidStaking>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        require!(
+            pool.receipt_mint == Pubkey::default(),
+            StakingError::LiquidStakingAlreadyEnabled
+        );
+        require!(
+            pool.reward_mint == pool.staking_mint,
+            StakingError::LiquidStakingRequiresSameMint
+        );
+
+        let receipt_mint = ctx.accounts.receipt_mint.key();
+        pool.receipt_mint = receipt_mint;
+        init_position(
+            &mut ctx.accounts.liquid_stake,
+            receipt_mint,
+            pool.key(),
+            ctx.bumps.liquid_stake,
+        );
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.liquid_stake.last_accrual_time = pool.accrual_time(now);
+
+        emit!(LiquidStakingInitialized {
+            pool: pool.key(),
+            receipt_mint,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Stake tokens into the liquid position and mint receipt tokens at the current exchange rate
+    ///
+    /// The exchange rate is the liquid position's `staked_amount` over the receipt supply.
+    /// Rewards still pending (unfunded, or accrued while compounding is paused) are not part
+    /// of it until they are compounded. Liquid deposits are not subject to the pool's tiers.
+    /// The deposit fee is taken out of `amount` and receipts are minted for the rest. In
+    /// oracle rate mode the pool's price account must be the first remaining account.
+    pub fn liquid_stake(ctx: Context<LiquidStake>, amount: u64) -> Result<()> {
+        require!(amount > 0, StakingError::InvalidAmount);
+        require!(!ctx.accounts.pool.is_paused(PAUSE_STAKE), StakingError::PoolPaused);
+        let clock = Clock::get()?;
+        refresh_oracle_rate(
+            &mut ctx.accounts.pool,
+            ctx.remaining_accounts,
+            clock.unix_timestamp,
+        )?;
+        let fee = fee_for(amount, ctx.accounts.pool.fees.deposit_bps);
+        let staked = amount - fee;
+
+        compound_liquid_rewards(
+            &mut ctx.accounts.pool,
+            &mut ctx.accounts.liquid_stake,
+            &ctx.accounts.reward_vault,
+            ctx.accounts.pool_vault.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            clock.unix_timestamp,
+        )?;
+
+        let receipts = receipts_for_deposit(
+            staked,
+            ctx.accounts.liquid_stake.staked_amount,
+            ctx.accounts.receipt_mint.supply,
+        )?;
+        require!(receipts > 0, StakingError::InvalidAmount);
+
+        // Transfer tokens to vault using token interface
+        let cpi_accounts = anchor_spl::token_interface::TransferChecked {
+            from: ctx.accounts.user_token_account.to_account_info(),
+            mint: ctx.accounts.staking_mint.to_account_info(),
+            to: ctx.accounts.pool_vault.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        };
+        let decimals = ctx.accounts.staking_mint.decimals;
+        anchor_spl::token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.staking_token_program.to_account_info(),
+                cpi_accounts,
+            ),
+            staked,
+            decimals,
+        )?;
+        pay_staking_fee(
+            ctx.accounts.user_token_account.to_account_info(),
+            ctx.accounts.user.to_account_info(),
+            ctx.accounts.staking_treasury.as_ref().map(|treasury| treasury.to_account_info()),
+            &ctx.accounts.staking_mint,
+            ctx.accounts.staking_token_program.to_account_info(),
+            &[],
+            fee,
+        )?;
+
+        // Mint receipts using PDA signer
+        let pool = &mut ctx.accounts.pool;
+        let staking_mint_key = pool.staking_mint;
+        let seeds = &[
+            b"pool".as_ref(),
+            staking_mint_key.as_ref(),
+            &[pool.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        anchor_spl::token_interface::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.receipt_token_program.to_account_info(),
+                anchor_spl::token_interface::MintTo {
+                    mint: ctx.accounts.receipt_mint.to_account_info(),
+                    to: ctx.accounts.user_receipt_account.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                signer,
+            ),
+            receipts,
+        )?;
+
+        let liquid_stake = &mut ctx.accounts.liquid_stake;
+        pool.update_campaigns(clock.unix_timestamp)?;
+        pool.campaigns.excluded_stake = pool
+            .campaigns
+            .excluded_stake
+            .checked_add(staked)
+            .ok_or(StakingError::MathOverflow)?;
+        liquid_stake.staked_amount = liquid_stake
+            .staked_amount
+            .checked_add(staked)
+            .ok_or(StakingError::MathOverflow)?;
+        pool.total_staked = pool
+            .total_staked
+            .checked_add(staked)
+            .ok_or(StakingError::MathOverflow)?;
+        pool.last_update_time = clock.unix_timestamp;
+
+        let receipt_supply = ctx
+            .accounts
+            .receipt_mint
+            .supply
+            .checked_add(receipts)
+            .ok_or(StakingError::MathOverflow)?;
+
+        record_pool_total(
+            pool,
+            &mut ctx.accounts.pool_checkpoints,
+            &mut ctx.accounts.next_pool_checkpoints,
+            &clock,
+        );
+
+        emit!(LiquidStakeEvent {
+            user: ctx.accounts.user.key(),
+            pool: pool.key(),
+            amount,
+            receipts,
+            total_underlying: liquid_stake.staked_amount,
+            receipt_supply,
+            timestamp: clock.unix_timestamp,
+            fee,
+        });
+
+        Ok(())
+    }
+
+    /// Burn receipt tokens and withdraw their share of the liquid position
+    ///
+    /// Receipts are fungible, so liquid stakes are not subject to `lock_period`, and the
+    /// remaining liquid position is not held to the tiers' minimum stake. Emergency mode
+    /// lifts `PAUSE_UNSTAKE`, as this is the only exit for receipt holders. The withdrawal
+    /// fee is taken out of the withdrawn amount. In oracle rate mode the pool's price account
+    /// must be the first remaining account.
+    pub fn liquid_unstake(ctx: Context<LiquidStake>, receipts: u64) -> Result<()> {
+        require!(receipts > 0, StakingError::InvalidAmount);
+        require!(!ctx.accounts.pool.unstake_paused(), StakingError::PoolPaused);
+        let clock = Clock::get()?;
+        refresh_oracle_rate(
+            &mut ctx.accounts.pool,
+            ctx.remaining_accounts,
+            clock.unix_timestamp,
+        )?;
+
+        compound_liquid_rewards(
+            &mut ctx.accounts.pool,
+            &mut ctx.accounts.liquid_stake,
+            &ctx.accounts.reward_vault,
+            ctx.accounts.pool_vault.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            clock.unix_timestamp,
+        )?;
+
+        let receipt_supply = ctx.accounts.receipt_mint.supply;
+        let amount = underlying_for_receipts(
+            receipts,
+            ctx.accounts.liquid_stake.staked_amount,
+            receipt_supply,
+        )?;
+        require!(amount > 0, StakingError::InvalidAmount);
+
+        anchor_spl::token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.receipt_token_program.to_account_info(),
+                anchor_spl::token_interface::Burn {
+                    mint: ctx.accounts.receipt_mint.to_account_info(),
+                    from: ctx.accounts.user_receipt_account.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            receipts,
+        )?;
+
+        // Transfer tokens back to user using PDA signer
+        let pool = &mut ctx.accounts.pool;
+        let staking_mint_key = pool.staking_mint;
+        let seeds = &[
+            b"pool".as_ref(),
+            staking_mint_key.as_ref(),
+            &[pool.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        let fee = fee_for(amount, pool.fees.withdrawal_bps);
+        let cpi_accounts = anchor_spl::token_interface::TransferChecked {
+            from: ctx.accounts.pool_vault.to_account_info(),
+            mint: ctx.accounts.staking_mint.to_account_info(),
+            to: ctx.accounts.user_token_account.to_account_info(),
+            authority: pool.to_account_info(),
+        };
+        let decimals = ctx.accounts.staking_mint.decimals;
+        anchor_spl::token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.staking_token_program.to_account_info(),
+                cpi_accounts,
+                signer,
+            ),
+            amount - fee,
+            decimals,
+        )?;
+        pay_staking_fee(
+            ctx.accounts.pool_vault.to_account_info(),
+            pool.to_account_info(),
+            ctx.accounts.staking_treasury.as_ref().map(|treasury| treasury.to_account_info()),
+            &ctx.accounts.staking_mint,
+            ctx.accounts.staking_token_program.to_account_info(),
+            signer,
+            fee,
+        )?;
+
+        let liquid_stake = &mut ctx.accounts.liquid_stake;
+        pool.update_campaigns(clock.unix_timestamp)?;
+        pool.campaigns.excluded_stake = pool
+            .campaigns
+            .excluded_stake
+            .checked_sub(amount)
+            .ok_or(StakingError::MathOverflow)?;
+        liquid_stake.staked_amount = liquid_stake
+            .staked_amount
+            .checked_sub(amount)
+            .ok_or(StakingError::MathOverflow)?;
+        pool.total_staked = pool
+            .total_staked
+            .checked_sub(amount)
+            .ok_or(StakingError::MathOverflow)?;
+        pool.last_update_time = clock.unix_timestamp;
+
+        record_pool_total(
+            pool,
+            &mut ctx.accounts.pool_checkpoints,
+            &mut ctx.accounts.next_pool_checkpoints,
+            &clock,
+        );
+
+        emit!(LiquidUnstakeEvent {
+            user: ctx.accounts.user.key(),
+            pool: pool.key(),
+            amount,
+            receipts,
+            total_underlying: liquid_stake.staked_amount,
+            receipt_supply: receipt_supply
+                .checked_sub(receipts)
+                .ok_or(StakingError::MathOverflow)?,
+            timestamp: clock.unix_timestamp,
+            fee,
+        });
+
+        Ok(())
+    }
+
+    /// Compound the liquid position's rewards into stake, growing the exchange rate (anyone can call)
+    ///
+    /// In oracle rate mode the pool's price account must be the first remaining account.
+    pub fn compound_liquid(ctx: Context<CompoundLiquid>) -> Result<()> {
+        require!(!ctx.accounts.pool.is_paused(PAUSE_COMPOUND), StakingError::PoolPaused);
+        let clock = Clock::get()?;
+        refresh_oracle_rate(
+            &mut ctx.accounts.pool,
+            ctx.remaining_accounts,
+            clock.unix_timestamp,
+        )?;
+
+        let amount = compound_liquid_rewards(
+            &mut ctx.accounts.pool,
+            &mut ctx.accounts.liquid_stake,
+            &ctx.accounts.reward_vault,
+            ctx.accounts.pool_vault.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            clock.unix_timestamp,
+        )?;
+        require!(amount > 0, StakingError::NoRewards);
+        record_pool_total(
+            &mut ctx.accounts.pool,
+            &mut ctx.accounts.pool_checkpoints,
+            &mut ctx.accounts.next_pool_checkpoints,
+            &clock,
+        );
+
+        Ok(())
+    }
+
+    /// Close an empty stake account and return its rent to the owner
+    ///
+    /// Referral rewards the position accrued for its referrer but nobody claimed yet are
+    /// forfeited and stay in the reward vault. The wallet's balance history is closed too
+    /// when passed, giving up its voting power for proposals drafted while it was staked;
+    /// staking again starts a new history.
+    pub fn close_user_stake(ctx: Context<CloseUserStake>) -> Result<()> {
+        let user_stake = &ctx.accounts.user_stake;
+
+        require!(
+            user_stake.staked_amount == 0 && user_stake.pending_rewards == 0,
+            StakingError::StakeNotEmpty
+        );
+        require!(
+            user_stake.boost_mint == Pubkey::default(),
+            StakingError::BoostNftAttached
+        );
+        require!(
+            !user_stake.has_campaign_rewards(&ctx.accounts.pool.campaigns),
+            StakingError::StakeNotEmpty
+        );
+
+        emit!(UserStakeClosed {
+            user: ctx.accounts.user.key(),
+            pool: ctx.accounts.pool.key(),
+            total_rewards_claimed: user_stake.total_rewards_claimed,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Claimable rewards of `user`'s position right now (view, returned via return data)
+    pub fn get_pending_rewards(ctx: Context<ViewUserStake>, _user: Pubkey) -> Result<u64> {
+        let pool = &ctx.accounts.pool;
+        let now = Clock::get()?.unix_timestamp;
+        claimable_rewards(pool, &ctx.accounts.user_stake, pool.accrual_time(now))
+    }
+
+    /// Pool configuration and balances (view, returned via return data)
+    pub fn get_pool_stats(ctx: Context<ViewPool>) -> Result<PoolStats> {
+        let pool = &ctx.accounts.pool;
+        let now = Clock::get()?.unix_timestamp;
+
+        Ok(PoolStats {
+            total_staked: pool.total_staked,
+            reward_rate: pool.reward_rate,
+            lock_period: pool.lock_period,
+            min_stake_amount: pool.tiers.min_stake(),
+            pause_flags: pool.pause_flags,
+            emergency_mode: pool.emergency_mode,
+            pool_vault_balance: ctx.accounts.pool_vault.amount,
+            reward_vault_balance: ctx.accounts.reward_vault.amount,
+            accrual_time: pool.accrual_time(now),
+            timestamp: now,
+        })
+    }
+
+    /// Full state of `user`'s position with rewards settled to now (view, returned via return data)
+    pub fn get_user_position(ctx: Context<ViewUserStake>, _user: Pubkey) -> Result<UserPosition> {
+        let pool = &ctx.accounts.pool;
+        let user_stake = &ctx.accounts.user_stake;
+        let now = Clock::get()?.unix_timestamp;
+
+        Ok(UserPosition {
+            owner: user_stake.owner,
+            staked_amount: user_stake.staked_amount,
+            pending_rewards: claimable_rewards(pool, user_stake, pool.accrual_time(now))?,
+            stake_start_time: user_stake.stake_start_time,
+            unlock_time: user_stake.unlock_time(pool.lock_period),
+            total_rewards_claimed: user_stake.total_rewards_claimed,
+            tier: user_stake.tier,
+            claim_delegate: user_stake.claim_delegate,
+            reward_destination: user_stake.reward_destination,
+            timestamp: now,
+        })
+    }
+
+    /// A wallet's staked balance at the start of `timestamp`, e.g. for governance snapshots
+    ///
+    /// Changes made during `timestamp` itself are excluded, so a snapshot taken when a
+    /// proposal is created can't be flash-staked. Tokenized and liquid stakes don't count.
+    /// Before the wallet's last `CHECKPOINT_CAPACITY` changes this is a lower bound.
+    pub fn get_stake_at(ctx: Context<ViewStakeAt>, _user: Pubkey, timestamp: i64) -> Result<u64> {
+        ctx.accounts.user_checkpoints.amount_at(timestamp)
+    }
+
+    /// The pool's total staked at the start of `timestamp` (see `get_stake_at`)
+    ///
+    /// `page` is the ring page holding the last change before `timestamp`, found by binary
+    /// searching the pages' first checkpoints in `sequence` order.
+    pub fn get_pool_stake_at(
+        ctx: Context<ViewPoolStakeAt>,
+        timestamp: i64,
+        _page: u32,
+    ) -> Result<u64> {
+        ctx.accounts.pool_checkpoints.amount_at(
+            timestamp,
+            ctx.accounts.following_checkpoints.as_deref(),
+            ctx.accounts.pool.checkpoint_ring.sequence,
+        )
+    }
+
+    /// Add a page to the pool total's checkpoint ring (admin only)
+    ///
+    /// The ring keeps the last `pages * CHECKPOINT_PAGE_CAPACITY` changes of the total, at
+    /// most one per slot; add pages until that covers the longest governance window.
+    pub fn add_checkpoint_page(ctx: Context<AddCheckpointPage>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        let pool_key = pool.key();
+        let index = pool.checkpoint_ring.pages;
+        init_checkpoint_page(&mut ctx.accounts.page, pool_key, index, ctx.bumps.page);
+        pool.checkpoint_ring.add_page()?;
+
+        emit!(CheckpointPageAdded {
+            pool: pool_key,
+            pages: pool.checkpoint_ring.pages,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Let a Realms DAO use this pool's stakes as voting power (pool and realm authority)
+    ///
+    /// Voter weight is the wallet's stake (for votes, as of the proposal's creation; see
+    /// `update_voter_weight_record`), boosted by up to `max_lock_boost_bps` while its
+    /// lock has `lock_boost_saturation` seconds or more left, scaling linearly below that.
+    pub fn set_voter_weight_config(
+        ctx: Context<SetVoterWeightConfig>,
+        governing_token_mint: Pubkey,
+        max_lock_boost_bps: u16,
+        lock_boost_saturation: i64,
+    ) -> Result<()> {
+        require!(lock_boost_saturation >= 0, StakingError::InvalidVoterWeightConfig);
+
+        let realm = RealmInfo::from_account_data(&ctx.accounts.realm.try_borrow_data()?)?;
+        require!(
+            realm.authority == Some(ctx.accounts.realm_authority.key()),
+            StakingError::Unauthorized
+        );
+        require!(
+            governing_token_mint == realm.community_mint
+                || Some(governing_token_mint) == realm.council_mint,
+            StakingError::InvalidMint
+        );
+
+        let pool = &mut ctx.accounts.pool;
+        pool.voter_weight = VoterWeightConfig {
+            realm: ctx.accounts.realm.key(),
+            governing_token_mint,
+            max_lock_boost_bps,
+            lock_boost_saturation,
+        };
+
+        emit!(VoterWeightConfigSet {
+            pool: pool.key(),
+            realm: pool.voter_weight.realm,
+            governing_token_mint,
+            max_lock_boost_bps,
+            lock_boost_saturation,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Stop counting this pool's stakes as voting power (admin only)
+    pub fn disable_voter_weight(ctx: Context<AdminAction>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        require!(pool.voter_weight.is_enabled(), StakingError::VoterWeightDisabled);
+        pool.voter_weight = VoterWeightConfig::default();
+
+        emit!(VoterWeightConfigSet {
+            pool: pool.key(),
+            realm: Pubkey::default(),
+            governing_token_mint: Pubkey::default(),
+            max_lock_boost_bps: 0,
+            lock_boost_saturation: 0,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Create or refresh `voter`'s spl-governance `VoterWeightRecord` for `action` on `target`
+    /// (anyone can call)
+    ///
+    /// Votes weigh the stake held when the proposal `target` was created, read from the
+    /// voter's checkpoints, so tokens transferred or restaked afterwards can't vote on it
+    /// again; the proposal account must be passed. Other actions weigh the stake held at the
+    /// start of the current second. The record expires at the current slot, so it must be
+    /// refreshed in the same transaction as the governance action that uses it.
+    pub fn update_voter_weight_record(
+        ctx: Context<UpdateVoterWeightRecord>,
+        voter: Pubkey,
+        action: VoterWeightAction,
+        target: Option<Pubkey>,
+    ) -> Result<()> {
+        let pool = &ctx.accounts.pool;
+        let user_stake = &ctx.accounts.user_stake;
+        let clock = Clock::get()?;
+
+        let snapshot_time = if action == VoterWeightAction::CastVote {
+            let proposal = ctx
+                .accounts
+                .proposal
+                .as_ref()
+                .ok_or(StakingError::InvalidProposal)?;
+            require!(
+                Some(proposal.key()) == target,
+                StakingError::InvalidProposal
+            );
+            let proposal = ProposalInfo::from_account_data(&proposal.try_borrow_data()?)?;
+            require!(
+                proposal.governing_token_mint == pool.voter_weight.governing_token_mint,
+                StakingError::InvalidMint
+            );
+            proposal.draft_at
+        } else {
+            clock.unix_timestamp
+        };
+        let voter_weight = pool.voter_weight.voter_weight(
+            ctx.accounts.user_checkpoints.amount_at(snapshot_time)?,
+            user_stake.unlock_time(pool.lock_period),
+            clock.unix_timestamp,
+        )?;
+
+        let record = &mut ctx.accounts.voter_weight_record;
+        record.realm = pool.voter_weight.realm;
+        record.governing_token_mint = pool.voter_weight.governing_token_mint;
+        record.governing_token_owner = voter;
+        record.voter_weight = voter_weight;
+        record.voter_weight_expiry = Some(clock.slot);
+        record.weight_action = Some(action);
+        record.weight_action_target = target;
+
+        Ok(())
+    }
+
+    /// Update pool reward rate (admin only)
+    pub fn update_reward_rate(ctx: Context<AdminAction>, new_rate: RewardRate) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        let old_rate = pool.reward_rate;
+        let now = Clock::get()?.unix_timestamp;
+        pool.set_reward_rate(new_rate, now);
+
+        emit!(RewardRateUpdated {
+            pool: pool.key(),
+            old_rate,
+            new_rate,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Update pool reward rate from "`reward_amount` per `per_staked_amount` per `period_seconds`" (admin only)
+    ///
+    /// All amounts are in base units, e.g. 0.10 USDC per 1M 6-decimal tokens per 5 minutes
+    /// is `(100_000, 1_000_000_000_000, 300)`.
+    pub fn update_reward_rate_per_period(
+        ctx: Context<AdminAction>,
+        reward_amount: u64,
+        per_staked_amount: u64,
+        period_seconds: u64,
+    ) -> Result<()> {
+        let new_rate = RewardRate::per_period(reward_amount, per_staked_amount, period_seconds)
+            .ok_or(StakingError::InvalidRewardRate)?;
+        update_reward_rate(ctx, new_rate)
+    }
+
+    /// Enable oracle rate mode, targeting `target_apr_bps` APR on the USD value staked (admin only)
+    ///
+    /// The price account quotes the staking token in the reward token's unit (e.g. USD for
+    /// USDC rewards). Refreshes move the rate towards the target by at most
+    /// `max_rate_change_bps` per `RATE_CHANGE_PERIOD` between price updates, and only for
+    /// prices younger than `max_staleness` seconds whose confidence interval is within
+    /// `max_confidence_bps` of the price.
+    pub fn set_rate_oracle(
+        ctx: Context<SetRateOracle>,
+        target_apr_bps: u32,
+        max_staleness: i64,
+        max_confidence_bps: u16,
+        max_rate_change_bps: u16,
+    ) -> Result<()> {
+        require!(
+            target_apr_bps > 0
+                && max_staleness > 0
+                && max_confidence_bps <= BPS_DENOMINATOR
+                && max_rate_change_bps > 0
+                && max_rate_change_bps <= BPS_DENOMINATOR,
+            StakingError::InvalidOracleConfig
+        );
+        OraclePrice::from_price_update(&ctx.accounts.price_account.try_borrow_data()?)?;
+
+        let decimals_offset = ctx.accounts.reward_mint.decimals as i8
+            - ctx.accounts.staking_mint.decimals as i8;
+        let pool = &mut ctx.accounts.pool;
+        pool.rate_oracle = RateOracle {
+            price_account: ctx.accounts.price_account.key(),
+            target_apr_bps,
+            max_staleness,
+            max_confidence_bps,
+            max_rate_change_bps,
+            decimals_offset,
+            last_publish_time: 0,
+        };
+
+        emit!(RateOracleSet {
+            pool: pool.key(),
+            price_account: pool.rate_oracle.price_account,
+            target_apr_bps,
+            max_staleness,
+            max_confidence_bps,
+            max_rate_change_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Leave oracle rate mode, keeping the current rate (admin only)
+    pub fn disable_rate_oracle(ctx: Context<AdminAction>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        require!(pool.rate_oracle.is_enabled(), StakingError::OracleDisabled);
+        pool.rate_oracle = RateOracle::default();
+
+        emit!(RateOracleSet {
+            pool: pool.key(),
+            price_account: Pubkey::default(),
+            target_apr_bps: 0,
+            max_staleness: 0,
+            max_confidence_bps: 0,
+            max_rate_change_bps: 0,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Move the reward rate towards the oracle APR target (permissionless)
+    ///
+    /// Fails on stale or uncertain prices; a price already applied is a no-op.
+    pub fn refresh_reward_rate(ctx: Context<RefreshRewardRate>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let price = OraclePrice::from_price_update(&ctx.accounts.price_account.try_borrow_data()?)?;
+        ctx.accounts.pool.rate_oracle.check_price(&price, now)?;
+        apply_oracle_price(&mut ctx.accounts.pool, &price)
+    }
+
+    /// Pause/unpause every pool operation at once (admin only)
+    pub fn set_paused(ctx: Context<AdminAction>, paused: bool) -> Result<()> {
+        let flags = if paused { PAUSE_ALL_OPERATIONS } else { 0 };
+        let now = Clock::get()?.unix_timestamp;
+        let pool = &mut ctx.accounts.pool;
+        pool.set_pause_flags(flags, now)?;
+
+        emit!(PoolPausedEvent {
+            pool: pool.key(),
+            pause_flags: flags,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Set the exact set of paused operations (admin only)
+    ///
+    /// Including `PAUSE_ACCRUAL` stops rewards from accruing until it is cleared.
+    pub fn set_pause_flags(ctx: Context<AdminAction>, pause_flags: u8) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let pool = &mut ctx.accounts.pool;
+        pool.set_pause_flags(pause_flags, now)?;
+
+        emit!(PoolPausedEvent {
+            pool: pool.key(),
+            pause_flags,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Enable/disable emergency mode, which allows `emergency_withdraw` (admin only)
+    ///
+    /// Also lifts `PAUSE_UNSTAKE` for liquid and tokenized stake.
+    pub fn set_emergency_mode(ctx: Context<AdminAction>, enabled: bool) -> Result<()> {
+        ctx.accounts.pool.emergency_mode = enabled;
+
+        emit!(EmergencyModeSet {
+            pool: ctx.accounts.pool.key(),
+            enabled,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw the full stake while the pool is in emergency mode, forfeiting its rewards
+    ///
+    /// Skips the lock period, pause flags and reward settlement. Campaign shares earned so
+    /// far are settled, as they are paid from the campaigns' own vaults, and stay claimable.
+    pub fn emergency_withdraw(ctx: Context<Unstake>) -> Result<()> {
+        init_checkpoints(
+            &mut ctx.accounts.user_checkpoints,
+            ctx.accounts.pool.key(),
+            ctx.accounts.user.key(),
+            ctx.bumps.user_checkpoints,
+            ctx.accounts.user_stake.staked_amount,
+        );
+        let pool = &mut ctx.accounts.pool;
+        let user_stake = &mut ctx.accounts.user_stake;
+        let clock = Clock::get()?;
+
+        require!(pool.emergency_mode, StakingError::NotInEmergencyMode);
+
+        let amount = user_stake.staked_amount;
+        require!(amount > 0, StakingError::InsufficientStake);
+        let forfeited_rewards = user_stake.pending_rewards;
+
+        // Transfer the full stake back to user using PDA signer
+        let staking_mint_key = pool.staking_mint;
+        let seeds = &[
+            b"pool".as_ref(),
+            staking_mint_key.as_ref(),
+            &[pool.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = anchor_spl::token_interface::TransferChecked {
+            from: ctx.accounts.pool_vault.to_account_info(),
+            mint: ctx.accounts.staking_mint.to_account_info(),
+            to: ctx.accounts.user_token_account.to_account_info(),
+            authority: pool.to_account_info(),
+        };
+        let decimals = ctx.accounts.staking_mint.decimals;
+        anchor_spl::token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.staking_token_program.to_account_info(),
+                cpi_accounts,
+                signer,
+            ),
+            amount,
+            decimals,
+        )?;
+
+        // Reset position, dropping any unsettled or pending rewards
+        settle_campaigns(pool, user_stake, clock.unix_timestamp)?;
+        user_stake.staked_amount = 0;
+        user_stake.pending_rewards = 0;
+        user_stake.stake_start_time = 0;
+        user_stake.last_stake_time = clock.unix_timestamp;
+        user_stake.last_accrual_time = pool.accrual_time(clock.unix_timestamp);
+        assign_tier(&pool.tiers, user_stake, clock.unix_timestamp);
+
+        pool.total_staked = pool
+            .total_staked
+            .checked_sub(amount)
+            .ok_or(StakingError::MathOverflow)?;
+        pool.last_update_time = clock.unix_timestamp;
+        ctx.accounts.user_checkpoints.record(0, &clock);
+        record_pool_total(
+            pool,
+            &mut ctx.accounts.pool_checkpoints,
+            &mut ctx.accounts.next_pool_checkpoints,
+            &clock,
+        );
+
+        emit!(EmergencyWithdrawEvent {
+            user: ctx.accounts.user.key(),
+            pool: pool.key(),
+            amount,
+            forfeited_rewards,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Allow/forbid `transfer_stake`, e.g. for soulbound campaigns (admin only)
+    pub fn set_transfers_disabled(ctx: Context<AdminAction>, disabled: bool) -> Result<()> {
+        ctx.accounts.pool.transfers_disabled = disabled;
+
+        emit!(TransfersDisabledSet {
+            pool: ctx.accounts.pool.key(),
+            disabled,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Set the share of each cranked claim paid to the cranker (admin only)
+    pub fn set_crank_tip(ctx: Context<AdminAction>, tip_bps: u16) -> Result<()> {
+        require!(tip_bps <= MAX_CRANK_TIP_BPS, StakingError::CrankTipTooHigh);
+
+        let pool = &mut ctx.accounts.pool;
+        let old_tip_bps = pool.crank_tip_bps;
+        pool.crank_tip_bps = tip_bps;
+
+        emit!(CrankTipUpdated {
+            pool: pool.key(),
+            old_tip_bps,
+            new_tip_bps: tip_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Set the share of referees' rewards paid to their referrers on top (admin only)
+    pub fn set_referral_bps(ctx: Context<AdminAction>, referral_bps: u16) -> Result<()> {
+        require!(
+            referral_bps <= MAX_REFERRAL_BPS,
+            StakingError::ReferralShareTooHigh
+        );
+
+        let pool = &mut ctx.accounts.pool;
+        let old_bps = pool.referral_bps;
+        pool.referral_bps = referral_bps;
+
+        emit!(ReferralBpsUpdated {
+            pool: pool.key(),
+            old_bps,
+            new_bps: referral_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Set the protocol fees and the treasuries they are paid to (admin only)
+    ///
+    /// Deposit and withdrawal fees are paid in staking tokens to `staking_treasury`,
+    /// performance fees in reward tokens to `reward_treasury`. Zero disables a fee.
+    pub fn set_fees(
+        ctx: Context<SetFees>,
+        deposit_bps: u16,
+        withdrawal_bps: u16,
+        performance_bps: u16,
+    ) -> Result<()> {
+        let fees = FeeConfig {
+            deposit_bps,
+            withdrawal_bps,
+            performance_bps,
+            staking_treasury: ctx.accounts.staking_treasury.key(),
+            reward_treasury: ctx.accounts.reward_treasury.key(),
+        };
+        fees.check_caps()?;
+
+        let pool = &mut ctx.accounts.pool;
+        pool.fees = fees;
+
+        emit!(FeesSet {
+            pool: pool.key(),
+            deposit_bps,
+            withdrawal_bps,
+            performance_bps,
+            staking_treasury: fees.staking_treasury,
+            reward_treasury: fees.reward_treasury,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Boost the reward weight of stakes escrowing an NFT of `collection` (admin only)
+    ///
+    /// `multiplier_bps` is relative to `BPS_DENOMINATOR` (1x). A default `collection`
+    /// disables the boost. The change applies to rewards not yet accrued, and NFTs of a
+    /// replaced collection keep their boost until detached.
+    pub fn set_nft_boost(
+        ctx: Context<AdminAction>,
+        collection: Pubkey,
+        multiplier_bps: u16,
+    ) -> Result<()> {
+        let nft_boost = NftBoost {
+            collection,
+            multiplier_bps,
+        };
+        require!(
+            !nft_boost.is_enabled()
+                || (BPS_DENOMINATOR..=MAX_NFT_BOOST_BPS).contains(&multiplier_bps),
+            StakingError::InvalidBoostMultiplier
+        );
+
+        let pool = &mut ctx.accounts.pool;
+        pool.nft_boost = nft_boost;
+
+        emit!(NftBoostSet {
+            pool: pool.key(),
+            collection,
+            multiplier_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Replace the staking tiers (admin only)
+    ///
+    /// `tiers` must be in ascending order of `min_stake`, and the lowest threshold becomes
+    /// the minimum stake. Positions keep their tier level and multiplier until their next
+    /// stake, unstake or `refresh_tier` reassigns them from the new table.
+    pub fn set_tiers(ctx: Context<AdminAction>, tiers: Vec<Tier>) -> Result<()> {
+        let tiers = TierTable::new(&tiers)?;
+
+        let pool = &mut ctx.accounts.pool;
+        pool.tiers = tiers;
+
+        emit!(TiersSet {
+            pool: pool.key(),
+            tiers,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Move a position into its tier under the pool's current tiers (anyone can call)
+    ///
+    /// Rewards accrued so far are settled at the position's old multiplier first, so a
+    /// `set_tiers` change only applies from the refresh on. The liquid position is rejected,
+    /// as it always earns at 1x.
+    pub fn refresh_tier(ctx: Context<RefreshTier>) -> Result<()> {
+        let pool = &ctx.accounts.pool;
+        let user_stake = &mut ctx.accounts.user_stake;
+        let now = Clock::get()?.unix_timestamp;
+
+        let accrual_time = pool.accrual_time(now);
+        accrue_rewards(pool, user_stake, accrual_time)?;
+        user_stake.last_accrual_time = accrual_time;
+        assign_tier(&pool.tiers, user_stake, now);
+
+        Ok(())
+    }
+
+    /// Fund the reward vault (anyone can fund)
+    pub fn fund_rewards(ctx: Context<FundRewards>, amount: u64) -> Result<()> {
+        require!(amount > 0, StakingError::InvalidAmount);
+        require!(!ctx.accounts.pool.is_paused(PAUSE_FUND), StakingError::PoolPaused);
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.funder_token_account.to_account_info(),
+            to: ctx.accounts.reward_vault.to_account_info(),
+            authority: ctx.accounts.funder.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+            amount,
+        )?;
+
+        emit!(RewardsFunded {
+            pool: ctx.accounts.pool.key(),
+            funder: ctx.accounts.funder.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Open the next distributor epoch paying `total_amount` from the reward vault (admin only)
+    ///
+    /// The tree's leaves are `distributor_leaf(index, claimant, amount)` for indices
+    /// `0..num_leaves`. The total is reserved in the reward vault until claimed or the
+    /// epoch is closed, so staking rewards can't spend it.
+    pub fn post_distributor_epoch(
+        ctx: Context<PostDistributorEpoch>,
+        merkle_root: [u8; 32],
+        total_amount: u64,
+        num_leaves: u32,
+    ) -> Result<()> {
+        require!(
+            total_amount > 0 && num_leaves > 0 && num_leaves <= MAX_DISTRIBUTOR_LEAVES,
+            StakingError::InvalidDistributorEpoch
+        );
+        let pool = &mut ctx.accounts.pool;
+        require!(
+            pool.available_rewards(ctx.accounts.reward_vault.amount) >= total_amount,
+            StakingError::InsufficientRewardBalance
+        );
+
+        let epoch = pool.distributor.epochs;
+        pool.distributor.epochs = epoch.checked_add(1).ok_or(StakingError::MathOverflow)?;
+        pool.distributor.reserved = pool
+            .distributor
+            .reserved
+            .checked_add(total_amount)
+            .ok_or(StakingError::MathOverflow)?;
+
+        let distributor_epoch = &mut ctx.accounts.distributor_epoch;
+        distributor_epoch.pool = pool.key();
+        distributor_epoch.epoch = epoch;
+        distributor_epoch.merkle_root = merkle_root;
+        distributor_epoch.total_amount = total_amount;
+        distributor_epoch.claimed_amount = 0;
+        distributor_epoch.num_leaves = num_leaves;
+        distributor_epoch.claimed_count = 0;
+        distributor_epoch.bump = ctx.bumps.distributor_epoch;
+        distributor_epoch.claimed = vec![0; DistributorEpoch::bitmap_len(num_leaves)];
+
+        emit!(DistributorEpochPosted {
+            pool: pool.key(),
+            epoch,
+            merkle_root,
+            total_amount,
+            num_leaves,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Claim the claimant's leaf of a distributor epoch
+    pub fn claim_distributor(
+        ctx: Context<ClaimDistributor>,
+        index: u32,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        let distributor_epoch = &mut ctx.accounts.distributor_epoch;
+        let claimant = ctx.accounts.claimant.key();
+
+        require!(!pool.is_paused(PAUSE_CLAIM), StakingError::PoolPaused);
+        require!(
+            verify_distributor_proof(
+                &proof,
+                &distributor_epoch.merkle_root,
+                distributor_leaf(index, &claimant, amount),
+            ),
+            StakingError::InvalidMerkleProof
+        );
+        distributor_epoch.set_claimed(index)?;
+        distributor_epoch.claimed_count = distributor_epoch
+            .claimed_count
+            .checked_add(1)
+            .ok_or(StakingError::MathOverflow)?;
+        distributor_epoch.claimed_amount = distributor_epoch
+            .claimed_amount
+            .checked_add(amount)
+            .filter(|claimed| *claimed <= distributor_epoch.total_amount)
+            .ok_or(StakingError::DistributionExceeded)?;
+        pool.distributor.reserved = pool
+            .distributor
+            .reserved
+            .checked_sub(amount)
+            .ok_or(StakingError::MathOverflow)?;
+
+        let staking_mint = pool.staking_mint;
+        let seeds = &[
+            b"pool".as_ref(),
+            staking_mint.as_ref(),
+            &[pool.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.reward_vault.to_account_info(),
+            to: ctx.accounts.claimant_reward_account.to_account_info(),
+            authority: pool.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer,
+            ),
+            amount,
+        )?;
+
+        emit!(DistributorClaimed {
+            pool: pool.key(),
+            epoch: distributor_epoch.epoch,
+            index,
+            claimant,
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Close a distributor epoch, releasing its unclaimed rewards back to stakers (admin only)
+    pub fn close_distributor_epoch(ctx: Context<CloseDistributorEpoch>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        let distributor_epoch = &ctx.accounts.distributor_epoch;
+        let unclaimed = distributor_epoch
+            .total_amount
+            .checked_sub(distributor_epoch.claimed_amount)
+            .ok_or(StakingError::MathOverflow)?;
+        pool.distributor.reserved = pool
+            .distributor
+            .reserved
+            .checked_sub(unclaimed)
+            .ok_or(StakingError::MathOverflow)?;
+
+        emit!(DistributorEpochClosed {
+            pool: pool.key(),
+            epoch: distributor_epoch.epoch,
+            unclaimed,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Sponsor a campaign paying `amount` of any mint to the pool's stakers (anyone can fund)
+    ///
+    /// Rewards are emitted evenly from `start_time` to `end_time` and split by staked amount,
+    /// regardless of tiers, boosts and accrual halts; the liquid position takes no share.
+    /// Whatever is emitted while nothing is staked stays unallocated and can be reclaimed by
+    /// the funder once the campaign ends. The few slots can't be held by dust: a campaign
+    /// must pay at least `min_campaign_amount` once transfer fees are taken.
+    pub fn create_campaign(
+        ctx: Context<CreateCampaign>,
+        amount: u64,
+        start_time: i64,
+        end_time: i64,
+    ) -> Result<()> {
+        require!(amount > 0, StakingError::InvalidAmount);
+        require!(!ctx.accounts.pool.is_paused(PAUSE_FUND), StakingError::PoolPaused);
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            start_time >= now
+                && end_time
+                    .checked_sub(start_time)
+                    .is_some_and(|duration| (1..=MAX_CAMPAIGN_DURATION).contains(&duration)),
+            StakingError::InvalidCampaignWindow
+        );
+        let slot = ctx
+            .accounts
+            .pool
+            .campaigns
+            .slots
+            .iter()
+            .position(|slot| !slot.is_active())
+            .ok_or(StakingError::CampaignSlotsFull)?;
+
+        anchor_spl::token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.reward_token_program.to_account_info(),
+                anchor_spl::token_interface::TransferChecked {
+                    from: ctx.accounts.funder_token_account.to_account_info(),
+                    mint: ctx.accounts.reward_mint.to_account_info(),
+                    to: ctx.accounts.campaign_vault.to_account_info(),
+                    authority: ctx.accounts.funder.to_account_info(),
+                },
+            ),
+            amount,
+            ctx.accounts.reward_mint.decimals,
+        )?;
+        // Token-2022 transfer fees can leave the vault short of `amount`
+        ctx.accounts.campaign_vault.reload()?;
+        let funded = ctx.accounts.campaign_vault.amount;
+        let decimals = ctx.accounts.reward_mint.decimals;
+        let min_amount = min_campaign_amount(end_time - start_time, decimals)
+            .ok_or(StakingError::MathOverflow)?;
+        require!(funded >= min_amount, StakingError::CampaignTooSmall);
+
+        let pool = &mut ctx.accounts.pool;
+        let campaign = &mut ctx.accounts.campaign;
+        campaign.pool = pool.key();
+        campaign.funder = ctx.accounts.funder.key();
+        campaign.reward_mint = ctx.accounts.reward_mint.key();
+        campaign.id = pool.campaigns.created;
+        campaign.slot = slot as u8;
+        campaign.claimed = 0;
+        campaign.reclaimed = 0;
+        campaign.bump = ctx.bumps.campaign;
+
+        pool.campaigns.created = pool
+            .campaigns
+            .created
+            .checked_add(1)
+            .ok_or(StakingError::MathOverflow)?;
+        pool.campaigns.slots[slot] = CampaignSlot {
+            campaign: campaign.key(),
+            start_time,
+            end_time,
+            amount: funded,
+            allocated: 0,
+            reward_per_token: 0,
+            last_update_time: start_time,
+        };
+
+        emit!(CampaignCreated {
+            pool: pool.key(),
+            campaign: campaign.key(),
+            funder: campaign.funder,
+            reward_mint: campaign.reward_mint,
+            amount: funded,
+            start_time,
+            end_time,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Claim the caller's share of a campaign's rewards
+    pub fn claim_campaign_rewards(ctx: Context<ClaimCampaignRewards>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let campaign = &mut ctx.accounts.campaign;
+        let amount = take_campaign_rewards(
+            &mut ctx.accounts.pool,
+            &mut ctx.accounts.user_stake,
+            campaign,
+            now,
+        )?;
+
+        pay_from_campaign(
+            campaign,
+            &ctx.accounts.campaign_vault,
+            &ctx.accounts.reward_mint,
+            ctx.accounts.user_reward_account.to_account_info(),
+            ctx.accounts.reward_token_program.to_account_info(),
+            amount,
+        )?;
+
+        emit!(CampaignRewardsClaimed {
+            pool: ctx.accounts.pool.key(),
+            campaign: campaign.key(),
+            user: ctx.accounts.user.key(),
+            amount,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Claim a tokenized position's share of a campaign's rewards (position NFT holder only)
+    ///
+    /// Shares left unclaimed also follow the position back into the wallet stake it is
+    /// redeemed into.
+    pub fn claim_position_campaign_rewards(
+        ctx: Context<ClaimPositionCampaignRewards>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let campaign = &mut ctx.accounts.campaign;
+        let amount = take_campaign_rewards(
+            &mut ctx.accounts.pool,
+            &mut ctx.accounts.position_stake,
+            campaign,
+            now,
+        )?;
+
+        pay_from_campaign(
+            campaign,
+            &ctx.accounts.campaign_vault,
+            &ctx.accounts.reward_mint,
+            ctx.accounts.holder_reward_account.to_account_info(),
+            ctx.accounts.reward_token_program.to_account_info(),
+            amount,
+        )?;
+
+        emit!(CampaignRewardsClaimed {
+            pool: ctx.accounts.pool.key(),
+            campaign: campaign.key(),
+            user: ctx.accounts.holder.key(),
+            amount,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Return the rewards a campaign emitted while nothing was staked to its funder
+    pub fn reclaim_campaign_leftovers(ctx: Context<CampaignFunderAction>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        let campaign = &mut ctx.accounts.campaign;
+        let now = Clock::get()?.unix_timestamp;
+
+        let slot = usize::from(campaign.slot);
+        require!(
+            now >= pool.campaigns.slots[slot].end_time,
+            StakingError::CampaignNotEnded
+        );
+        pool.update_campaigns(now)?;
+
+        let slot = &pool.campaigns.slots[slot];
+        let leftovers = slot
+            .amount
+            .checked_sub(slot.allocated)
+            .and_then(|unallocated| unallocated.checked_sub(campaign.reclaimed))
+            .ok_or(StakingError::MathOverflow)?;
+        require!(leftovers > 0, StakingError::NoCampaignLeftovers);
+        campaign.reclaimed = campaign
+            .reclaimed
+            .checked_add(leftovers)
+            .ok_or(StakingError::MathOverflow)?;
+
+        pay_from_campaign(
+            campaign,
+            &ctx.accounts.campaign_vault,
+            &ctx.accounts.reward_mint,
+            ctx.accounts.funder_token_account.to_account_info(),
+            ctx.accounts.reward_token_program.to_account_info(),
+            leftovers,
+        )?;
+
+        emit!(CampaignLeftoversReclaimed {
+            pool: pool.key(),
+            campaign: campaign.key(),
+            funder: campaign.funder,
+            amount: leftovers,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Close a campaign `CAMPAIGN_CLAIM_PERIOD` after it ended, freeing its slot
+    ///
+    /// Everything left in the vault, unclaimed rewards included, goes back to the funder.
+    pub fn close_campaign(ctx: Context<CampaignFunderAction>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        let campaign = &ctx.accounts.campaign;
+        let now = Clock::get()?.unix_timestamp;
+
+        let slot = usize::from(campaign.slot);
+        require!(
+            now >= pool.campaigns.slots[slot]
+                .end_time
+                .saturating_add(CAMPAIGN_CLAIM_PERIOD),
+            StakingError::CampaignClaimPeriodActive
+        );
+
+        let returned = ctx.accounts.campaign_vault.amount;
+        if returned > 0 {
+            pay_from_campaign(
+                campaign,
+                &ctx.accounts.campaign_vault,
+                &ctx.accounts.reward_mint,
+                ctx.accounts.funder_token_account.to_account_info(),
+                ctx.accounts.reward_token_program.to_account_info(),
+                returned,
+            )?;
+        }
+
+        let pool_key = pool.key();
+        let id = campaign.id.to_le_bytes();
+        let seeds = &[
+            b"campaign".as_ref(),
+            pool_key.as_ref(),
+            id.as_ref(),
+            &[campaign.bump],
+        ];
+        anchor_spl::token_interface::close_account(CpiContext::new_with_signer(
+            ctx.accounts.reward_token_program.to_account_info(),
+            anchor_spl::token_interface::CloseAccount {
+                account: ctx.accounts.campaign_vault.to_account_info(),
+                destination: ctx.accounts.funder.to_account_info(),
+                authority: campaign.to_account_info(),
+            },
+            &[&seeds[..]],
+        ))?;
+        pool.campaigns.slots[slot] = CampaignSlot::default();
+
+        emit!(CampaignClosed {
+            pool: pool_key,
+            campaign: campaign.key(),
+            funder: campaign.funder,
+            returned,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+// ============ PAUSE FLAGS ============
+
+/// Blocks `stake`
+pub const PAUSE_STAKE: u8 = 1 << 0;
+/// Blocks `unstake`
+pub const PAUSE_UNSTAKE: u8 = 1 << 1;
+/// Blocks `claim_rewards`
+pub const PAUSE_CLAIM: u8 = 1 << 2;
+/// Blocks `fund_rewards`
+pub const PAUSE_FUND: u8 = 1 << 3;
+/// Halts reward accrual for every staker while set
+pub const PAUSE_ACCRUAL: u8 = 1 << 4;
+/// Blocks compounding rewards back into stake
+pub const PAUSE_COMPOUND: u8 = 1 << 5;
+
+/// Every user-facing operation (accrual keeps running)
+pub const PAUSE_ALL_OPERATIONS: u8 =
+    PAUSE_STAKE | PAUSE_UNSTAKE | PAUSE_CLAIM | PAUSE_FUND | PAUSE_COMPOUND;
+/// Every valid pause flag
+pub const PAUSE_ALL_FLAGS: u8 = PAUSE_ALL_OPERATIONS | PAUSE_ACCRUAL;
+
+// ============ POSITION NFTS ============
+
+/// Name of every position NFT
+pub const POSITION_NAME: &str = "Memecoin Stake Position";
+/// Symbol of every position NFT
+pub const POSITION_SYMBOL: &str = "mSTAKE";
+/// Metadata key holding the position's staked amount (base units)
+pub const POSITION_STAKED_AMOUNT_KEY: &str = "staked_amount";
+/// Metadata key holding the unix time the position's lock expires
+pub const POSITION_UNLOCK_TIME_KEY: &str = "unlock_time";
+
+// ============ CRANK ============
+
+/// Basis points denominator (100%)
+pub const BPS_DENOMINATOR: u16 = 10_000;
+/// Highest crank tip the authority can configure (1%)
+pub const MAX_CRANK_TIP_BPS: u16 = 100;
+
+// ============ REFERRALS ============
+
+/// Highest referral share the authority can configure (20%)
+pub const MAX_REFERRAL_BPS: u16 = 2_000;
+
+/// Referrer's share of `accrued` rewards at `referral_bps`
+pub fn referral_bonus(accrued: u64, referral_bps: u16) -> u64 {
+    // At most `accrued` since `referral_bps` never exceeds the denominator
+    (accrued as u128 * referral_bps as u128 / BPS_DENOMINATOR as u128) as u64
+}
+
+// ============ PROTOCOL FEES ============
+
+/// Highest deposit fee the authority can configure (2%)
+pub const MAX_DEPOSIT_FEE_BPS: u16 = 200;
+/// Highest withdrawal fee the authority can configure (2%)
+pub const MAX_WITHDRAWAL_FEE_BPS: u16 = 200;
+/// Highest performance fee the authority can configure (20%)
+pub const MAX_PERFORMANCE_FEE_BPS: u16 = 2_000;
+
+/// Operator fees and the treasury token accounts they are paid to
+///
+/// Deposit fees apply to `stake` and `stake_for`, withdrawal fees to `unstake` and
+/// `unstake_position`, and performance fees to every streaming rewards claim. Liquid stakes
+/// and unstakes pay the deposit and withdrawal fees; `emergency_withdraw` is not charged.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FeeConfig {
+    /// Share of staked deposits, in bps
+    pub deposit_bps: u16,
+    /// Share of unstaked amounts, in bps
+    pub withdrawal_bps: u16,
+    /// Share of claimed rewards, in bps
+    pub performance_bps: u16,
+    /// Staking token account receiving deposit and withdrawal fees
+    pub staking_treasury: Pubkey,
+    /// Reward token account receiving performance fees
+    pub reward_treasury: Pubkey,
+}
+
+impl FeeConfig {
+    pub fn check_caps(&self) -> Result<()> {
+        require!(
+            self.deposit_bps <= MAX_DEPOSIT_FEE_BPS
+                && self.withdrawal_bps <= MAX_WITHDRAWAL_FEE_BPS
+                && self.performance_bps <= MAX_PERFORMANCE_FEE_BPS,
+            StakingError::FeeTooHigh
+        );
+        Ok(())
+    }
+}
+
+/// Fee of `fee_bps` on `amount`, rounded down so it never exceeds `amount`
+pub fn fee_for(amount: u64, fee_bps: u16) -> u64 {
+    (amount as u128 * fee_bps.min(BPS_DENOMINATOR) as u128 / BPS_DENOMINATOR as u128) as u64
+}
+
+/// Move a nonzero staking token `fee` from `from` to the staking treasury
+///
+/// `signer_seeds` are the pool's for fees paid out of the vault, empty for deposits.
+fn pay_staking_fee<'info>(
+    from: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    staking_treasury: Option<AccountInfo<'info>>,
+    staking_mint: &InterfaceAccount<'info, MintInterface>,
+    staking_token_program: AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    fee: u64,
+) -> Result<()> {
+    if fee == 0 {
+        return Ok(());
+    }
+    let staking_treasury = staking_treasury.ok_or(StakingError::MissingFeeTreasury)?;
+    anchor_spl::token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            staking_token_program,
+            anchor_spl::token_interface::TransferChecked {
+                from,
+                mint: staking_mint.to_account_info(),
+                to: staking_treasury,
+                authority,
+            },
+            signer_seeds,
+        ),
+        fee,
+        staking_mint.decimals,
+    )
+}
+
+/// Move a nonzero reward `fee` from the reward vault to the reward treasury
+fn pay_reward_fee<'info>(
+    pool: &Account<'info, StakePool>,
+    reward_vault: AccountInfo<'info>,
+    reward_treasury: Option<AccountInfo<'info>>,
+    token_program: AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    fee: u64,
+) -> Result<()> {
+    if fee == 0 {
+        return Ok(());
+    }
+    let reward_treasury = reward_treasury.ok_or(StakingError::MissingFeeTreasury)?;
+    token::transfer(
+        CpiContext::new_with_signer(
+            token_program,
+            Transfer {
+                from: reward_vault,
+                to: reward_treasury,
+                authority: pool.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        fee,
+    )
+}
+
+// ============ NFT BOOST ============
+
+/// Highest reward weight multiplier the authority can configure (3x)
+pub const MAX_NFT_BOOST_BPS: u16 = 30_000;
+/// Metaplex Token Metadata program
+pub const TOKEN_METADATA_PROGRAM_ID: Pubkey =
+    pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+/// Reward boost for stakes escrowing a collection NFT (`collection` is default while disabled)
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NftBoost {
+    /// Verified Metaplex collection whose NFTs earn the boost
+    pub collection: Pubkey,
+    /// Reward weight multiplier, in bps of the staked amount
+    pub multiplier_bps: u16,
+}
+
+impl NftBoost {
+    pub fn is_enabled(&self) -> bool {
+        self.collection != Pubkey::default()
+    }
+}
+
+/// `amount` multiplied by `multiplier_bps`, or `None` if it exceeds a u64
+///
+/// Boosting accrued rewards rather than the staked amount keeps huge boosted stakes
+/// accruing until their rewards themselves overflow.
+pub fn boosted(amount: u64, multiplier_bps: u16) -> Option<u64> {
+    u64::try_from(amount as u128 * multiplier_bps as u128 / BPS_DENOMINATOR as u128).ok()
+}
+
+/// Metaplex `MetadataV1` fields up to its collection
+#[derive(AnchorDeserialize)]
+struct MetadataPrefix {
+    key: u8,
+    _update_authority: Pubkey,
+    _mint: Pubkey,
+    _name: String,
+    _symbol: String,
+    _uri: String,
+    _seller_fee_basis_points: u16,
+    _creators: Option<Vec<(Pubkey, bool, u8)>>,
+    _primary_sale_happened: bool,
+    _is_mutable: bool,
+    _edition_nonce: Option<u8>,
+    _token_standard: Option<u8>,
+    /// `(verified, key)`
+    collection: Option<(bool, Pubkey)>,
+}
+
+/// The verified collection of a Metaplex metadata account, if it has one
+///
+/// Metadata addresses are Token Metadata PDAs of their mint, so the caller must check
+/// the account's address for the result to be trusted.
+pub fn verified_collection(data: &[u8]) -> Result<Option<Pubkey>> {
+    const METADATA_V1: u8 = 4;
+
+    let metadata = MetadataPrefix::deserialize(&mut &data[..])
+        .map_err(|_| error!(StakingError::InvalidBoostNft))?;
+    require!(metadata.key == METADATA_V1, StakingError::InvalidBoostNft);
+    Ok(metadata
+        .collection
+        .and_then(|(verified, key)| verified.then_some(key)))
+}
+
+// ============ STAKING TIERS ============
+//
+// A pool's tiers are ascending stake thresholds, each with a reward multiplier of at least
+// 1x. A position's tier is the highest one whose threshold its staked amount reaches, and
+// the lowest threshold is the pool's minimum stake. Stakes, unstakes and transfers settle
+// a position's rewards and then reassign its tier; the position keeps the multiplier it
+// was assigned, so `set_tiers` never reprices rewards already accrued. `refresh_tier`
+// moves a position onto the current tiers without touching its stake.
+
+/// Most tiers a pool can configure
+pub const MAX_TIERS: usize = 5;
+/// Highest reward multiplier a tier can earn (5x)
+pub const MAX_TIER_MULTIPLIER_BPS: u16 = 50_000;
+
+/// Stake size threshold and the reward multiplier of positions at or above it
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Tier {
+    /// Smallest stake in the tier
+    pub min_stake: u64,
+    /// Reward multiplier, in bps of the rewards accrued
+    pub multiplier_bps: u16,
+}
+
+/// Staking tiers, in ascending order of `min_stake`
+///
+/// Tier levels count from 1; level 0 holds positions below every threshold (or emptied),
+/// which earn at 1x.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TierTable {
+    pub tiers: [Tier; MAX_TIERS],
+    /// Number of configured entries at the start of `tiers`
+    pub count: u8,
+}
+
+impl TierTable {
+    /// Table of `tiers`, checking their order and multipliers
+    pub fn new(tiers: &[Tier]) -> Result<Self> {
+        require!(
+            !tiers.is_empty() && tiers.len() <= MAX_TIERS,
+            StakingError::InvalidTiers
+        );
+        require!(
+            tiers.windows(2).all(|pair| pair[0].min_stake < pair[1].min_stake),
+            StakingError::InvalidTiers
+        );
+        require!(
+            tiers.iter().all(|tier| {
+                (BPS_DENOMINATOR..=MAX_TIER_MULTIPLIER_BPS).contains(&tier.multiplier_bps)
+            }),
+            StakingError::InvalidTierMultiplier
+        );
+
+        let mut table = Self {
+            count: tiers.len() as u8,
+            ..Self::default()
+        };
+        table.tiers[..tiers.len()].copy_from_slice(tiers);
+        Ok(table)
+    }
+
+    /// The configured tiers
+    pub fn active(&self) -> &[Tier] {
+        &self.tiers[..usize::from(self.count).min(MAX_TIERS)]
+    }
+
+    /// Smallest stake a position may hold after staking: the lowest tier's threshold
+    pub fn min_stake(&self) -> u64 {
+        self.active().first().map_or(0, |tier| tier.min_stake)
+    }
+
+    /// Tier level of a position staking `staked_amount`
+    pub fn tier_for(&self, staked_amount: u64) -> u8 {
+        if staked_amount == 0 {
+            return 0;
+        }
+        self.active()
+            .iter()
+            .take_while(|tier| tier.min_stake <= staked_amount)
+            .count() as u8
+    }
+
+    /// Reward multiplier of tier level `tier`; 1x for level 0 or levels no longer configured
+    pub fn multiplier_bps(&self, tier: u8) -> u16 {
+        usize::from(tier)
+            .checked_sub(1)
+            .and_then(|index| self.active().get(index))
+            .map_or(BPS_DENOMINATOR, |tier| tier.multiplier_bps)
+    }
+}
+
+/// Move a position into the tier of its staked amount at that tier's current multiplier,
+/// emitting `TierChanged` if its level moved
+///
+/// Callers settle accrued rewards first so they are paid at the old tier's multiplier.
+fn assign_tier(tiers: &TierTable, user_stake: &mut UserStake, now: i64) {
+    let old_tier = user_stake.tier;
+    let new_tier = tiers.tier_for(user_stake.staked_amount);
+    user_stake.tier_multiplier_bps = tiers.multiplier_bps(new_tier);
+    if new_tier == old_tier {
+        return;
+    }
+    user_stake.tier = new_tier;
+
+    emit!(TierChanged {
+        pool: user_stake.pool,
+        user: user_stake.owner,
+        old_tier,
+        new_tier,
+        staked_amount: user_stake.staked_amount,
+        timestamp: now,
+    });
+}
+
+// ============ CAMPAIGNS ============
+
+/// Most campaigns a pool can run at once
+pub const MAX_CAMPAIGNS: usize = 4;
+/// Longest window a campaign can run for (one year)
+pub const MAX_CAMPAIGN_DURATION: i64 = 365 * 86_400;
+/// How long stakers can still claim after a campaign ends before its funder can close it
+pub const CAMPAIGN_CLAIM_PERIOD: i64 = 30 * 86_400;
+/// Campaigns emit at least one whole reward token per this many seconds of their window
+pub const MIN_CAMPAIGN_EMISSION_PERIOD: i64 = 86_400;
+/// Fixed-point scale of `CampaignSlot::reward_per_token`
+pub const CAMPAIGN_INDEX_SCALE: u128 = 1_000_000_000_000_000_000;
+
+/// Pool-wide campaign bookkeeping
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CampaignState {
+    /// Campaigns created so far (the next campaign's id)
+    pub created: u64,
+    /// Open campaigns, indexed by `Campaign::slot`
+    pub slots: [CampaignSlot; MAX_CAMPAIGNS],
+    /// Stake of the liquid position, which takes no campaign share: campaign mints can't
+    /// be compounded and receipt holders have no position to claim from
+    pub excluded_stake: u64,
+}
+
+/// Emission schedule and reward index of an open campaign (default while the slot is free)
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CampaignSlot {
+    pub campaign: Pubkey,
+    pub start_time: i64,
+    pub end_time: i64,
+    /// Rewards emitted evenly over the window
+    pub amount: u64,
+    /// Rewards emitted while something was staked, owed to stakers
+    pub allocated: u64,
+    /// Rewards emitted per staked token, scaled by `CAMPAIGN_INDEX_SCALE`
+    pub reward_per_token: u128,
+    /// Time rewards were emitted up to
+    pub last_update_time: i64,
+}
+
+impl CampaignSlot {
+    pub fn is_active(&self) -> bool {
+        self.campaign != Pubkey::default()
+    }
+
+    /// Rewards emitted from the start of the window up to `time`
+    fn emitted_at(&self, time: i64) -> Option<u64> {
+        let elapsed = time.clamp(self.start_time, self.end_time).abs_diff(self.start_time);
+        let duration = self.end_time.abs_diff(self.start_time);
+        let emitted = (self.amount as u128 * elapsed as u128).checked_div(duration as u128)?;
+        u64::try_from(emitted).ok()
+    }
+
+    /// Emit rewards up to `now` to `total_staked` tokens, or `None` on overflow
+    pub fn update(&mut self, total_staked: u64, now: i64) -> Option<()> {
+        let time = now.min(self.end_time);
+        if time <= self.last_update_time {
+            return Some(());
+        }
+        let emitted = self
+            .emitted_at(time)?
+            .checked_sub(self.emitted_at(self.last_update_time)?)?;
+        if total_staked > 0 {
+            let per_token = emitted as u128 * CAMPAIGN_INDEX_SCALE / total_staked as u128;
+            self.reward_per_token = self.reward_per_token.checked_add(per_token)?;
+            self.allocated = self.allocated.checked_add(emitted)?;
+        }
+        self.last_update_time = time;
+        Some(())
+    }
+
+    /// Rewards `staked_amount` earned since the index was `reward_per_token_paid`
+    pub fn rewards_since(&self, staked_amount: u64, reward_per_token_paid: u128) -> Option<u64> {
+        let per_token = self.reward_per_token.checked_sub(reward_per_token_paid)?;
+        u64::try_from(per_token.checked_mul(staked_amount as u128)? / CAMPAIGN_INDEX_SCALE).ok()
+    }
+}
+
+/// Smallest amount a campaign lasting `duration` seconds can pay in a mint of `decimals`
+///
+/// One whole token per `MIN_CAMPAIGN_EMISSION_PERIOD`, and at least one token however short
+/// the window, since every campaign holds its slot through `CAMPAIGN_CLAIM_PERIOD`.
+pub fn min_campaign_amount(duration: i64, decimals: u8) -> Option<u64> {
+    let token = 10u64.checked_pow(u32::from(decimals))?;
+    let periods = duration.max(MIN_CAMPAIGN_EMISSION_PERIOD) as u128;
+    u64::try_from(token as u128 * periods / MIN_CAMPAIGN_EMISSION_PERIOD as u128).ok()
+}
+
+/// A position's share of the campaign in the same slot
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CampaignReward {
+    /// Campaign the entry is for; entries of closed campaigns are reset on the next settle
+    pub campaign: Pubkey,
+    /// The campaign's `reward_per_token` when the position was last settled
+    pub reward_per_token_paid: u128,
+    /// Settled rewards not claimed yet
+    pub pending: u64,
+}
+
+/// Emit every open campaign's rewards up to `now` and settle a position's shares
+///
+/// Must run before the position's stake changes, so each share is earned at the stake
+/// held since the last settle.
+fn settle_campaigns(pool: &mut StakePool, user_stake: &mut UserStake, now: i64) -> Result<()> {
+    pool.update_campaigns(now)?;
+
+    let staked_amount = user_stake.staked_amount;
+    for (slot, entry) in pool.campaigns.slots.iter().zip(&mut user_stake.campaign_rewards) {
+        if !slot.is_active() {
+            continue;
+        }
+        if entry.campaign != slot.campaign {
+            // Unsettled since the campaign opened, when its index was zero
+            *entry = CampaignReward {
+                campaign: slot.campaign,
+                ..CampaignReward::default()
+            };
+        }
+        let earned = slot
+            .rewards_since(staked_amount, entry.reward_per_token_paid)
+            .ok_or(StakingError::MathOverflow)?;
+        entry.pending = entry
+            .pending
+            .checked_add(earned)
+            .ok_or(StakingError::MathOverflow)?;
+        entry.reward_per_token_paid = slot.reward_per_token;
+    }
+    Ok(())
+}
+
+/// A position's claimable rewards from the campaign in `slot` at `now`, or `None` on overflow
+///
+/// `campaign_staked` is the pool's `StakePool::campaign_staked`.
+pub fn campaign_claimable(
+    campaigns: &CampaignState,
+    campaign_staked: u64,
+    user_stake: &UserStake,
+    slot: usize,
+    now: i64,
+) -> Option<u64> {
+    let mut campaign = *campaigns.slots.get(slot)?;
+    if !campaign.is_active() {
+        return Some(0);
+    }
+    campaign.update(campaign_staked, now)?;
+    let entry = user_stake.campaign_rewards[slot];
+    let (pending, paid) = if entry.campaign == campaign.campaign {
+        (entry.pending, entry.reward_per_token_paid)
+    } else {
+        (0, 0)
+    };
+    pending.checked_add(campaign.rewards_since(user_stake.staked_amount, paid)?)
+}
+
+/// Settle a position's campaign shares and take its pending share of `campaign`
+fn take_campaign_rewards(
+    pool: &mut StakePool,
+    user_stake: &mut UserStake,
+    campaign: &mut Campaign,
+    now: i64,
+) -> Result<u64> {
+    require!(!pool.is_paused(PAUSE_CLAIM), StakingError::PoolPaused);
+    settle_campaigns(pool, user_stake, now)?;
+
+    let entry = &mut user_stake.campaign_rewards[usize::from(campaign.slot)];
+    let amount = entry.pending;
+    require!(amount > 0, StakingError::NoRewards);
+    entry.pending = 0;
+    campaign.claimed = campaign
+        .claimed
+        .checked_add(amount)
+        .ok_or(StakingError::MathOverflow)?;
+    Ok(amount)
+}
+
+/// Transfer `amount` out of a campaign's vault, signed by the campaign
+fn pay_from_campaign<'info>(
+    campaign: &Account<'info, Campaign>,
+    campaign_vault: &InterfaceAccount<'info, TokenAccountInterface>,
+    reward_mint: &InterfaceAccount<'info, MintInterface>,
+    to: AccountInfo<'info>,
+    reward_token_program: AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    let id = campaign.id.to_le_bytes();
+    let seeds = &[
+        b"campaign".as_ref(),
+        campaign.pool.as_ref(),
+        id.as_ref(),
+        &[campaign.bump],
+    ];
+    anchor_spl::token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            reward_token_program,
+            anchor_spl::token_interface::TransferChecked {
+                from: campaign_vault.to_account_info(),
+                mint: reward_mint.to_account_info(),
+                to,
+                authority: campaign.to_account_info(),
+            },
+            &[&seeds[..]],
+        ),
+        amount,
+        reward_mint.decimals,
+    )
+}
+
+// ============ CHECKPOINTS ============
+
+/// Balance changes kept exactly per wallet checkpoint history before the oldest are merged
+pub const CHECKPOINT_CAPACITY: usize = 64;
+
+/// Changes of the pool total held by one `CheckpointPage`
+pub const CHECKPOINT_PAGE_CAPACITY: usize = 128;
+
+/// The pool total's history: a ring of `CheckpointPage`s written one after the other
+///
+/// Once the page being written is full the next one is cleared and written instead, so
+/// the ring holds at least the last `(pages - 1) * CHECKPOINT_PAGE_CAPACITY` changes.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CheckpointRing {
+    /// Pages in the ring, at least 2
+    pub pages: u32,
+    /// Page being written
+    pub current: u32,
+    /// Page written once `current` is full
+    pub next: u32,
+    /// `CheckpointPage::sequence` of `current`
+    pub sequence: u64,
+}
+
+impl CheckpointRing {
+    /// Ring of a new pool: pages 0 and 1, writing page 0
+    pub const INITIAL: Self = Self {
+        pages: 2,
+        current: 0,
+        next: 1,
+        sequence: 0,
+    };
+
+    /// Grow the ring by one page, written after the current one's successors wrap around
+    pub fn add_page(&mut self) -> Result<()> {
+        self.pages = self.pages.checked_add(1).ok_or(StakingError::MathOverflow)?;
+        self.next = (self.current + 1) % self.pages;
+        Ok(())
+    }
+
+    /// Record `amount` as the total from now on, moving on to `next` once `current` is full
+    pub fn record(
+        &mut self,
+        current: &mut CheckpointPage,
+        next: &mut CheckpointPage,
+        amount: u64,
+        clock: &Clock,
+    ) {
+        if current.record(amount, clock) {
+            return;
+        }
+        self.sequence += 1;
+        self.current = self.next;
+        self.next = (self.current + 1) % self.pages;
+        next.sequence = self.sequence;
+        next.checkpoints.clear();
+        next.record(amount, clock);
+    }
+}
+
+// ============ VOTER WEIGHT ============
+
+/// Pool settings for the spl-governance voter weight add-in (`realm` is default while disabled)
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct VoterWeightConfig {
+    /// Realm whose votes this pool's stakes count towards
+    pub realm: Pubkey,
+    /// Realm governing token mint (community or council) the voter weight stands in for
+    pub governing_token_mint: Pubkey,
+    /// Extra weight for a fully boosted lock, in bps of the stake
+    pub max_lock_boost_bps: u16,
+    /// Remaining lock time earning the full boost, in seconds (0 disables boosting)
+    pub lock_boost_saturation: i64,
+}
+
+impl VoterWeightConfig {
+    /// Whether the pool's stakes count as voting power
+    pub fn is_enabled(&self) -> bool {
+        self.realm != Pubkey::default()
+    }
+
+    /// Voting power of `staked_amount` locked until `unlock_time`
+    pub fn voter_weight(&self, staked_amount: u64, unlock_time: i64, now: i64) -> Result<u64> {
+        if self.lock_boost_saturation == 0 {
+            return Ok(staked_amount);
+        }
+
+        let remaining = unlock_time.saturating_sub(now).clamp(0, self.lock_boost_saturation);
+        let boost = staked_amount as u128 * self.max_lock_boost_bps as u128 * remaining as u128
+            / (BPS_DENOMINATOR as u128 * self.lock_boost_saturation as u128);
+        u64::try_from(staked_amount as u128 + boost)
+            .map_err(|_| error!(StakingError::MathOverflow))
+    }
+}
+
+/// The fields of an spl-governance realm the add-in relies on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RealmInfo {
+    pub community_mint: Pubkey,
+    pub council_mint: Option<Pubkey>,
+    pub authority: Option<Pubkey>,
+}
+
+impl RealmInfo {
+    /// Parse an spl-governance `RealmV1`/`RealmV2` account
+    ///
+    /// Realm addresses are governance program PDAs, so a parsed realm can't be spoofed by
+    /// another program as long as the caller passes the realm's actual address.
+    pub fn from_account_data(data: &[u8]) -> Result<Self> {
+        const REALM_V1: u8 = 1;
+        const REALM_V2: u8 = 16;
+        // Realm config before `council_mint`: legacy (2) | reserved (6)
+        // | min_community_weight_to_create_governance (8) | max_voter_weight_source (9)
+        const CONFIG_PREFIX: usize = 2 + 6 + 8 + 9;
+        // Between the config and `authority`: reserved (6) | legacy (2)
+        const AUTHORITY_PADDING: usize = 6 + 2;
+
+        let mut reader = data;
+        let account_type = read_bytes(&mut reader, 1)?[0];
+        require!(
+            account_type == REALM_V1 || account_type == REALM_V2,
+            StakingError::InvalidRealm
+        );
+        let community_mint = read_pubkey(&mut reader)?;
+        read_bytes(&mut reader, CONFIG_PREFIX)?;
+        let council_mint = read_optional_pubkey(&mut reader)?;
+        read_bytes(&mut reader, AUTHORITY_PADDING)?;
+        let authority = read_optional_pubkey(&mut reader)?;
+
+        Ok(Self {
+            community_mint,
+            council_mint,
+            authority,
+        })
+    }
+}
+
+/// The fields of an spl-governance proposal the add-in relies on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProposalInfo {
+    pub governing_token_mint: Pubkey,
+    /// When the proposal was created
+    pub draft_at: i64,
+}
+
+impl ProposalInfo {
+    /// Parse an spl-governance `ProposalV2` account
+    pub fn from_account_data(data: &[u8]) -> Result<Self> {
+        Self::parse(data).map_err(|_| error!(StakingError::InvalidProposal))
+    }
+
+    fn parse(data: &[u8]) -> Result<Self> {
+        const PROPOSAL_V2: u8 = 14;
+        const MULTI_CHOICE: u8 = 1;
+        // Between `governing_token_mint` and `vote_type`: state (1) | token_owner_record (32)
+        // | signatories_count (1) | signatories_signed_off_count (1)
+        const HEADER_PADDING: usize = 1 + 32 + 1 + 1;
+        // Multi choice vote settings: choice_type | min/max voter options | max winning options
+        const MULTI_CHOICE_SETTINGS: usize = 4;
+        // Each option after its label: vote_weight (8) | vote_result (1)
+        // | transactions executed, count and next index (3 * 2)
+        const OPTION_FIELDS: usize = 8 + 1 + 3 * 2;
+
+        let mut reader = data;
+        require!(
+            read_bytes(&mut reader, 1)?[0] == PROPOSAL_V2,
+            StakingError::InvalidProposal
+        );
+        read_pubkey(&mut reader)?; // governance
+        let governing_token_mint = read_pubkey(&mut reader)?;
+        read_bytes(&mut reader, HEADER_PADDING)?;
+        if read_bytes(&mut reader, 1)?[0] == MULTI_CHOICE {
+            read_bytes(&mut reader, MULTI_CHOICE_SETTINGS)?;
+        }
+        for _ in 0..read_u32(&mut reader)? {
+            let label_len = read_u32(&mut reader)? as usize;
+            read_bytes(&mut reader, label_len + OPTION_FIELDS)?;
+        }
+        read_optional(&mut reader, 8)?; // deny_vote_weight
+        read_bytes(&mut reader, 1)?; // reserved
+        read_optional(&mut reader, 8)?; // abstain_vote_weight
+        read_optional(&mut reader, 8)?; // start_voting_at
+        let draft_at = i64::from_le_bytes(read_bytes(&mut reader, 8)?.try_into().unwrap());
+
+        Ok(Self {
+            governing_token_mint,
+            draft_at,
+        })
+    }
+}
+
+/// Take `len` bytes off the front of a governance account
+fn read_bytes<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    require!(data.len() >= len, StakingError::InvalidRealm);
+    let (bytes, rest) = data.split_at(len);
+    *data = rest;
+    Ok(bytes)
+}
+
+/// Read a Borsh `u32` off the front of a governance account
+fn read_u32(data: &mut &[u8]) -> Result<u32> {
+    Ok(u32::from_le_bytes(read_bytes(data, 4)?.try_into().unwrap()))
+}
+
+/// Skip a Borsh `Option` of a `len`-byte value off the front of a governance account
+fn read_optional(data: &mut &[u8], len: usize) -> Result<()> {
+    match read_bytes(data, 1)?[0] {
+        0 => Ok(()),
+        1 => read_bytes(data, len).map(|_| ()),
+        _ => err!(StakingError::InvalidRealm),
+    }
+}
+
+/// Read a Borsh `Pubkey` off the front of a governance account
+fn read_pubkey(data: &mut &[u8]) -> Result<Pubkey> {
+    Ok(Pubkey::new_from_array(read_bytes(data, 32)?.try_into().unwrap()))
+}
+
+/// Read a Borsh `Option<Pubkey>` off the front of a governance account
+fn read_optional_pubkey(data: &mut &[u8]) -> Result<Option<Pubkey>> {
+    match read_bytes(data, 1)?[0] {
+        0 => Ok(None),
+        1 => Ok(Some(read_pubkey(data)?)),
+        _ => err!(StakingError::InvalidRealm),
+    }
+}
+
+// ============ RATE ORACLE ============
+
+/// Seconds in the 365-day year APR targets are quoted over
+pub const SECONDS_PER_YEAR: u128 = 365 * 24 * 60 * 60;
+/// Price time over which an oracle rate may move by its full `max_rate_change_bps`
+pub const RATE_CHANGE_PERIOD: i64 = 60 * 60;
+/// Anchor discriminator of Pyth's `PriceUpdateV2` account
+const PRICE_UPDATE_V2_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+
+/// Oracle rate mode settings (`price_account` is default while disabled)
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RateOracle {
+    /// Pyth `PriceUpdateV2` account pricing the staking token in reward token units
+    pub price_account: Pubkey,
+    /// APR paid on the value staked, in bps
+    pub target_apr_bps: u32,
+    /// Oldest accepted price, in seconds
+    pub max_staleness: i64,
+    /// Widest accepted confidence interval, in bps of the price
+    pub max_confidence_bps: u16,
+    /// Largest rate move per `RATE_CHANGE_PERIOD`, in bps of the current rate
+    pub max_rate_change_bps: u16,
+    /// Reward mint decimals minus staking mint decimals
+    pub decimals_offset: i8,
+    /// Publish time of the last applied price
+    pub last_publish_time: i64,
+}
+
+impl RateOracle {
+    /// Whether the pool is in oracle rate mode
+    pub fn is_enabled(&self) -> bool {
+        self.price_account != Pubkey::default()
+    }
+
+    /// Reject prices that are stale, non-positive or too uncertain
+    pub fn check_price(&self, price: &OraclePrice, now: i64) -> Result<()> {
+        require!(
+            now.saturating_sub(price.publish_time) <= self.max_staleness,
+            StakingError::OraclePriceStale
+        );
+        require!(price.price > 0, StakingError::OraclePriceUncertain);
+        require!(
+            price.conf as u128 * BPS_DENOMINATOR as u128
+                <= price.price as u128 * self.max_confidence_bps as u128,
+            StakingError::OraclePriceUncertain
+        );
+        Ok(())
+    }
+
+    /// Rate paying `target_apr_bps` a year on the value of the stake at `price`
+    pub fn target_rate(&self, price: &OraclePrice) -> Result<RewardRate> {
+        // rate = apr * price * 10^(exponent + decimals_offset) * RATE_SCALE / year
+        let numerator = self.target_apr_bps as u128 * price.price.unsigned_abs() as u128;
+        let denominator = BPS_DENOMINATOR as u128 * SECONDS_PER_YEAR;
+        let exponent = price.exponent as i64 + self.decimals_offset as i64 + 36;
+
+        let rate = if numerator == 0 {
+            0
+        } else if exponent >= 0 {
+            // Long division keeps the remainder below `denominator`, so only the quotient can
+            // overflow, which it does within a few dozen digits
+            let (mut quotient, mut remainder) = (numerator / denominator, numerator % denominator);
+            for _ in 0..exponent {
+                remainder *= 10;
+                quotient = quotient
+                    .checked_mul(10)
+                    .and_then(|q| q.checked_add(remainder / denominator))
+                    .ok_or(StakingError::MathOverflow)?;
+                remainder %= denominator;
+            }
+            quotient
+        } else {
+            10u128
+                .checked_pow(exponent.unsigned_abs() as u32)
+                .map_or(0, |scale| numerator / denominator / scale)
+        };
+
+        Ok(RewardRate(rate))
+    }
+
+    /// `target`, limited to the move `max_rate_change_bps` allows over `elapsed` seconds of
+    /// price time (unbounded from zero)
+    ///
+    /// Moves over more than `RATE_CHANGE_PERIOD` are capped at one period's worth, so a
+    /// rate can move at most `max_rate_change_bps` per period however often it is refreshed.
+    pub fn bounded_rate(
+        &self,
+        current: RewardRate,
+        target: RewardRate,
+        elapsed: i64,
+    ) -> RewardRate {
+        if current.0 == 0 {
+            return target;
+        }
+
+        let bps = self.max_rate_change_bps as u128;
+        let denominator = BPS_DENOMINATOR as u128;
+        let period_change =
+            current.0 / denominator * bps + current.0 % denominator * bps / denominator;
+        let elapsed = elapsed.clamp(0, RATE_CHANGE_PERIOD) as u128;
+        let period = RATE_CHANGE_PERIOD as u128;
+        let max_change =
+            period_change / period * elapsed + period_change % period * elapsed / period;
+        RewardRate(target.0.clamp(current.0 - max_change, current.0.saturating_add(max_change)))
+    }
+}
+
+/// The fields of a Pyth price update the pool relies on (value is `price * 10^exponent`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OraclePrice {
+    pub price: i64,
+    pub conf: u64,
+    pub exponent: i32,
+    pub publish_time: i64,
+}
+
+impl OraclePrice {
+    /// Parse a fully verified Pyth `PriceUpdateV2` account
+    ///
+    /// Ownership is not checked: the pool pins the exact account in `RateOracle`, which
+    /// also lets local tests load mock price accounts.
+    pub fn from_price_update(data: &[u8]) -> Result<Self> {
+        // discriminator (8) | write_authority (32) | verification_level (1, 2 if partial) | ...
+        const VERIFICATION_LEVEL: usize = 8 + 32;
+        const FULL: u8 = 1;
+        require!(
+            data.len() > VERIFICATION_LEVEL && data[..8] == PRICE_UPDATE_V2_DISCRIMINATOR,
+            StakingError::InvalidOracleAccount
+        );
+        require!(data[VERIFICATION_LEVEL] == FULL, StakingError::OraclePriceUncertain);
+
+        // message: feed_id (32) | price (8) | conf (8) | exponent (4) | publish_time (8) | ...
+        let message = &data[VERIFICATION_LEVEL + 1..];
+        require!(message.len() >= 60, StakingError::InvalidOracleAccount);
+        let field = |offset: usize, len: usize| &message[offset..offset + len];
+        Ok(Self {
+            price: i64::from_le_bytes(field(32, 8).try_into().unwrap()),
+            conf: u64::from_le_bytes(field(40, 8).try_into().unwrap()),
+            exponent: i32::from_le_bytes(field(48, 4).try_into().unwrap()),
+            publish_time: i64::from_le_bytes(field(52, 8).try_into().unwrap()),
+        })
+    }
+}
+
+// ============ REWARD RATE ============
+
+/// Square root of `RATE_SCALE`, used to split products that would overflow a u128
+const RATE_HALF_SCALE: u128 = 1_000_000_000_000_000_000;
+/// Fixed-point scale of `RewardRate` (1e36)
+pub const RATE_SCALE: u128 = RATE_HALF_SCALE * RATE_HALF_SCALE;
+
+/// Reward base units paid per staked base unit per second, fixed-point scaled by `RATE_SCALE`
+#[derive(
+    AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, Default, PartialEq, Eq,
+)]
+pub struct RewardRate(pub u128);
+
+impl RewardRate {
+    /// Rate paying `reward_amount` for every `per_staked_amount` staked over `period_seconds`
+    ///
+    /// Rounds down; `None` if either divisor is zero or the rate does not fit.
+    pub fn per_period(reward_amount: u64, per_staked_amount: u64, period_seconds: u64) -> Option<Self> {
+        if per_staked_amount == 0 || period_seconds == 0 {
+            return None;
+        }
+
+        // floor(reward * S² / (per * period)) == floor(floor(reward * S * S / per) / period)
+        let numerator = reward_amount as u128 * RATE_HALF_SCALE;
+        let per = per_staked_amount as u128;
+        let per_staked = (numerator / per)
+            .checked_mul(RATE_HALF_SCALE)?
+            .checked_add(numerator % per * RATE_HALF_SCALE / per)?;
+
+        Some(Self(per_staked / period_seconds as u128))
+    }
+
+    /// Rewards earned by `staked_amount` over `elapsed` seconds, rounded down
+    ///
+    /// Exact for every input; `None` only when the result does not fit in a u64.
+    pub fn rewards_for(&self, staked_amount: u64, elapsed: u64) -> Option<u64> {
+        // With x = staked * elapsed = xh*S + xl and rate = rh*S + rl (S = RATE_HALF_SCALE):
+        // x * rate / S² = xh*rh + floor((xh*rl + xl*rh + floor(xl*rl / S)) / S)
+        // and none of the partial products can overflow a u128.
+        let x = staked_amount as u128 * elapsed as u128;
+        let (xh, xl) = (x / RATE_HALF_SCALE, x % RATE_HALF_SCALE);
+        let (rh, rl) = (self.0 / RATE_HALF_SCALE, self.0 % RATE_HALF_SCALE);
+
+        let cross = (xh * rl)
+            .checked_add(xl * rh)?
+            .checked_add(xl * rl / RATE_HALF_SCALE)?;
+        let rewards = xh.checked_mul(rh)?.checked_add(cross / RATE_HALF_SCALE)?;
+
+        u64::try_from(rewards).ok()
+    }
+}
+
+/// Rewards accrued per staked base unit since the pool started, scaled by `RATE_SCALE`
+///
+/// Settled before every rate change, so a new rate only applies from then on. The value
+/// wraps: only differences are meaningful, and they are exact while a position's unsettled
+/// share stays below 2^128 / `RATE_SCALE` (about 340) reward units per staked unit.
+#[derive(
+    AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, Default, PartialEq, Eq,
+)]
+pub struct RewardIndex {
+    /// Accrued rewards per staked base unit at `updated_at`
+    pub value: u128,
+    /// Pool accrual clock (see `StakePool::accrual_time`) the value was settled at
+    pub updated_at: i64,
+}
+
+impl RewardIndex {
+    /// The index at `accrual_time` if `rate` has applied since `updated_at`
+    pub fn at(&self, rate: RewardRate, accrual_time: i64) -> u128 {
+        let elapsed = accrual_time.saturating_sub(self.updated_at).max(0) as u128;
+        self.value.wrapping_add(rate.0.wrapping_mul(elapsed))
+    }
+}
+
+/// Rewards earned by `staked_amount` while the reward index rose by `index_delta`
+///
+/// Fails with `MathOverflow` instead of truncating when the result does not fit in a u64.
+pub fn index_rewards(staked_amount: u64, index_delta: u128) -> Result<u64> {
+    // An index rise is a rate integrated over time: that rate paid for one second
+    Ok(RewardRate(index_delta)
+        .rewards_for(staked_amount, 1)
+        .ok_or(StakingError::MathOverflow)?)
+}
+
+// ============ MERKLE DISTRIBUTOR ============
+
+/// Most leaves a distributor epoch can have (an 8 KiB claimed bitmap)
+pub const MAX_DISTRIBUTOR_LEAVES: u32 = 65_536;
+
+/// Pool-wide distributor bookkeeping
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DistributorState {
+    /// Epochs posted so far (the next epoch's number)
+    pub epochs: u64,
+    /// Reward vault tokens owed to open epochs' unclaimed leaves
+    pub reserved: u64,
+}
+
+/// Merkle leaf of `claimant`'s `amount` at `index`
+///
+/// Leaves and inner nodes get distinct prefixes so an inner node can't pass as a leaf.
+pub fn distributor_leaf(index: u32, claimant: &Pubkey, amount: u64) -> [u8; 32] {
+    hashv(&[&[0], &index.to_le_bytes(), claimant.as_ref(), &amount.to_le_bytes()]).to_bytes()
+}
+
+/// Parent of two Merkle nodes, hashed in sorted order so proofs need no directions
+pub fn distributor_node(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (low, high) = if a <= b { (a, b) } else { (b, a) };
+    hashv(&[&[1], low, high]).to_bytes()
+}
+
+/// Whether `proof` links `leaf` to `root`
+pub fn verify_distributor_proof(proof: &[[u8; 32]], root: &[u8; 32], leaf: [u8; 32]) -> bool {
+    proof.iter().fold(leaf, |node, sibling| distributor_node(&node, sibling)) == *root
+}
+
+// ============ HELPER FUNCTIONS ============
+
+/// Move the pool's rate towards the oracle target for a checked price not applied yet
+fn apply_oracle_price(pool: &mut Account<StakePool>, price: &OraclePrice) -> Result<()> {
+    if price.publish_time <= pool.rate_oracle.last_publish_time {
+        return Ok(());
+    }
+
+    let old_rate = pool.reward_rate;
+    let target = pool.rate_oracle.target_rate(price)?;
+    let elapsed = price
+        .publish_time
+        .saturating_sub(pool.rate_oracle.last_publish_time);
+    let now = Clock::get()?.unix_timestamp;
+    let new_rate = pool.rate_oracle.bounded_rate(old_rate, target, elapsed);
+    pool.set_reward_rate(new_rate, now);
+    pool.rate_oracle.last_publish_time = price.publish_time;
+
+    if pool.reward_rate != old_rate {
+        emit!(RewardRateUpdated {
+            pool: pool.key(),
+            old_rate,
+            new_rate: pool.reward_rate,
+            timestamp: now,
+        });
+    }
+    Ok(())
+}
+
+/// Refresh an oracle-mode pool's rate from the price account leading `remaining_accounts`
+///
+/// Returns the remaining accounts after the price account. Unusable prices keep the
+/// current rate so an oracle outage never blocks stakes, unstakes or claims.
+fn refresh_oracle_rate<'a, 'info>(
+    pool: &mut Account<StakePool>,
+    remaining_accounts: &'a [AccountInfo<'info>],
+    now: i64,
+) -> Result<&'a [AccountInfo<'info>]> {
+    if !pool.rate_oracle.is_enabled() {
+        return Ok(remaining_accounts);
+    }
+
+    let (price_account, rest) = remaining_accounts
+        .split_first()
+        .filter(|(account, _)| account.key() == pool.rate_oracle.price_account)
+        .ok_or(StakingError::InvalidOracleAccount)?;
+    let price = OraclePrice::from_price_update(&price_account.try_borrow_data()?)?;
+    if pool.rate_oracle.check_price(&price, now).is_ok() {
+        apply_oracle_price(pool, &price)?;
+    }
+    Ok(rest)
+}
+
+/// Move rewards accrued since the last checkpoint into `pending_rewards`
+///
+/// Referred positions also accrue their referrer's share into `referral_pending`. Moves
+/// `reward_index_paid` up to `accrual_time`; callers are responsible for moving
+/// `last_accrual_time` forward.
+fn accrue_rewards(pool: &StakePool, user_stake: &mut UserStake, accrual_time: i64) -> Result<()> {
+    let pending = claimable_rewards(pool, user_stake, accrual_time)?;
+    if user_stake.referrer != Pubkey::default() {
+        let accrued = pending
+            .checked_sub(user_stake.pending_rewards)
+            .ok_or(StakingError::MathOverflow)?;
+        user_stake.referral_pending = user_stake
+            .referral_pending
+            .checked_add(referral_bonus(accrued, pool.referral_bps))
+            .ok_or(StakingError::MathOverflow)?;
+    }
+    user_stake.pending_rewards = pending;
+    user_stake.reward_index_paid = pool.reward_index_at(accrual_time);
+    Ok(())
+}
+
+/// Stored pending rewards plus everything accrued since the last checkpoint
+pub fn claimable_rewards(pool: &StakePool, user_stake: &UserStake, accrual_time: i64) -> Result<u64> {
+    if user_stake.staked_amount == 0 {
+        return Ok(user_stake.pending_rewards);
+    }
+    let index_delta = pool
+        .reward_index_at(accrual_time)
+        .wrapping_sub(user_stake.reward_index_paid);
+    let pending = index_rewards(user_stake.staked_amount, index_delta)?;
+    let pending = boosted(pending, user_stake.tier_multiplier_bps)
+        .and_then(|pending| boosted(pending, user_stake.boost_bps(&pool.nft_boost)))
+        .ok_or(StakingError::MathOverflow)?;
+    Ok(user_stake
+        .pending_rewards
+        .checked_add(pending)
+        .ok_or(StakingError::MathOverflow)?)
+}
+
+/// Reset a freshly created (or re-created after close) position for `owner`
+fn init_position(user_stake: &mut UserStake, owner: Pubkey, pool: Pubkey, bump: u8) {
+    user_stake.owner = owner;
+    user_stake.pool = pool;
+    user_stake.staked_amount = 0;
+    user_stake.pending_rewards = 0;
+    user_stake.stake_start_time = 0;
+    user_stake.total_rewards_claimed = 0;
+    user_stake.claim_delegate = Pubkey::default();
+    user_stake.reward_destination = Pubkey::default();
+    user_stake.referrer = Pubkey::default();
+    user_stake.referral_pending = 0;
+    user_stake.boost_mint = Pubkey::default();
+    user_stake.tier = 0;
+    user_stake.tier_multiplier_bps = BPS_DENOMINATOR;
+    user_stake.campaign_rewards = Default::default();
+    user_stake.reward_index_paid = 0;
+    user_stake.bump = bump;
+}
+
+/// Settle accrued rewards and add `amount` to a position, initializing it on first stake
+fn credit_stake(
+    pool: &mut Account<StakePool>,
+    user_stake: &mut Account<UserStake>,
+    owner: Pubkey,
+    bump: u8,
+    amount: u64,
+    now: i64,
+) -> Result<()> {
+    // Check minimum stake requirement
+    let new_total = user_stake
+        .staked_amount
+        .checked_add(amount)
+        .ok_or(StakingError::MathOverflow)?;
+    require!(
+        new_total >= pool.tiers.min_stake(),
+        StakingError::BelowMinimumStake
+    );
+
+    // Initialize user stake account if first time (or first time since it was closed)
+    if user_stake.owner == Pubkey::default() {
+        init_position(user_stake, owner, pool.key(), bump);
+    }
+
+    // Calculate pending rewards before updating stake
+    let accrual_time = pool.accrual_time(now);
+    accrue_rewards(pool, user_stake, accrual_time)?;
+    settle_campaigns(pool, user_stake, now)?;
+
+    // Update state
+    user_stake.staked_amount = new_total;
+    user_stake.last_stake_time = now;
+    user_stake.last_accrual_time = accrual_time;
+    assign_tier(&pool.tiers, user_stake, now);
+
+    // Only set start time if this is a new stake
+    if user_stake.stake_start_time == 0 {
+        user_stake.stake_start_time = now;
+    }
+
+    pool.total_staked = pool
+        .total_staked
+        .checked_add(amount)
+        .ok_or(StakingError::MathOverflow)?;
+    pool.last_update_time = now;
+
+    Ok(())
+}
+
+/// Settle accrued rewards and remove `amount` from a position, enforcing the lock period
+fn debit_stake(pool: &mut StakePool, user_stake: &mut UserStake, amount: u64, now: i64) -> Result<()> {
+    require!(amount > 0, StakingError::InvalidAmount);
+    require!(
+        user_stake.staked_amount >= amount,
+        StakingError::InsufficientStake
+    );
+
+    // Check lock period
+    let time_staked = now
+        .checked_sub(user_stake.stake_start_time)
+        .ok_or(StakingError::MathOverflow)?;
+    require!(time_staked >= pool.lock_period, StakingError::StillLocked);
+
+    // Calculate and store pending rewards
+    let accrual_time = pool.accrual_time(now);
+    accrue_rewards(pool, user_stake, accrual_time)?;
+    settle_campaigns(pool, user_stake, now)?;
+
+    // Update state
+    user_stake.staked_amount = user_stake
+        .staked_amount
+        .checked_sub(amount)
+        .ok_or(StakingError::MathOverflow)?;
+    user_stake.last_stake_time = now;
+    user_stake.last_accrual_time = accrual_time;
+
+    pool.total_staked = pool
+        .total_staked
+        .checked_sub(amount)
+        .ok_or(StakingError::MathOverflow)?;
+    pool.last_update_time = now;
+
+    // Reset stake start time if fully unstaked
+    if user_stake.staked_amount == 0 {
+        user_stake.stake_start_time = 0;
+    }
+    assign_tier(&pool.tiers, user_stake, now);
+
+    Ok(())
+}
+
+/// Claim a freshly created checkpoint history for `owner`'s stake
+///
+/// `held` is what the position held before this instruction; a history started over a
+/// position opened before histories existed doesn't know its earlier balances. No-op for
+/// histories that already exist.
+fn init_checkpoints(
+    checkpoints: &mut StakeCheckpoints,
+    pool: Pubkey,
+    owner: Pubkey,
+    bump: u8,
+    held: u64,
+) {
+    if checkpoints.pool == Pubkey::default() {
+        checkpoints.pool = pool;
+        checkpoints.owner = owner;
+        checkpoints.truncated = held > 0;
+        checkpoints.bump = bump;
+    }
+}
+
+/// Set up a freshly created page of the pool total's checkpoint ring
+fn init_checkpoint_page(page: &mut CheckpointPage, pool: Pubkey, index: u32, bump: u8) {
+    page.pool = pool;
+    page.page = index;
+    page.bump = bump;
+}
+
+/// Record the pool's `total_staked` in its checkpoint ring
+fn record_pool_total(
+    pool: &mut StakePool,
+    current: &mut CheckpointPage,
+    next: &mut CheckpointPage,
+    clock: &Clock,
+) {
+    pool.checkpoint_ring.record(current, next, pool.total_staked, clock);
+}
+
+/// Settle accrued rewards and take everything claimable out of a position
+fn take_rewards(pool: &StakePool, user_stake: &mut UserStake, now: i64) -> Result<u64> {
+    let accrual_time = pool.accrual_time(now);
+    accrue_rewards(pool, user_stake, accrual_time)?;
+
+    let total_rewards = user_stake.pending_rewards;
+    require!(total_rewards > 0, StakingError::NoRewards);
+
+    // Reset pending rewards and update timestamp
+    user_stake.pending_rewards = 0;
+    user_stake.last_stake_time = now;
+    user_stake.last_accrual_time = accrual_time;
+    user_stake.total_rewards_claimed = user_stake
+        .total_rewards_claimed
+        .checked_add(total_rewards)
+        .ok_or(StakingError::MathOverflow)?;
+
+    Ok(total_rewards)
+}
+
+/// Move a whole position into `destination`, returning the staked and pending amounts moved
+///
+/// Keeps the later of the two stake starts so neither lock is shortened.
+fn move_position(
+    pool: &mut StakePool,
+    source: &mut UserStake,
+    destination: &mut UserStake,
+    now: i64,
+) -> Result<(u64, u64)> {
+    let amount = source.staked_amount;
+    let accrual_time = pool.accrual_time(now);
+    accrue_rewards(pool, source, accrual_time)?;
+    accrue_rewards(pool, destination, accrual_time)?;
+    settle_campaigns(pool, source, now)?;
+    settle_campaigns(pool, destination, now)?;
+
+    let pending_rewards = source.pending_rewards;
+    destination.staked_amount = destination
+        .staked_amount
+        .checked_add(amount)
+        .ok_or(StakingError::MathOverflow)?;
+    destination.pending_rewards = destination
+        .pending_rewards
+        .checked_add(pending_rewards)
+        .ok_or(StakingError::MathOverflow)?;
+    destination.stake_start_time = destination.stake_start_time.max(source.stake_start_time);
+    destination.last_stake_time = now;
+    destination.last_accrual_time = accrual_time;
+    // Both are settled to the same campaign indexes, so shares move as they are
+    for (from, to) in source.campaign_rewards.iter_mut().zip(&mut destination.campaign_rewards) {
+        to.pending = to
+            .pending
+            .checked_add(from.pending)
+            .ok_or(StakingError::MathOverflow)?;
+        from.pending = 0;
+    }
+
+    source.staked_amount = 0;
+    source.pending_rewards = 0;
+    source.stake_start_time = 0;
+    source.last_stake_time = now;
+    source.last_accrual_time = accrual_time;
+
+    assign_tier(&pool.tiers, source, now);
+    assign_tier(&pool.tiers, destination, now);
+
+    Ok((amount, pending_rewards))
+}
+
+/// Receipt tokens minted for depositing `amount` at the liquid exchange rate
+pub fn receipts_for_deposit(amount: u64, total_underlying: u64, receipt_supply: u64) -> Result<u64> {
+    if receipt_supply == 0 || total_underlying == 0 {
+        return Ok(amount);
+    }
+    let receipts = (amount as u128)
+        .checked_mul(receipt_supply as u128)
+        .and_then(|v| v.checked_div(total_underlying as u128))
+        .ok_or(StakingError::MathOverflow)?;
+    u64::try_from(receipts).map_err(|_| error!(StakingError::MathOverflow))
+}
+
+/// Underlying tokens redeemed for burning `receipts` at the liquid exchange rate
+pub fn underlying_for_receipts(receipts: u64, total_underlying: u64, receipt_supply: u64) -> Result<u64> {
+    require!(receipts <= receipt_supply, StakingError::InsufficientStake);
+    let amount = (receipts as u128)
+        .checked_mul(total_underlying as u128)
+        .and_then(|v| v.checked_div(receipt_supply as u128))
+        .ok_or(StakingError::MathOverflow)?;
+    u64::try_from(amount).map_err(|_| error!(StakingError::MathOverflow))
+}
+
+/// Move the liquid position's accrued rewards from the reward vault into its stake
+///
+/// Always accrues; compounds as much as the reward vault holds unless compounding is
+/// paused, and the remainder stays pending.
+fn compound_liquid_rewards<'info>(
+    pool: &mut Account<'info, StakePool>,
+    liquid_stake: &mut UserStake,
+    reward_vault: &Account<'info, TokenAccount>,
+    pool_vault: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    now: i64,
+) -> Result<u64> {
+    let accrual_time = pool.accrual_time(now);
+    accrue_rewards(pool, liquid_stake, accrual_time)?;
+    liquid_stake.last_accrual_time = accrual_time;
+    if pool.is_paused(PAUSE_COMPOUND) {
+        return Ok(0);
+    }
+
+    let amount = liquid_stake
+        .pending_rewards
+        .min(pool.available_rewards(reward_vault.amount));
+    if amount == 0 {
+        return Ok(0);
+    }
+
+    let staking_mint_key = pool.staking_mint;
+    let seeds = &[
+        b"pool".as_ref(),
+        staking_mint_key.as_ref(),
+        &[pool.bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    let cpi_accounts = Transfer {
+        from: reward_vault.to_account_info(),
+        to: pool_vault,
+        authority: pool.to_account_info(),
+    };
+    token::transfer(
+        CpiContext::new_with_signer(token_program, cpi_accounts, signer),
+        amount,
+    )?;
+
+    pool.update_campaigns(now)?;
+    pool.campaigns.excluded_stake = pool
+        .campaigns
+        .excluded_stake
+        .checked_add(amount)
+        .ok_or(StakingError::MathOverflow)?;
+    liquid_stake.pending_rewards = liquid_stake
+        .pending_rewards
+        .checked_sub(amount)
+        .ok_or(StakingError::MathOverflow)?;
+    liquid_stake.staked_amount = liquid_stake
+        .staked_amount
+        .checked_add(amount)
+        .ok_or(StakingError::MathOverflow)?;
+    liquid_stake.total_rewards_claimed = liquid_stake
+        .total_rewards_claimed
+        .checked_add(amount)
+        .ok_or(StakingError::MathOverflow)?;
+    pool.total_staked = pool
+        .total_staked
+        .checked_add(amount)
+        .ok_or(StakingError::MathOverflow)?;
+    pool.last_update_time = now;
+
+    emit!(LiquidCompounded {
+        pool: pool.key(),
+        amount,
+        total_underlying: liquid_stake.staked_amount,
+        timestamp: now,
+    });
+
+    Ok(amount)
+}
+
+/// Write a position's staked amount and unlock time into its NFT metadata
+fn update_position_metadata<'info>(
+    pool: &Account<'info, StakePool>,
+    position_mint: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    position: &UserStake,
+) -> Result<()> {
+    let staking_mint_key = pool.staking_mint;
+    let seeds = &[
+        b"pool".as_ref(),
+        staking_mint_key.as_ref(),
+        &[pool.bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    let fields = [
+        (POSITION_STAKED_AMOUNT_KEY, position.staked_amount.to_string()),
+        (POSITION_UNLOCK_TIME_KEY, position.unlock_time(pool.lock_period).to_string()),
+    ];
+    for (key, value) in fields {
+        token_metadata_update_field(
+            CpiContext::new_with_signer(
+                token_program.clone(),
+                TokenMetadataUpdateField {
+                    program_id: token_program.clone(),
+                    metadata: position_mint.clone(),
+                    update_authority: pool.to_account_info(),
+                },
+                signer,
+            ),
+            Field::Key(key.to_string()),
+            value,
+        )?;
+    }
+
+    Ok(())
+}
+
+// ============ ACCOUNT CONTEXTS ============
+
+#[derive(Accounts)]
+pub struct InitializePool<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + StakePool::INIT_SPACE,
+        seeds = [b"pool", staking_mint.key().as_ref()],
+        bump
+    )]
+    pub pool: Account<'info, StakePool>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + CheckpointPage::INIT_SPACE,
+        seeds = [b"pool_checkpoints", pool.key().as_ref(), 0u32.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub pool_checkpoints: Box<Account<'info, CheckpointPage>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + CheckpointPage::INIT_SPACE,
+        seeds = [b"pool_checkpoints", pool.key().as_ref(), 1u32.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub next_pool_checkpoints: Box<Account<'info, CheckpointPage>>,
+
+    /// The staking token mint (can be Token or Token-2022)
+    pub staking_mint: InterfaceAccount<'info, MintInterface>,
+    /// The reward token mint (USDC - regular Token program)
+    pub reward_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = staking_mint,
+        token::authority = pool,
+        token::token_program = staking_token_program,
+        seeds = [b"pool_vault", pool.key().as_ref()],
+        bump
+    )]
+    pub pool_vault: InterfaceAccount<'info, TokenAccountInterface>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = reward_mint,
+        token::authority = pool,
+        seeds = [b"reward_vault", pool.key().as_ref()],
+        bump
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+
+    pub system_program: Program<'info, System>,
+    /// Token program for staking token (Token or Token-2022)
+    pub staking_token_program: Interface<'info, TokenInterface>,
+    /// Token program for reward token (regular Token)
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct Stake<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, StakePool>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserStake::INIT_SPACE,
+        seeds = [b"user_stake", pool.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + StakeCheckpoints::INIT_SPACE,
+        seeds = [b"checkpoints", pool.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_checkpoints: Account<'info, StakeCheckpoints>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pool_checkpoints",
+            pool.key().as_ref(),
+            pool.checkpoint_ring.current.to_le_bytes().as_ref(),
+        ],
+        bump = pool_checkpoints.bump,
+    )]
+    pub pool_checkpoints: Box<Account<'info, CheckpointPage>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pool_checkpoints",
+            pool.key().as_ref(),
+            pool.checkpoint_ring.next.to_le_bytes().as_ref(),
+        ],
+        bump = next_pool_checkpoints.bump,
+    )]
+    pub next_pool_checkpoints: Box<Account<'info, CheckpointPage>>,
+
+    /// The staking token mint
+    #[account(
+        constraint = staking_mint.key() == pool.staking_mint @ StakingError::InvalidMint,
+    )]
+    pub staking_mint: InterfaceAccount<'info, MintInterface>,
+
+    #[account(
+        mut,
+        constraint = user_token_account.owner == user.key() @ StakingError::InvalidOwner,
+        constraint = user_token_account.mint == pool.staking_mint @ StakingError::InvalidMint,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccountInterface>,
+
+    #[account(
+        mut,
+        seeds = [b"pool_vault", pool.key().as_ref()],
+        bump,
+    )]
+    pub pool_vault: InterfaceAccount<'info, TokenAccountInterface>,
+
+    /// Treasury receiving the fee, required while the pool charges one
+    #[account(
+        mut,
+        address = pool.fees.staking_treasury @ StakingError::InvalidFeeTreasury,
+    )]
+    pub staking_treasury: Option<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    pub system_program: Program<'info, System>,
+    /// Token program for staking token (Token or Token-2022)
+    pub staking_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(beneficiary: Pubkey)]
+pub struct StakeFor<'info> {
+    #[account(mut)]
+    pub funder: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, StakePool>,
+
+    #[account(
+        init_if_needed,
+        payer = funder,
+        space = 8 + UserStake::INIT_SPACE,
+        seeds = [b"user_stake", pool.key().as_ref(), beneficiary.as_ref()],
+        bump
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    #[account(
+        init_if_needed,
+        payer = funder,
+        space = 8 + StakeCheckpoints::INIT_SPACE,
+        seeds = [b"checkpoints", pool.key().as_ref(), beneficiary.as_ref()],
+        bump
+    )]
+    pub user_checkpoints: Account<'info, StakeCheckpoints>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pool_checkpoints",
+            pool.key().as_ref(),
+            pool.checkpoint_ring.current.to_le_bytes().as_ref(),
+        ],
+        bump = pool_checkpoints.bump,
+    )]
+    pub pool_checkpoints: Box<Account<'info, CheckpointPage>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pool_checkpoints",
+            pool.key().as_ref(),
+            pool.checkpoint_ring.next.to_le_bytes().as_ref(),
+        ],
+        bump = next_pool_checkpoints.bump,
+    )]
+    pub next_pool_checkpoints: Box<Account<'info, CheckpointPage>>,
+
+    /// The staking token mint
+    #[account(
+        constraint = staking_mint.key() == pool.staking_mint @ StakingError::InvalidMint,
+    )]
+    pub staking_mint: InterfaceAccount<'info, MintInterface>,
+
+    #[account(
+        mut,
+        constraint = funder_token_account.owner == funder.key() @ StakingError::InvalidOwner,
+        constraint = funder_token_account.mint == pool.staking_mint @ StakingError::InvalidMint,
+    )]
+    pub funder_token_account: InterfaceAccount<'info, TokenAccountInterface>,
+
+    #[account(
+        mut,
+        seeds = [b"pool_vault", pool.key().as_ref()],
+        bump,
+    )]
+    pub pool_vault: InterfaceAccount<'info, TokenAccountInterface>,
+
+    /// Treasury receiving the fee, required while the pool charges one
+    #[account(
+        mut,
+        address = pool.fees.staking_treasury @ StakingError::InvalidFeeTreasury,
+    )]
+    pub staking_treasury: Option<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    pub system_program: Program<'info, System>,
+    /// Token program for staking token (Token or Token-2022)
+    pub staking_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct Unstake<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, StakePool>,
+
+    #[account(
+        mut,
+        seeds = [b"user_stake", pool.key().as_ref(), user.key().as_ref()],
+        bump = user_stake.bump,
+        constraint = user_stake.owner == user.key() @ StakingError::InvalidOwner,
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + StakeCheckpoints::INIT_SPACE,
+        seeds = [b"checkpoints", pool.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_checkpoints: Account<'info, StakeCheckpoints>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pool_checkpoints",
+            pool.key().as_ref(),
+            pool.checkpoint_ring.current.to_le_bytes().as_ref(),
+        ],
+        bump = pool_checkpoints.bump,
+    )]
+    pub pool_checkpoints: Box<Account<'info, CheckpointPage>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pool_checkpoints",
+            pool.key().as_ref(),
+            pool.checkpoint_ring.next.to_le_bytes().as_ref(),
+        ],
+        bump = next_pool_checkpoints.bump,
+    )]
+    pub next_pool_checkpoints: Box<Account<'info, CheckpointPage>>,
+
+    /// The staking token mint
+    #[account(
+        constraint = staking_mint.key() == pool.staking_mint @ StakingError::InvalidMint,
+    )]
+    pub staking_mint: InterfaceAccount<'info, MintInterface>,
+
+    #[account(
+        mut,
+        constraint = user_token_account.owner == user.key() @ StakingError::InvalidOwner,
+        constraint = user_token_account.mint == pool.staking_mint @ StakingError::InvalidMint,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccountInterface>,
+
+    #[account(
+        mut,
+        seeds = [b"pool_vault", pool.key().as_ref()],
+        bump,
+    )]
+    pub pool_vault: InterfaceAccount<'info, TokenAccountInterface>,
+
+    /// Treasury receiving the fee, required while the pool charges one
+    #[account(
+        mut,
+        address = pool.fees.staking_treasury @ StakingError::InvalidFeeTreasury,
+    )]
+    pub staking_treasury: Option<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    pub system_program: Program<'info, System>,
+    /// Token program for staking token (Token or Token-2022)
+    pub staking_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRewards<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, StakePool>,
+
+    #[account(
+        mut,
+        seeds = [b"user_stake", pool.key().as_ref(), user.key().as_ref()],
+        bump = user_stake.bump,
+        constraint = user_stake.owner == user.key() @ StakingError::InvalidOwner,
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    #[account(
+        mut,
+        constraint = user_reward_account.owner == user.key() @ StakingError::InvalidOwner,
+        constraint = user_reward_account.mint == pool.reward_mint @ StakingError::InvalidMint,
+    )]
+    pub user_reward_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"reward_vault", pool.key().as_ref()],
+        bump,
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+
+    /// Treasury receiving the performance fee, required while the pool charges one
+    #[account(
+        mut,
+        address = pool.fees.reward_treasury @ StakingError::InvalidFeeTreasury,
+    )]
+    pub reward_treasury: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRewardsFor<'info> {
+    /// Position owner or its claim delegate
+    pub claimer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, StakePool>,
+
+    #[account(
+        mut,
+        seeds = [b"user_stake", pool.key().as_ref(), user_stake.owner.as_ref()],
+        bump = user_stake.bump,
+        constraint = user_stake.can_claim(&claimer.key()) @ StakingError::Unauthorized,
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    #[account(
+        mut,
+        constraint = reward_destination.mint == pool.reward_mint @ StakingError::InvalidMint,
+        constraint = user_stake.accepts_reward_destination(
+            &reward_destination.key(),
+            &reward_destination.owner,
+        ) @ StakingError::InvalidRewardDestination,
+    )]
+    pub reward_destination: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"reward_vault", pool.key().as_ref()],
+        bump,
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+
+    /// Treasury receiving the performance fee, required while the pool charges one
+    #[account(
+        mut,
+        address = pool.fees.reward_treasury @ StakingError::InvalidFeeTreasury,
+    )]
+    pub reward_treasury: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CrankClaim<'info> {
+    pub cranker: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, StakePool>,
+
+    #[account(
+        mut,
+        constraint = cranker_reward_account.owner == cranker.key() @ StakingError::InvalidOwner,
+        constraint = cranker_reward_account.mint == pool.reward_mint @ StakingError::InvalidMint,
+    )]
+    pub cranker_reward_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"reward_vault", pool.key().as_ref()],
+        bump,
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+
+    /// Treasury receiving the performance fee, required while the pool charges one
+    #[account(
+        mut,
+        address = pool.fees.reward_treasury @ StakingError::InvalidFeeTreasury,
+    )]
+    pub reward_treasury: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(referrer: Pubkey)]
+pub struct RegisterReferrer<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, StakePool>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserStake::INIT_SPACE,
+        seeds = [b"user_stake", pool.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + ReferrerStats::INIT_SPACE,
+        seeds = [b"referrer", pool.key().as_ref(), referrer.as_ref()],
+        bump
+    )]
+    pub referrer_stats: Account<'info, ReferrerStats>,
+
+    /// The referrer's own position, which must hold at least the pool's minimum stake
+    #[account(
+        seeds = [b"user_stake", pool.key().as_ref(), referrer.as_ref()],
+        bump = referrer_stake.bump,
+        constraint = referrer_stake.staked_amount >= pool.tiers.min_stake().max(1)
+            @ StakingError::ReferrerNotStaked,
+    )]
+    pub referrer_stake: Account<'info, UserStake>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimReferralRewards<'info> {
+    #[account(
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, StakePool>,
+
+    #[account(
+        mut,
+        seeds = [b"referrer", pool.key().as_ref(), referrer_stats.referrer.as_ref()],
+        bump = referrer_stats.bump,
+    )]
+    pub referrer_stats: Account<'info, ReferrerStats>,
+
+    #[account(
+        mut,
+        constraint = referrer_reward_account.owner == referrer_stats.referrer @ StakingError::InvalidOwner,
+        constraint = referrer_reward_account.mint == pool.reward_mint @ StakingError::InvalidMint,
+    )]
+    pub referrer_reward_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"reward_vault", pool.key().as_ref()],
+        bump,
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateUserStake<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, StakePool>,
+
+    #[account(
+        mut,
+        seeds = [b"user_stake", pool.key().as_ref(), user.key().as_ref()],
+        bump = user_stake.bump,
+        constraint = user_stake.owner == user.key() @ StakingError::InvalidOwner,
+    )]
+    pub user_stake: Account<'info, UserStake>,
+}
+
+#[derive(Accounts)]
+pub struct SetRewardDestination<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, StakePool>,
+
+    #[account(
+        mut,
+        seeds = [b"user_stake", pool.key().as_ref(), user.key().as_ref()],
+        bump = user_stake.bump,
+        constraint = user_stake.owner == user.key() @ StakingError::InvalidOwner,
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    #[account(
+        constraint = reward_destination.mint == pool.reward_mint @ StakingError::InvalidMint,
+    )]
+    pub reward_destination: Option<Account<'info, TokenAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct AttachBoostNft<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, StakePool>>,
+
+    #[account(
+        mut,
+        seeds = [b"user_stake", pool.key().as_ref(), user.key().as_ref()],
+        bump = user_stake.bump,
+        constraint = user_stake.owner == user.key() @ StakingError::InvalidOwner,
+    )]
+    pub user_stake: Box<Account<'info, UserStake>>,
+
+    #[account(
+        mint::decimals = 0,
+        constraint = nft_mint.supply == 1 @ StakingError::InvalidBoostNft,
+    )]
+    pub nft_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+    /// CHECK: the Metaplex metadata PDA of `nft_mint`, parsed by `verified_collection`
+    #[account(
+        seeds = [b"metadata", TOKEN_METADATA_PROGRAM_ID.as_ref(), nft_mint.key().as_ref()],
+        seeds::program = TOKEN_METADATA_PROGRAM_ID,
+        bump,
+        owner = TOKEN_METADATA_PROGRAM_ID @ StakingError::InvalidBoostNft,
+    )]
+    pub nft_metadata: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        constraint = user_nft_account.owner == user.key() @ StakingError::InvalidOwner,
+        constraint = user_nft_account.mint == nft_mint.key() @ StakingError::InvalidMint,
+    )]
+    pub user_nft_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    #[account(
+        init,
+        payer = user,
+        seeds = [b"boost_escrow", user_stake.key().as_ref()],
+        bump,
+        token::mint = nft_mint,
+        token::authority = pool,
+        token::token_program = nft_token_program,
+    )]
+    pub boost_escrow: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    pub system_program: Program<'info, System>,
+    /// Token program owning `nft_mint` (Token or Token-2022)
+    pub nft_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct DetachBoostNft<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, StakePool>>,
+
+    #[account(
+        mut,
+        seeds = [b"user_stake", pool.key().as_ref(), user.key().as_ref()],
+        bump = user_stake.bump,
+        constraint = user_stake.owner == user.key() @ StakingError::InvalidOwner,
+        constraint = user_stake.boost_mint != Pubkey::default() @ StakingError::NoBoostNft,
+    )]
+    pub user_stake: Box<Account<'info, UserStake>>,
+
+    #[account(address = user_stake.boost_mint @ StakingError::InvalidBoostNft)]
+    pub nft_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+    #[account(
+        mut,
+        constraint = user_nft_account.owner == user.key() @ StakingError::InvalidOwner,
+        constraint = user_nft_account.mint == nft_mint.key() @ StakingError::InvalidMint,
+    )]
+    pub user_nft_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    #[account(
+        mut,
+        seeds = [b"boost_escrow", user_stake.key().as_ref()],
+        bump,
+    )]
+    pub boost_escrow: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    /// Token program owning `nft_mint` (Token or Token-2022)
+    pub nft_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(new_owner: Pubkey)]
+pub struct TransferStake<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, StakePool>,
+
+    #[account(
+        mut,
+        seeds = [b"user_stake", pool.key().as_ref(), user.key().as_ref()],
+        bump = user_stake.bump,
+        constraint = user_stake.owner == user.key() @ StakingError::InvalidOwner,
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserStake::INIT_SPACE,
+        seeds = [b"user_stake", pool.key().as_ref(), new_owner.as_ref()],
+        bump
+    )]
+    pub destination_stake: Account<'info, UserStake>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + StakeCheckpoints::INIT_SPACE,
+        seeds = [b"checkpoints", pool.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_checkpoints: Account<'info, StakeCheckpoints>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + StakeCheckpoints::INIT_SPACE,
+        seeds = [b"checkpoints", pool.key().as_ref(), new_owner.as_ref()],
+        bump
+    )]
+    pub destination_checkpoints: Account<'info, StakeCheckpoints>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct TokenizeStake<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, StakePool>>,
+
+    #[account(
+        mut,
+        seeds = [b"user_stake", pool.key().as_ref(), user.key().as_ref()],
+        bump = user_stake.bump,
+        constraint = user_stake.owner == user.key() @ StakingError::InvalidOwner,
+    )]
+    pub user_stake: Box<Account<'info, UserStake>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + StakeCheckpoints::INIT_SPACE,
+        seeds = [b"checkpoints", pool.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_checkpoints: Box<Account<'info, StakeCheckpoints>>,
+
+    /// New position NFT mint (fresh keypair)
+    #[account(
+        init,
+        payer = user,
+        mint::decimals = 0,
+        mint::authority = pool,
+        mint::token_program = position_token_program,
+        extensions::metadata_pointer::authority = pool,
+        extensions::metadata_pointer::metadata_address = position_mint,
+    )]
+    pub position_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + UserStake::INIT_SPACE,
+        seeds = [b"position", position_mint.key().as_ref()],
+        bump
+    )]
+    pub position_stake: Box<Account<'info, UserStake>>,
+
+    #[account(
+        init,
         payer = user,
+        associated_token::mint = position_mint,
+        associated_token::authority = user,
+        associated_token::token_program = position_token_program,
+    )]
+    pub user_position_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    pub system_program: Program<'info, System>,
+    /// Token program for position NFTs (Token-2022)
+    pub position_token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct UnstakePosition<'info> {
+    pub holder: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, StakePool>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pool_checkpoints",
+            pool.key().as_ref(),
+            pool.checkpoint_ring.current.to_le_bytes().as_ref(),
+        ],
+        bump = pool_checkpoints.bump,
+    )]
+    pub pool_checkpoints: Box<Account<'info, CheckpointPage>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pool_checkpoints",
+            pool.key().as_ref(),
+            pool.checkpoint_ring.next.to_le_bytes().as_ref(),
+        ],
+        bump = next_pool_checkpoints.bump,
+    )]
+    pub next_pool_checkpoints: Box<Account<'info, CheckpointPage>>,
+
+    #[account(
+        mut,
+        seeds = [b"position", position_mint.key().as_ref()],
+        bump = position_stake.bump,
+        constraint = position_stake.pool == pool.key() @ StakingError::InvalidPosition,
+    )]
+    pub position_stake: Box<Account<'info, UserStake>>,
+
+    #[account(mut)]
+    pub position_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+    #[account(
+        constraint = holder_position_account.mint == position_mint.key() @ StakingError::InvalidPosition,
+        constraint = holder_position_account.owner == holder.key() @ StakingError::NotPositionHolder,
+        constraint = holder_position_account.amount == 1 @ StakingError::NotPositionHolder,
+    )]
+    pub holder_position_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    /// The staking token mint
+    #[account(
+        constraint = staking_mint.key() == pool.staking_mint @ StakingError::InvalidMint,
+    )]
+    pub staking_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+    #[account(
+        mut,
+        constraint = holder_token_account.owner == holder.key() @ StakingError::InvalidOwner,
+        constraint = holder_token_account.mint == pool.staking_mint @ StakingError::InvalidMint,
+    )]
+    pub holder_token_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    #[account(
+        mut,
+        seeds = [b"pool_vault", pool.key().as_ref()],
+        bump,
+    )]
+    pub pool_vault: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    /// Treasury receiving the fee, required while the pool charges one
+    #[account(
+        mut,
+        address = pool.fees.staking_treasury @ StakingError::InvalidFeeTreasury,
+    )]
+    pub staking_treasury: Option<Box<InterfaceAccount<'info, TokenAccountInterface>>>,
+
+    /// Token program for staking token (Token or Token-2022)
+    pub staking_token_program: Interface<'info, TokenInterface>,
+    /// Token program for position NFTs (Token-2022)
+    pub position_token_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimPositionRewards<'info> {
+    pub holder: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, StakePool>>,
+
+    #[account(
+        mut,
+        seeds = [b"position", holder_position_account.mint.as_ref()],
+        bump = position_stake.bump,
+        constraint = position_stake.pool == pool.key() @ StakingError::InvalidPosition,
+    )]
+    pub position_stake: Box<Account<'info, UserStake>>,
+
+    #[account(
+        constraint = holder_position_account.owner == holder.key() @ StakingError::NotPositionHolder,
+        constraint = holder_position_account.amount == 1 @ StakingError::NotPositionHolder,
+    )]
+    pub holder_position_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    #[account(
+        mut,
+        constraint = holder_reward_account.owner == holder.key() @ StakingError::InvalidOwner,
+        constraint = holder_reward_account.mint == pool.reward_mint @ StakingError::InvalidMint,
+    )]
+    pub holder_reward_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"reward_vault", pool.key().as_ref()],
+        bump,
+    )]
+    pub reward_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Treasury receiving the performance fee, required while the pool charges one
+    #[account(
+        mut,
+        address = pool.fees.reward_treasury @ StakingError::InvalidFeeTreasury,
+    )]
+    pub reward_treasury: Option<Box<Account<'info, TokenAccount>>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RedeemPosition<'info> {
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, StakePool>>,
+
+    #[account(
+        mut,
+        close = holder,
+        seeds = [b"position", position_mint.key().as_ref()],
+        bump = position_stake.bump,
+        constraint = position_stake.pool == pool.key() @ StakingError::InvalidPosition,
+    )]
+    pub position_stake: Box<Account<'info, UserStake>>,
+
+    #[account(mut)]
+    pub position_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+    #[account(
+        mut,
+        constraint = holder_position_account.mint == position_mint.key() @ StakingError::InvalidPosition,
+        constraint = holder_position_account.owner == holder.key() @ StakingError::NotPositionHolder,
+        constraint = holder_position_account.amount == 1 @ StakingError::NotPositionHolder,
+    )]
+    pub holder_position_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    #[account(
+        init_if_needed,
+        payer = holder,
+        space = 8 + UserStake::INIT_SPACE,
+        seeds = [b"user_stake", pool.key().as_ref(), holder.key().as_ref()],
+        bump
+    )]
+    pub user_stake: Box<Account<'info, UserStake>>,
+
+    #[account(
+        init_if_needed,
+        payer = holder,
+        space = 8 + StakeCheckpoints::INIT_SPACE,
+        seeds = [b"checkpoints", pool.key().as_ref(), holder.key().as_ref()],
+        bump
+    )]
+    pub user_checkpoints: Box<Account<'info, StakeCheckpoints>>,
+
+    pub system_program: Program<'info, System>,
+    /// Token program for position NFTs (Token-2022)
+    pub position_token_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeLiquidStaking<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == pool.authority @ StakingError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, StakePool>>,
+
+    /// The staking token mint
+    #[account(
+        constraint = staking_mint.key() == pool.staking_mint @ StakingError::InvalidMint,
+    )]
+    pub staking_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+    #[account(
+        init,
+        payer = authority,
+        mint::decimals = staking_mint.decimals,
+        mint::authority = pool,
+        mint::token_program = receipt_token_program,
+        seeds = [b"receipt_mint", pool.key().as_ref()],
+        bump
+    )]
+    pub receipt_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+    #[account(
+        init,
+        payer = authority,
         space = 8 + UserStake::INIT_SPACE,
+        seeds = [b"liquid_stake", pool.key().as_ref()],
+        bump
+    )]
+    pub liquid_stake: Box<Account<'info, UserStake>>,
+
+    pub system_program: Program<'info, System>,
+    /// Token program for the receipt token (Token or Token-2022)
+    pub receipt_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct LiquidStake<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, StakePool>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pool_checkpoints",
+            pool.key().as_ref(),
+            pool.checkpoint_ring.current.to_le_bytes().as_ref(),
+        ],
+        bump = pool_checkpoints.bump,
+    )]
+    pub pool_checkpoints: Box<Account<'info, CheckpointPage>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pool_checkpoints",
+            pool.key().as_ref(),
+            pool.checkpoint_ring.next.to_le_bytes().as_ref(),
+        ],
+        bump = next_pool_checkpoints.bump,
+    )]
+    pub next_pool_checkpoints: Box<Account<'info, CheckpointPage>>,
+
+    #[account(
+        mut,
+        seeds = [b"liquid_stake", pool.key().as_ref()],
+        bump = liquid_stake.bump,
+    )]
+    pub liquid_stake: Box<Account<'info, UserStake>>,
+
+    #[account(
+        mut,
+        address = pool.receipt_mint @ StakingError::InvalidMint,
+    )]
+    pub receipt_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+    #[account(
+        mut,
+        constraint = user_receipt_account.owner == user.key() @ StakingError::InvalidOwner,
+        constraint = user_receipt_account.mint == pool.receipt_mint @ StakingError::InvalidMint,
+    )]
+    pub user_receipt_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    /// The staking token mint
+    #[account(
+        constraint = staking_mint.key() == pool.staking_mint @ StakingError::InvalidMint,
+    )]
+    pub staking_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+    #[account(
+        mut,
+        constraint = user_token_account.owner == user.key() @ StakingError::InvalidOwner,
+        constraint = user_token_account.mint == pool.staking_mint @ StakingError::InvalidMint,
+    )]
+    pub user_token_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    #[account(
+        mut,
+        seeds = [b"pool_vault", pool.key().as_ref()],
+        bump,
+    )]
+    pub pool_vault: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    #[account(
+        mut,
+        seeds = [b"reward_vault", pool.key().as_ref()],
+        bump,
+    )]
+    pub reward_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Treasury receiving the fee, required while the pool charges one
+    #[account(
+        mut,
+        address = pool.fees.staking_treasury @ StakingError::InvalidFeeTreasury,
+    )]
+    pub staking_treasury: Option<Box<InterfaceAccount<'info, TokenAccountInterface>>>,
+
+    /// Token program for staking token (Token or Token-2022)
+    pub staking_token_program: Interface<'info, TokenInterface>,
+    /// Token program for the receipt token (Token or Token-2022)
+    pub receipt_token_program: Interface<'info, TokenInterface>,
+    /// Token program for reward token (regular Token)
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CompoundLiquid<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, StakePool>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pool_checkpoints",
+            pool.key().as_ref(),
+            pool.checkpoint_ring.current.to_le_bytes().as_ref(),
+        ],
+        bump = pool_checkpoints.bump,
+    )]
+    pub pool_checkpoints: Box<Account<'info, CheckpointPage>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pool_checkpoints",
+            pool.key().as_ref(),
+            pool.checkpoint_ring.next.to_le_bytes().as_ref(),
+        ],
+        bump = next_pool_checkpoints.bump,
+    )]
+    pub next_pool_checkpoints: Box<Account<'info, CheckpointPage>>,
+
+    #[account(
+        mut,
+        seeds = [b"liquid_stake", pool.key().as_ref()],
+        bump = liquid_stake.bump,
+    )]
+    pub liquid_stake: Box<Account<'info, UserStake>>,
+
+    #[account(
+        mut,
+        seeds = [b"pool_vault", pool.key().as_ref()],
+        bump,
+    )]
+    pub pool_vault: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    #[account(
+        mut,
+        seeds = [b"reward_vault", pool.key().as_ref()],
+        bump,
+    )]
+    pub reward_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Token program for reward token (regular Token)
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CloseUserStake<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, StakePool>,
+
+    #[account(
+        mut,
+        close = user,
         seeds = [b"user_stake", pool.key().as_ref(), user.key().as_ref()],
+        bump = user_stake.bump,
+        constraint = user_stake.owner == user.key() @ StakingError::InvalidOwner,
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    /// Wallet's balance history, if it has one
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"checkpoints", pool.key().as_ref(), user.key().as_ref()],
+        bump = user_checkpoints.bump,
+    )]
+    pub user_checkpoints: Option<Account<'info, StakeCheckpoints>>,
+}
+
+#[derive(Accounts)]
+pub struct RefreshTier<'info> {
+    #[account(
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, StakePool>,
+
+    /// Any position but the liquid one, which is not subject to the pool's tiers
+    #[account(
+        mut,
+        constraint = user_stake.pool == pool.key() @ StakingError::InvalidPosition,
+        constraint = user_stake.owner != pool.receipt_mint @ StakingError::InvalidPosition,
+    )]
+    pub user_stake: Account<'info, UserStake>,
+}
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct ViewUserStake<'info> {
+    #[account(
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, StakePool>,
+
+    #[account(
+        seeds = [b"user_stake", pool.key().as_ref(), user.as_ref()],
+        bump = user_stake.bump,
+    )]
+    pub user_stake: Account<'info, UserStake>,
+}
+
+#[derive(Accounts)]
+pub struct ViewPool<'info> {
+    #[account(
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, StakePool>,
+
+    #[account(
+        seeds = [b"pool_vault", pool.key().as_ref()],
+        bump,
+    )]
+    pub pool_vault: InterfaceAccount<'info, TokenAccountInterface>,
+
+    #[account(
+        seeds = [b"reward_vault", pool.key().as_ref()],
+        bump,
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct ViewStakeAt<'info> {
+    #[account(
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, StakePool>,
+
+    #[account(
+        seeds = [b"checkpoints", pool.key().as_ref(), user.as_ref()],
+        bump = user_checkpoints.bump,
+    )]
+    pub user_checkpoints: Account<'info, StakeCheckpoints>,
+}
+
+#[derive(Accounts)]
+#[instruction(timestamp: i64, page: u32)]
+pub struct ViewPoolStakeAt<'info> {
+    #[account(
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, StakePool>,
+
+    #[account(
+        seeds = [b"pool_checkpoints", pool.key().as_ref(), page.to_le_bytes().as_ref()],
+        bump = pool_checkpoints.bump,
+    )]
+    pub pool_checkpoints: Account<'info, CheckpointPage>,
+
+    /// The page written after `pool_checkpoints`, needed when `timestamp` is past its last
+    /// change and it isn't the page being written
+    pub following_checkpoints: Option<Account<'info, CheckpointPage>>,
+}
+
+#[derive(Accounts)]
+pub struct AddCheckpointPage<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == pool.authority @ StakingError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, StakePool>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + CheckpointPage::INIT_SPACE,
+        seeds = [
+            b"pool_checkpoints",
+            pool.key().as_ref(),
+            pool.checkpoint_ring.pages.to_le_bytes().as_ref(),
+        ],
+        bump
+    )]
+    pub page: Account<'info, CheckpointPage>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetVoterWeightConfig<'info> {
+    #[account(
+        constraint = authority.key() == pool.authority @ StakingError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    /// Authority of `realm`, consenting to the pool's stakes counting as its voting power
+    pub realm_authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, StakePool>,
+
+    /// CHECK: parsed as an spl-governance realm owned by `governance_program`
+    #[account(owner = governance_program.key() @ StakingError::InvalidRealm)]
+    pub realm: UncheckedAccount<'info>,
+
+    /// CHECK: only used as the expected owner of `realm`
+    #[account(executable)]
+    pub governance_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(voter: Pubkey)]
+pub struct UpdateVoterWeightRecord<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+        constraint = pool.voter_weight.is_enabled() @ StakingError::VoterWeightDisabled,
+    )]
+    pub pool: Account<'info, StakePool>,
+
+    #[account(
+        seeds = [b"user_stake", pool.key().as_ref(), voter.as_ref()],
+        bump = user_stake.bump,
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    #[account(
+        seeds = [b"checkpoints", pool.key().as_ref(), voter.as_ref()],
+        bump = user_checkpoints.bump,
+    )]
+    pub user_checkpoints: Account<'info, StakeCheckpoints>,
+
+    /// CHECK: the pool's realm, only used for its owner, the governance program
+    #[account(address = pool.voter_weight.realm @ StakingError::InvalidRealm)]
+    pub realm: UncheckedAccount<'info>,
+
+    /// CHECK: the proposal voted on, parsed as an spl-governance proposal; required for votes
+    #[account(owner = *realm.owner @ StakingError::InvalidProposal)]
+    pub proposal: Option<UncheckedAccount<'info>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + VoterWeightRecord::INIT_SPACE,
+        seeds = [b"voter_weight_record", pool.key().as_ref(), voter.as_ref()],
+        bump
+    )]
+    pub voter_weight_record: Account<'info, VoterWeightRecord>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AdminAction<'info> {
+    #[account(
+        constraint = authority.key() == pool.authority @ StakingError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, StakePool>,
+}
+
+#[derive(Accounts)]
+pub struct SetFees<'info> {
+    #[account(
+        constraint = authority.key() == pool.authority @ StakingError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, StakePool>,
+
+    #[account(
+        constraint = staking_treasury.mint == pool.staking_mint @ StakingError::InvalidMint,
+    )]
+    pub staking_treasury: InterfaceAccount<'info, TokenAccountInterface>,
+
+    #[account(
+        constraint = reward_treasury.mint == pool.reward_mint @ StakingError::InvalidMint,
+    )]
+    pub reward_treasury: Account<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct SetRateOracle<'info> {
+    #[account(
+        constraint = authority.key() == pool.authority @ StakingError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, StakePool>,
+
+    #[account(address = pool.staking_mint @ StakingError::InvalidMint)]
+    pub staking_mint: InterfaceAccount<'info, MintInterface>,
+
+    #[account(address = pool.reward_mint @ StakingError::InvalidMint)]
+    pub reward_mint: Account<'info, Mint>,
+
+    /// CHECK: parsed as a Pyth `PriceUpdateV2` account and pinned in the pool
+    pub price_account: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RefreshRewardRate<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+        constraint = pool.rate_oracle.is_enabled() @ StakingError::OracleDisabled,
+    )]
+    pub pool: Account<'info, StakePool>,
+
+    /// CHECK: the price account pinned by `set_rate_oracle`
+    #[account(address = pool.rate_oracle.price_account @ StakingError::InvalidOracleAccount)]
+    pub price_account: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FundRewards<'info> {
+    #[account(mut)]
+    pub funder: Signer<'info>,
+
+    #[account(
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, StakePool>,
+
+    #[account(
+        mut,
+        constraint = funder_token_account.owner == funder.key() @ StakingError::InvalidOwner,
+        constraint = funder_token_account.mint == pool.reward_mint @ StakingError::InvalidMint,
+    )]
+    pub funder_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"reward_vault", pool.key().as_ref()],
+        bump,
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(merkle_root: [u8; 32], total_amount: u64, num_leaves: u32)]
+pub struct PostDistributorEpoch<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == pool.authority @ StakingError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, StakePool>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + DistributorEpoch::INIT_SPACE + DistributorEpoch::bitmap_len(num_leaves),
+        seeds = [
+            b"distributor",
+            pool.key().as_ref(),
+            pool.distributor.epochs.to_le_bytes().as_ref(),
+        ],
+        bump
+    )]
+    pub distributor_epoch: Account<'info, DistributorEpoch>,
+
+    #[account(
+        seeds = [b"reward_vault", pool.key().as_ref()],
+        bump,
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimDistributor<'info> {
+    pub claimant: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, StakePool>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"distributor",
+            pool.key().as_ref(),
+            distributor_epoch.epoch.to_le_bytes().as_ref(),
+        ],
+        bump = distributor_epoch.bump,
+    )]
+    pub distributor_epoch: Account<'info, DistributorEpoch>,
+
+    #[account(
+        mut,
+        constraint = claimant_reward_account.owner == claimant.key() @ StakingError::InvalidOwner,
+        constraint = claimant_reward_account.mint == pool.reward_mint @ StakingError::InvalidMint,
+    )]
+    pub claimant_reward_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"reward_vault", pool.key().as_ref()],
+        bump,
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CloseDistributorEpoch<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == pool.authority @ StakingError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, StakePool>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [
+            b"distributor",
+            pool.key().as_ref(),
+            distributor_epoch.epoch.to_le_bytes().as_ref(),
+        ],
+        bump = distributor_epoch.bump,
+    )]
+    pub distributor_epoch: Account<'info, DistributorEpoch>,
+}
+
+#[derive(Accounts)]
+pub struct CreateCampaign<'info> {
+    #[account(mut)]
+    pub funder: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, StakePool>>,
+
+    #[account(
+        init,
+        payer = funder,
+        space = 8 + Campaign::INIT_SPACE,
+        seeds = [
+            b"campaign",
+            pool.key().as_ref(),
+            pool.campaigns.created.to_le_bytes().as_ref(),
+        ],
         bump
     )]
-    pub user_stake: Account<'info, UserStake>,
+    pub campaign: Box<Account<'info, Campaign>>,
+
+    pub reward_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+    #[account(
+        init,
+        payer = funder,
+        seeds = [b"campaign_vault", campaign.key().as_ref()],
+        bump,
+        token::mint = reward_mint,
+        token::authority = campaign,
+        token::token_program = reward_token_program,
+    )]
+    pub campaign_vault: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    #[account(
+        mut,
+        constraint = funder_token_account.owner == funder.key() @ StakingError::InvalidOwner,
+        constraint = funder_token_account.mint == reward_mint.key() @ StakingError::InvalidMint,
+    )]
+    pub funder_token_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    pub system_program: Program<'info, System>,
+    /// Token program owning `reward_mint` (Token or Token-2022)
+    pub reward_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimCampaignRewards<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, StakePool>>,
+
+    #[account(
+        mut,
+        seeds = [b"user_stake", pool.key().as_ref(), user.key().as_ref()],
+        bump = user_stake.bump,
+        constraint = user_stake.owner == user.key() @ StakingError::InvalidOwner,
+    )]
+    pub user_stake: Box<Account<'info, UserStake>>,
+
+    #[account(
+        mut,
+        has_one = pool,
+        has_one = reward_mint @ StakingError::InvalidMint,
+        seeds = [b"campaign", pool.key().as_ref(), campaign.id.to_le_bytes().as_ref()],
+        bump = campaign.bump,
+    )]
+    pub campaign: Box<Account<'info, Campaign>>,
+
+    #[account(
+        mut,
+        seeds = [b"campaign_vault", campaign.key().as_ref()],
+        bump,
+    )]
+    pub campaign_vault: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    pub reward_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+    #[account(
+        mut,
+        constraint = user_reward_account.owner == user.key() @ StakingError::InvalidOwner,
+        constraint = user_reward_account.mint == reward_mint.key() @ StakingError::InvalidMint,
+    )]
+    pub user_reward_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    /// Token program owning `reward_mint` (Token or Token-2022)
+    pub reward_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimPositionCampaignRewards<'info> {
+    pub holder: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, StakePool>>,
+
+    #[account(
+        mut,
+        seeds = [b"position", holder_position_account.mint.as_ref()],
+        bump = position_stake.bump,
+        constraint = position_stake.pool == pool.key() @ StakingError::InvalidPosition,
+    )]
+    pub position_stake: Box<Account<'info, UserStake>>,
+
+    #[account(
+        constraint = holder_position_account.owner == holder.key() @ StakingError::NotPositionHolder,
+        constraint = holder_position_account.amount == 1 @ StakingError::NotPositionHolder,
+    )]
+    pub holder_position_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    #[account(
+        mut,
+        has_one = pool,
+        has_one = reward_mint @ StakingError::InvalidMint,
+        seeds = [b"campaign", pool.key().as_ref(), campaign.id.to_le_bytes().as_ref()],
+        bump = campaign.bump,
+    )]
+    pub campaign: Box<Account<'info, Campaign>>,
+
+    #[account(
+        mut,
+        seeds = [b"campaign_vault", campaign.key().as_ref()],
+        bump,
+    )]
+    pub campaign_vault: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    pub reward_mint: Box<InterfaceAccount<'info, MintInterface>>,
 
-    /// The staking token mint
     #[account(
-        constraint = staking_mint.key() == pool.staking_mint @ StakingError::InvalidMint,
+        mut,
+        constraint = holder_reward_account.owner == holder.key() @ StakingError::InvalidOwner,
+        constraint = holder_reward_account.mint == reward_mint.key() @ StakingError::InvalidMint,
     )]
-    pub staking_mint: InterfaceAccount<'info, MintInterface>,
+    pub holder_reward_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    /// Token program owning `reward_mint` (Token or Token-2022)
+    pub reward_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CampaignFunderAction<'info> {
+    #[account(mut)]
+    pub funder: Signer<'info>,
 
     #[account(
         mut,
-        constraint = user_token_account.owner == user.key() @ StakingError::InvalidOwner,
-        constraint = user_token_account.mint == pool.staking_mint @ StakingError::InvalidMint,
+        seeds = [b"pool", pool.staking_mint.as_ref()],
+        bump = pool.bump,
     )]
-    pub user_token_account: InterfaceAccount<'info, TokenAccountInterface>,
+    pub pool: Box<Account<'info, StakePool>>,
+
+    #[account(
+        mut,
+        has_one = pool,
+        has_one = funder @ StakingError::Unauthorized,
+        has_one = reward_mint @ StakingError::InvalidMint,
+        seeds = [b"campaign", pool.key().as_ref(), campaign.id.to_le_bytes().as_ref()],
+        bump = campaign.bump,
+    )]
+    pub campaign: Box<Account<'info, Campaign>>,
+
+    #[account(
+        mut,
+        seeds = [b"campaign_vault", campaign.key().as_ref()],
+        bump,
+    )]
+    pub campaign_vault: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    pub reward_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+    #[account(
+        mut,
+        constraint = funder_token_account.mint == reward_mint.key() @ StakingError::InvalidMint,
+    )]
+    pub funder_token_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    /// Token program owning `reward_mint` (Token or Token-2022)
+    pub reward_token_program: Interface<'info, TokenInterface>,
+}
+
+// ============ STATE ACCOUNTS ============
+
+#[account]
+#[derive(InitSpace)]
+pub struct StakePool {
+    /// Pool authority (admin)
+    pub authority: Pubkey,
+    /// Token mint for staking
+    pub staking_mint: Pubkey,
+    /// Token mint for rewards
+    pub reward_mint: Pubkey,
+    /// Vault holding staked tokens
+    pub pool_vault: Pubkey,
+    /// Vault holding reward tokens
+    pub reward_vault: Pubkey,
+    /// Rewards per second per staked token
+    pub reward_rate: RewardRate,
+    /// Minimum stake lock period in seconds
+    pub lock_period: i64,
+    /// Total tokens staked in pool
+    pub total_staked: u64,
+    /// Last pool update timestamp
+    pub last_update_time: i64,
+    /// Bitset of paused operations (see `PAUSE_*` flags)
+    pub pause_flags: u8,
+    /// When accrual was last halted via `PAUSE_ACCRUAL`
+    pub accrual_halted_at: i64,
+    /// Total seconds accrual has been halted, excluding an ongoing halt
+    pub accrual_halted_seconds: i64,
+    /// Whether `emergency_withdraw` is available
+    pub emergency_mode: bool,
+    /// Whether `transfer_stake` is forbidden (soulbound positions)
+    pub transfers_disabled: bool,
+    /// Liquid staking receipt mint (default if liquid staking is disabled)
+    pub receipt_mint: Pubkey,
+    /// Share of each `crank_claim` payout kept by the cranker, in bps
+    pub crank_tip_bps: u16,
+    /// Oracle APR rate mode settings
+    pub rate_oracle: RateOracle,
+    /// spl-governance voter weight add-in settings
+    pub voter_weight: VoterWeightConfig,
+    /// Merkle distributor epochs and their reserved rewards
+    pub distributor: DistributorState,
+    /// Share of referees' rewards paid to their referrers on top, in bps
+    pub referral_bps: u16,
+    /// Protocol fees and their treasuries
+    pub fees: FeeConfig,
+    /// Reward boost for stakes escrowing an NFT of a collection
+    pub nft_boost: NftBoost,
+    /// Reward multipliers by stake size; the lowest threshold is the minimum stake
+    pub tiers: TierTable,
+    /// Sponsored campaigns and their reward indexes
+    pub campaigns: CampaignState,
+    /// Rewards accrued per staked unit, settled whenever `reward_rate` changes
+    pub reward_index: RewardIndex,
+    /// Pages of the `total_staked` history
+    pub checkpoint_ring: CheckpointRing,
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl StakePool {
+    /// Whether any of the given pause flags are set
+    pub fn is_paused(&self, flags: u8) -> bool {
+        self.pause_flags & flags != 0
+    }
+
+    /// Whether unstakes are paused; emergency mode lifts the pause for liquid and tokenized
+    /// stake, which `emergency_withdraw` can't reach
+    pub fn unstake_paused(&self) -> bool {
+        self.is_paused(PAUSE_UNSTAKE) && !self.emergency_mode
+    }
+
+    /// Reward vault tokens not reserved for distributor epochs
+    pub fn available_rewards(&self, reward_vault_balance: u64) -> u64 {
+        reward_vault_balance.saturating_sub(self.distributor.reserved)
+    }
+
+    /// Pool accrual clock: wall-clock time minus every second accrual was halted
+    pub fn accrual_time(&self, now: i64) -> i64 {
+        let end = if self.is_paused(PAUSE_ACCRUAL) {
+            self.accrual_halted_at
+        } else {
+            now
+        };
+        end.saturating_sub(self.accrual_halted_seconds)
+    }
+
+    /// Reward index at `accrual_time` at the current rate (see `RewardIndex`)
+    pub fn reward_index_at(&self, accrual_time: i64) -> u128 {
+        self.reward_index.at(self.reward_rate, accrual_time)
+    }
+
+    /// Settle the reward index at the current rate, then switch to `new_rate`
+    pub fn set_reward_rate(&mut self, new_rate: RewardRate, now: i64) {
+        let accrual_time = self.accrual_time(now);
+        self.reward_index = RewardIndex {
+            value: self.reward_index_at(accrual_time),
+            updated_at: accrual_time,
+        };
+        self.reward_rate = new_rate;
+        self.last_update_time = now;
+    }
+
+    /// Stake sharing in campaigns: everything but the liquid position
+    pub fn campaign_staked(&self) -> u64 {
+        self.total_staked.saturating_sub(self.campaigns.excluded_stake)
+    }
+
+    /// Emit every open campaign's rewards up to `now`, before `campaign_staked` changes
+    pub fn update_campaigns(&mut self, now: i64) -> Result<()> {
+        let total_staked = self.campaign_staked();
+        for slot in self.campaigns.slots.iter_mut().filter(|slot| slot.is_active()) {
+            slot.update(total_staked, now)
+                .ok_or(StakingError::MathOverflow)?;
+        }
+        Ok(())
+    }
+
+    /// Replace the pause flags, opening or closing an accrual halt as needed
+    pub fn set_pause_flags(&mut self, flags: u8, now: i64) -> Result<()> {
+        require!(flags & !PAUSE_ALL_FLAGS == 0, StakingError::InvalidPauseFlags);
+
+        let was_halted = self.is_paused(PAUSE_ACCRUAL);
+        let halt = flags & PAUSE_ACCRUAL != 0;
+        if halt && !was_halted {
+            self.accrual_halted_at = now;
+        } else if !halt && was_halted {
+            let halted_for = now
+                .checked_sub(self.accrual_halted_at)
+                .ok_or(StakingError::MathOverflow)?;
+            self.accrual_halted_seconds = self
+                .accrual_halted_seconds
+                .checked_add(halted_for)
+                .ok_or(StakingError::MathOverflow)?;
+        }
+
+        self.pause_flags = flags;
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct UserStake {
+    /// User wallet, or the position NFT mint for tokenized positions
+    pub owner: Pubkey,
+    /// Associated pool
+    pub pool: Pubkey,
+    /// Amount currently staked
+    pub staked_amount: u64,
+    /// Unclaimed rewards
+    pub pending_rewards: u64,
+    /// Last stake/claim timestamp
+    pub last_stake_time: i64,
+    /// Pool accrual clock at the last stake/claim (see `StakePool::accrual_time`)
+    pub last_accrual_time: i64,
+    /// When the current stake period started
+    pub stake_start_time: i64,
+    /// Total rewards claimed all-time
+    pub total_rewards_claimed: u64,
+    /// Wallet allowed to trigger claims besides the owner (default if none)
+    pub claim_delegate: Pubkey,
+    /// Reward account delegated claims must pay into (default: any owned by `owner`)
+    pub reward_destination: Pubkey,
+    /// Wallet that referred the owner (default if none)
+    pub referrer: Pubkey,
+    /// Referral rewards owed to `referrer`, paid by `claim_referral_rewards`
+    pub referral_pending: u64,
+    /// Boost collection NFT escrowed by `attach_boost_nft` (default if none)
+    pub boost_mint: Pubkey,
+    /// Level in the pool's `tiers`, reassigned on every stake, unstake and `refresh_tier`
+    pub tier: u8,
+    /// Shares of the pool's campaigns, by slot
+    pub campaign_rewards: [CampaignReward; MAX_CAMPAIGNS],
+    /// Pool reward index at `last_accrual_time` (see `RewardIndex`)
+    pub reward_index_paid: u128,
+    /// Reward multiplier of `tier` when it was assigned
+    pub tier_multiplier_bps: u16,
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl UserStake {
+    /// When the position can be unstaked (0 if nothing is staked)
+    pub fn unlock_time(&self, lock_period: i64) -> i64 {
+        if self.staked_amount == 0 {
+            0
+        } else {
+            self.stake_start_time.saturating_add(lock_period)
+        }
+    }
+
+    /// Reward weight multiplier: the pool's boost while an NFT is attached, else 1x
+    pub fn boost_bps(&self, nft_boost: &NftBoost) -> u16 {
+        if self.boost_mint == Pubkey::default() || !nft_boost.is_enabled() {
+            BPS_DENOMINATOR
+        } else {
+            nft_boost.multiplier_bps
+        }
+    }
+
+    /// Whether the position holds unclaimed rewards of a campaign still open in `campaigns`
+    pub fn has_campaign_rewards(&self, campaigns: &CampaignState) -> bool {
+        campaigns
+            .slots
+            .iter()
+            .zip(&self.campaign_rewards)
+            .any(|(slot, entry)| {
+                slot.is_active() && entry.campaign == slot.campaign && entry.pending > 0
+            })
+    }
+
+    /// Whether `claimer` may trigger claims for this position
+    pub fn can_claim(&self, claimer: &Pubkey) -> bool {
+        *claimer == self.owner
+            || (self.claim_delegate != Pubkey::default() && *claimer == self.claim_delegate)
+    }
+
+    /// Whether claimed rewards may be paid into the given reward account
+    pub fn accepts_reward_destination(&self, account: &Pubkey, account_owner: &Pubkey) -> bool {
+        if self.reward_destination == Pubkey::default() {
+            *account_owner == self.owner
+        } else {
+            *account == self.reward_destination
+        }
+    }
+}
+
+/// A referrer's referrals in a pool
+#[account]
+#[derive(InitSpace)]
+pub struct ReferrerStats {
+    /// Associated pool
+    pub pool: Pubkey,
+    /// Referring wallet
+    pub referrer: Pubkey,
+    /// Wallets that named this referrer
+    pub referral_count: u32,
+    /// Referral rewards paid out all-time
+    pub total_rewards_paid: u64,
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+/// Staked balance history of a wallet's position
+#[account]
+#[derive(InitSpace)]
+pub struct StakeCheckpoints {
+    /// Associated pool
+    pub pool: Pubkey,
+    /// Position owner
+    pub owner: Pubkey,
+    /// Whether the position held stake before the history began, making balances before
+    /// the first checkpoint unknown
+    pub truncated: bool,
+    /// Balance changes, oldest first
+    #[max_len(CHECKPOINT_CAPACITY)]
+    pub checkpoints: Vec<Checkpoint>,
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl StakeCheckpoints {
+    /// Record `amount` as the balance from now on
+    ///
+    /// Changes within one slot share a checkpoint. Once full, the two oldest checkpoints
+    /// are merged into the lower of their balances, so old snapshots read a lower bound
+    /// instead of becoming unavailable and never over-count voting power.
+    pub fn record(&mut self, amount: u64, clock: &Clock) {
+        if let Some(last) = self.checkpoints.last_mut() {
+            if last.amount == amount {
+                return;
+            }
+            if last.slot == clock.slot {
+                last.amount = amount;
+                return;
+            }
+        }
+
+        if self.checkpoints.len() >= CHECKPOINT_CAPACITY {
+            let merged = self.checkpoints.remove(1);
+            self.checkpoints[0].amount = self.checkpoints[0].amount.min(merged.amount);
+        }
+        self.checkpoints.push(Checkpoint {
+            slot: clock.slot,
+            timestamp: clock.unix_timestamp,
+            amount,
+        });
+    }
+
+    /// Balance at the start of `timestamp`, i.e. after every change made before it
+    pub fn amount_at(&self, timestamp: i64) -> Result<u64> {
+        let index = self.checkpoints.partition_point(|c| c.timestamp < timestamp);
+        match index.checked_sub(1) {
+            Some(previous) => Ok(self.checkpoints[previous].amount),
+            None if self.truncated => err!(StakingError::CheckpointUnavailable),
+            None => Ok(0),
+        }
+    }
+}
+
+/// One page of the pool total's history, written in turn with the others of its `CheckpointRing`
+#[account]
+#[derive(InitSpace)]
+pub struct CheckpointPage {
+    /// Associated pool
+    pub pool: Pubkey,
+    /// Index of the page in the ring
+    pub page: u32,
+    /// Which part of the history the page holds, counting pages written since the pool began
+    pub sequence: u64,
+    /// Changes of the total, oldest first
+    #[max_len(CHECKPOINT_PAGE_CAPACITY)]
+    pub checkpoints: Vec<Checkpoint>,
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl CheckpointPage {
+    /// Record `amount` as the total from now on, or return false if the page is full
+    ///
+    /// Changes within one slot share a checkpoint, as in `StakeCheckpoints::record`.
+    pub fn record(&mut self, amount: u64, clock: &Clock) -> bool {
+        if let Some(last) = self.checkpoints.last_mut() {
+            if last.amount == amount {
+                return true;
+            }
+            if last.slot == clock.slot {
+                last.amount = amount;
+                return true;
+            }
+        }
+
+        if self.checkpoints.len() >= CHECKPOINT_PAGE_CAPACITY {
+            return false;
+        }
+        self.checkpoints.push(Checkpoint {
+            slot: clock.slot,
+            timestamp: clock.unix_timestamp,
+            amount,
+        });
+        true
+    }
+
+    /// Total at the start of `timestamp`, if this page holds the last change before it
+    ///
+    /// `following` is the page written after this one, needed unless this page is the one
+    /// being written (`sequence == current_sequence`).
+    pub fn amount_at(
+        &self,
+        timestamp: i64,
+        following: Option<&CheckpointPage>,
+        current_sequence: u64,
+    ) -> Result<u64> {
+        let index = self.checkpoints.partition_point(|c| c.timestamp < timestamp);
+        let Some(previous) = index.checked_sub(1) else {
+            // Only the very first page can answer for times before its first change
+            require!(
+                self.page == 0 && self.sequence == 0,
+                StakingError::CheckpointUnavailable
+            );
+            return Ok(0);
+        };
+
+        if index == self.checkpoints.len() && self.sequence != current_sequence {
+            let following_start = following
+                .filter(|page| page.pool == self.pool && page.sequence == self.sequence + 1)
+                .and_then(|page| page.checkpoints.first())
+                .map(|checkpoint| checkpoint.timestamp);
+            require!(
+                following_start.is_some_and(|start| start >= timestamp),
+                StakingError::CheckpointUnavailable
+            );
+        }
+        Ok(self.checkpoints[previous].amount)
+    }
+}
+
+/// spl-governance add-in `VoterWeightRecord`, read by the governance program when voting
+///
+/// The Anchor discriminator of this name is the one spl-governance expects.
+#[account]
+#[derive(InitSpace)]
+pub struct VoterWeightRecord {
+    /// Realm the weight is valid for
+    pub realm: Pubkey,
+    /// Governing token mint the weight stands in for
+    pub governing_token_mint: Pubkey,
+    /// Wallet whose voting power this is
+    pub governing_token_owner: Pubkey,
+    /// Voting power
+    pub voter_weight: u64,
+    /// Last slot the weight is valid in
+    pub voter_weight_expiry: Option<u64>,
+    /// Governance action the weight is restricted to (any if none)
+    pub weight_action: Option<VoterWeightAction>,
+    /// Target of `weight_action` (any if none)
+    pub weight_action_target: Option<Pubkey>,
+    /// Reserved by the add-in interface
+    pub reserved: [u8; 8],
+}
+
+/// Governance actions a `VoterWeightRecord` can be restricted to
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VoterWeightAction {
+    CastVote,
+    CommentProposal,
+    CreateGovernance,
+    CreateProposal,
+    SignOffProposal,
+}
+
+/// A staked balance and when it took effect
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub slot: u64,
+    pub timestamp: i64,
+    pub amount: u64,
+}
+
+/// One epoch of off-chain computed rewards, claimable with Merkle proofs
+#[account]
+#[derive(InitSpace)]
+pub struct DistributorEpoch {
+    /// Associated pool
+    pub pool: Pubkey,
+    /// Epoch number within the pool
+    pub epoch: u64,
+    /// Root of the tree of `distributor_leaf`s
+    pub merkle_root: [u8; 32],
+    /// Sum of every leaf's amount, reserved in the reward vault
+    pub total_amount: u64,
+    /// Amount claimed so far
+    pub claimed_amount: u64,
+    /// Leaves in the tree
+    pub num_leaves: u32,
+    /// Leaves claimed so far
+    pub claimed_count: u32,
+    /// PDA bump seed
+    pub bump: u8,
+    /// One bit per leaf index, set once claimed (sized by `bitmap_len` at creation)
+    #[max_len(0)]
+    pub claimed: Vec<u8>,
+}
+
+impl DistributorEpoch {
+    /// Bytes of claimed bitmap needed for `num_leaves`
+    pub fn bitmap_len(num_leaves: u32) -> usize {
+        (num_leaves as usize).div_ceil(8)
+    }
+
+    /// Whether leaf `index` has been claimed
+    pub fn is_claimed(&self, index: u32) -> bool {
+        self.claimed
+            .get(index as usize / 8)
+            .is_some_and(|byte| byte & (1 << (index % 8)) != 0)
+    }
+
+    /// Mark leaf `index` claimed, failing if it's out of range or already claimed
+    pub fn set_claimed(&mut self, index: u32) -> Result<()> {
+        require!(index < self.num_leaves, StakingError::InvalidMerkleProof);
+        require!(!self.is_claimed(index), StakingError::AlreadyClaimed);
+        self.claimed[index as usize / 8] |= 1 << (index % 8);
+        Ok(())
+    }
+}
+
+/// A sponsored campaign; its emission schedule lives in the pool's slot `slot`
+#[account]
+#[derive(InitSpace)]
+pub struct Campaign {
+    pub pool: Pubkey,
+    /// Wallet that funded the campaign and gets its leftovers back
+    pub funder: Pubkey,
+    /// Mint the campaign pays rewards in
+    pub reward_mint: Pubkey,
+    /// Sequence number within the pool (PDA seed)
+    pub id: u64,
+    /// Index of the campaign's `CampaignSlot` in the pool
+    pub slot: u8,
+    /// Rewards claimed by stakers so far
+    pub claimed: u64,
+    /// Unallocated rewards returned to the funder so far
+    pub reclaimed: u64,
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+// ============ VIEW TYPES ============
+
+/// Returned by `get_pool_stats`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct PoolStats {
+    pub total_staked: u64,
+    pub reward_rate: RewardRate,
+    pub lock_period: i64,
+    pub min_stake_amount: u64,
+    pub pause_flags: u8,
+    pub emergency_mode: bool,
+    pub pool_vault_balance: u64,
+    pub reward_vault_balance: u64,
+    /// Pool accrual clock (see `StakePool::accrual_time`)
+    pub accrual_time: i64,
+    pub timestamp: i64,
+}
+
+/// Returned by `get_user_position`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct UserPosition {
+    pub owner: Pubkey,
+    pub staked_amount: u64,
+    /// Claimable rewards including everything accrued up to `timestamp`
+    pub pending_rewards: u64,
+    pub stake_start_time: i64,
+    /// When the position can be unstaked (0 if nothing is staked)
+    pub unlock_time: i64,
+    pub total_rewards_claimed: u64,
+    /// Level in the pool's tiers (0: below every tier)
+    pub tier: u8,
+    pub claim_delegate: Pubkey,
+    pub reward_destination: Pubkey,
+    pub timestamp: i64,
+}
+
+// ============ EVENTS ============
+
+// Fields added to an event once it is emitted go after `timestamp`, so indexers can
+// still decode the shorter payloads logged before.
+
+#[event]
+pub struct PoolInitialized {
+    pub pool: Pubkey,
+    pub staking_mint: Pubkey,
+    pub reward_mint: Pubkey,
+    pub reward_rate: RewardRate,
+    pub lock_period: i64,
+    pub min_stake_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StakeEvent {
+    pub user: Pubkey,
+    pub pool: Pubkey,
+    /// Tokens paid in, deposit fee included
+    pub amount: u64,
+    pub total_staked: u64,
+    pub timestamp: i64,
+    /// Deposit fee paid to the staking treasury
+    pub fee: u64,
+}
+
+#[event]
+pub struct StakeForEvent {
+    pub funder: Pubkey,
+    pub beneficiary: Pubkey,
+    pub pool: Pubkey,
+    /// Tokens paid in, deposit fee included
+    pub amount: u64,
+    pub total_staked: u64,
+    pub timestamp: i64,
+    /// Deposit fee paid to the staking treasury
+    pub fee: u64,
+}
 
-    #[account(
-        mut,
-        seeds = [b"pool_vault", pool.key().as_ref()],
-        bump,
-    )]
-    pub pool_vault: InterfaceAccount<'info, TokenAccountInterface>,
+#[event]
+pub struct UnstakeEvent {
+    pub user: Pubkey,
+    pub pool: Pubkey,
+    /// Tokens unstaked, withdrawal fee included
+    pub amount: u64,
+    pub remaining_stake: u64,
+    pub timestamp: i64,
+    /// Withdrawal fee paid to the staking treasury
+    pub fee: u64,
+}
 
-    pub system_program: Program<'info, System>,
-    /// Token program for staking token (Token or Token-2022)
-    pub staking_token_program: Interface<'info, TokenInterface>,
+#[event]
+pub struct ClaimEvent {
+    pub user: Pubkey,
+    pub pool: Pubkey,
+    /// Rewards claimed, performance fee included
+    pub amount: u64,
+    pub timestamp: i64,
+    /// Performance fee paid to the reward treasury
+    pub fee: u64,
 }
 
-#[derive(Accounts)]
-pub struct Unstake<'info> {
-    #[account(mut)]
-    pub user: Signer<'info>,
+#[event]
+pub struct CrankClaimed {
+    pub cranker: Pubkey,
+    pub pool: Pubkey,
+    pub claimed_count: u32,
+    pub total_claimed: u64,
+    pub total_tip: u64,
+    pub timestamp: i64,
+    /// Performance fees paid to the reward treasury
+    pub total_fee: u64,
+}
 
-    #[account(
-        mut,
-        seeds = [b"pool", pool.staking_mint.as_ref()],
-        bump = pool.bump,
-    )]
-    pub pool: Account<'info, StakePool>,
+#[event]
+pub struct CrankTipUpdated {
+    pub pool: Pubkey,
+    pub old_tip_bps: u16,
+    pub new_tip_bps: u16,
+    pub timestamp: i64,
+}
 
-    #[account(
-        mut,
-        seeds = [b"user_stake", pool.key().as_ref(), user.key().as_ref()],
-        bump = user_stake.bump,
-        constraint = user_stake.owner == user.key() @ StakingError::InvalidOwner,
-    )]
-    pub user_stake: Account<'info, UserStake>,
+#[event]
+pub struct CheckpointPageAdded {
+    pub pool: Pubkey,
+    pub pages: u32,
+    pub timestamp: i64,
+}
 
-    /// The staking token mint
-    #[account(
-        constraint = staking_mint.key() == pool.staking_mint @ StakingError::InvalidMint,
-    )]
-    pub staking_mint: InterfaceAccount<'info, MintInterface>,
+#[event]
+pub struct ClaimDelegateSet {
+    pub user: Pubkey,
+    pub pool: Pubkey,
+    pub delegate: Pubkey,
+    pub timestamp: i64,
+}
 
-    #[account(
-        mut,
-        constraint = user_token_account.owner == user.key() @ StakingError::InvalidOwner,
-        constraint = user_token_account.mint == pool.staking_mint @ StakingError::InvalidMint,
-    )]
-    pub user_token_account: InterfaceAccount<'info, TokenAccountInterface>,
+#[event]
+pub struct RewardDestinationSet {
+    pub user: Pubkey,
+    pub pool: Pubkey,
+    pub destination: Pubkey,
+    pub timestamp: i64,
+}
 
-    #[account(
-        mut,
-        seeds = [b"pool_vault", pool.key().as_ref()],
-        bump,
-    )]
-    pub pool_vault: InterfaceAccount<'info, TokenAccountInterface>,
+#[event]
+pub struct StakeTransferred {
+    pub pool: Pubkey,
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub amount: u64,
+    pub pending_rewards: u64,
+    pub timestamp: i64,
+}
 
-    /// Token program for staking token (Token or Token-2022)
-    pub staking_token_program: Interface<'info, TokenInterface>,
+#[event]
+pub struct StakeTokenized {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub position_mint: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
 }
 
-#[derive(Accounts)]
-pub struct ClaimRewards<'info> {
-    #[account(mut)]
-    pub user: Signer<'info>,
+#[event]
+pub struct PositionRedeemed {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub position_mint: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
 
-    #[account(
-        seeds = [b"pool", pool.staking_mint.as_ref()],
-        bump = pool.bump,
-    )]
-    pub pool: Account<'info, StakePool>,
+#[event]
+pub struct LiquidStakingInitialized {
+    pub pool: Pubkey,
+    pub receipt_mint: Pubkey,
+    pub timestamp: i64,
+}
 
-    #[account(
-        mut,
-        seeds = [b"user_stake", pool.key().as_ref(), user.key().as_ref()],
-        bump = user_stake.bump,
-        constraint = user_stake.owner == user.key() @ StakingError::InvalidOwner,
-    )]
-    pub user_stake: Account<'info, UserStake>,
+#[event]
+pub struct LiquidStakeEvent {
+    pub user: Pubkey,
+    pub pool: Pubkey,
+    /// Tokens paid in, deposit fee included
+    pub amount: u64,
+    pub receipts: u64,
+    pub total_underlying: u64,
+    pub receipt_supply: u64,
+    pub timestamp: i64,
+    /// Deposit fee paid to the staking treasury
+    pub fee: u64,
+}
 
-    #[account(
-        mut,
-        constraint = user_reward_account.owner == user.key() @ StakingError::InvalidOwner,
-        constraint = user_reward_account.mint == pool.reward_mint @ StakingError::InvalidMint,
-    )]
-    pub user_reward_account: Account<'info, TokenAccount>,
+#[event]
+pub struct LiquidUnstakeEvent {
+    pub user: Pubkey,
+    pub pool: Pubkey,
+    /// Tokens withdrawn, withdrawal fee included
+    pub amount: u64,
+    pub receipts: u64,
+    pub total_underlying: u64,
+    pub receipt_supply: u64,
+    pub timestamp: i64,
+    /// Withdrawal fee paid to the staking treasury
+    pub fee: u64,
+}
 
-    #[account(
-        mut,
-        seeds = [b"reward_vault", pool.key().as_ref()],
-        bump,
-    )]
-    pub reward_vault: Account<'info, TokenAccount>,
+#[event]
+pub struct LiquidCompounded {
+    pub pool: Pubkey,
+    pub amount: u64,
+    pub total_underlying: u64,
+    pub timestamp: i64,
+}
 
-    pub token_program: Program<'info, Token>,
+#[event]
+pub struct UserStakeClosed {
+    pub user: Pubkey,
+    pub pool: Pubkey,
+    pub total_rewards_claimed: u64,
+    pub timestamp: i64,
 }
 
-#[derive(Accounts)]
-pub struct AdminAction<'info> {
-    #[account(
-        constraint = authority.key() == pool.authority @ StakingError::Unauthorized
-    )]
-    pub authority: Signer<'info>,
+#[event]
+pub struct RewardRateUpdated {
+    pub pool: Pubkey,
+    pub old_rate: RewardRate,
+    pub new_rate: RewardRate,
+    pub timestamp: i64,
+}
 
-    #[account(
-        mut,
-        seeds = [b"pool", pool.staking_mint.as_ref()],
-        bump = pool.bump,
-    )]
-    pub pool: Account<'info, StakePool>,
+#[event]
+pub struct RateOracleSet {
+    pub pool: Pubkey,
+    pub price_account: Pubkey,
+    pub target_apr_bps: u32,
+    pub max_staleness: i64,
+    pub max_confidence_bps: u16,
+    pub max_rate_change_bps: u16,
+    pub timestamp: i64,
 }
 
-#[derive(Accounts)]
-pub struct FundRewards<'info> {
-    #[account(mut)]
-    pub funder: Signer<'info>,
+#[event]
+pub struct VoterWeightConfigSet {
+    pub pool: Pubkey,
+    pub realm: Pubkey,
+    pub governing_token_mint: Pubkey,
+    pub max_lock_boost_bps: u16,
+    pub lock_boost_saturation: i64,
+    pub timestamp: i64,
+}
 
-    #[account(
-        seeds = [b"pool", pool.staking_mint.as_ref()],
-        bump = pool.bump,
-    )]
-    pub pool: Account<'info, StakePool>,
+#[event]
+pub struct PoolPausedEvent {
+    pub pool: Pubkey,
+    pub pause_flags: u8,
+    pub timestamp: i64,
+}
 
-    #[account(
-        mut,
-        constraint = funder_token_account.owner == funder.key() @ StakingError::InvalidOwner,
-        constraint = funder_token_account.mint == pool.reward_mint @ StakingError::InvalidMint,
-    )]
-    pub funder_token_account: Account<'info, TokenAccount>,
+#[event]
+pub struct EmergencyModeSet {
+    pub pool: Pubkey,
+    pub enabled: bool,
+    pub timestamp: i64,
+}
 
-    #[account(
-        mut,
-        seeds = [b"reward_vault", pool.key().as_ref()],
-        bump,
-    )]
-    pub reward_vault: Account<'info, TokenAccount>,
+#[event]
+pub struct EmergencyWithdrawEvent {
+    pub user: Pubkey,
+    pub pool: Pubkey,
+    pub amount: u64,
+    pub forfeited_rewards: u64,
+    pub timestamp: i64,
+}
 
-    pub token_program: Program<'info, Token>,
+#[event]
+pub struct TransfersDisabledSet {
+    pub pool: Pubkey,
+    pub disabled: bool,
+    pub timestamp: i64,
 }
 
-// ============ STATE ACCOUNTS ============
+#[event]
+pub struct RewardsFunded {
+    pub pool: Pubkey,
+    pub funder: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
 
-#[account]
-#[derive(InitSpace)]
-pub struct StakePool {
-    /// Pool authority (admin)
-    pub authority: Pubkey,
-    /// Token mint for staking
-    pub staking_mint: Pubkey,
-    /// Token mint for rewards
-    pub reward_mint: Pubkey,
-    /// Vault holding staked tokens
-    pub pool_vault: Pubkey,
-    /// Vault holding reward tokens
-    pub reward_vault: Pubkey,
-    /// Rewards per second per staked token (scaled by 1e9)
-    pub reward_rate: u64,
-    /// Minimum stake lock period in seconds
-    pub lock_period: i64,
-    /// Minimum tokens required to stake
-    pub min_stake_amount: u64,
-    /// Total tokens staked in pool
-    pub total_staked: u64,
-    /// Last pool update timestamp
-    pub last_update_time: i64,
-    /// Whether pool is paused
-    pub paused: bool,
-    /// PDA bump seed
-    pub bump: u8,
+#[event]
+pub struct ReferralBpsUpdated {
+    pub pool: Pubkey,
+    pub old_bps: u16,
+    pub new_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeesSet {
+    pub pool: Pubkey,
+    pub deposit_bps: u16,
+    pub withdrawal_bps: u16,
+    pub performance_bps: u16,
+    pub staking_treasury: Pubkey,
+    pub reward_treasury: Pubkey,
+    pub timestamp: i64,
 }
 
-#[account]
-#[derive(InitSpace)]
-pub struct UserStake {
-    /// User wallet
-    pub owner: Pubkey,
-    /// Associated pool
+#[event]
+pub struct NftBoostSet {
     pub pool: Pubkey,
-    /// Amount currently staked
-    pub staked_amount: u64,
-    /// Unclaimed rewards
-    pub pending_rewards: u64,
-    /// Last stake/claim timestamp
-    pub last_stake_time: i64,
-    /// When the current stake period started
-    pub stake_start_time: i64,
-    /// Total rewards claimed all-time
-    pub total_rewards_claimed: u64,
-    /// PDA bump seed
-    pub bump: u8,
+    pub collection: Pubkey,
+    pub multiplier_bps: u16,
+    pub timestamp: i64,
 }
 
-// ============ EVENTS ============
-
 #[event]
-pub struct PoolInitialized {
+pub struct TiersSet {
     pub pool: Pubkey,
-    pub staking_mint: Pubkey,
-    pub reward_mint: Pubkey,
-    pub reward_rate: u64,
-    pub lock_period: i64,
-    pub min_stake_amount: u64,
+    pub tiers: TierTable,
+    pub timestamp: i64,
 }
 
 #[event]
-pub struct StakeEvent {
+pub struct TierChanged {
+    pub pool: Pubkey,
+    /// Position owner
     pub user: Pubkey,
+    pub old_tier: u8,
+    pub new_tier: u8,
+    pub staked_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BoostNftAttached {
     pub pool: Pubkey,
-    pub amount: u64,
-    pub total_staked: u64,
+    pub user: Pubkey,
+    pub nft_mint: Pubkey,
+    pub multiplier_bps: u16,
     pub timestamp: i64,
 }
 
 #[event]
-pub struct UnstakeEvent {
+pub struct BoostNftDetached {
+    pub pool: Pubkey,
     pub user: Pubkey,
+    pub nft_mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReferralRegistered {
+    pub pool: Pubkey,
+    pub referrer: Pubkey,
+    pub referee: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReferralRewardsPaid {
     pub pool: Pubkey,
+    pub referrer: Pubkey,
+    pub referee_count: u32,
     pub amount: u64,
-    pub remaining_stake: u64,
     pub timestamp: i64,
 }
 
 #[event]
-pub struct ClaimEvent {
-    pub user: Pubkey,
+pub struct DistributorEpochPosted {
+    pub pool: Pubkey,
+    pub epoch: u64,
+    pub merkle_root: [u8; 32],
+    pub total_amount: u64,
+    pub num_leaves: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DistributorClaimed {
     pub pool: Pubkey,
+    pub epoch: u64,
+    pub index: u32,
+    pub claimant: Pubkey,
     pub amount: u64,
     pub timestamp: i64,
 }
 
 #[event]
-pub struct RewardRateUpdated {
+pub struct DistributorEpochClosed {
     pub pool: Pubkey,
-    pub old_rate: u64,
-    pub new_rate: u64,
+    pub epoch: u64,
+    /// Reserved rewards released back to stakers
+    pub unclaimed: u64,
+    pub timestamp: i64,
 }
 
 #[event]
-pub struct PoolPausedEvent {
+pub struct CampaignCreated {
     pub pool: Pubkey,
-    pub paused: bool,
+    pub campaign: Pubkey,
+    pub funder: Pubkey,
+    pub reward_mint: Pubkey,
+    /// Rewards received by the campaign vault
+    pub amount: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub timestamp: i64,
 }
 
 #[event]
-pub struct RewardsFunded {
+pub struct CampaignRewardsClaimed {
     pub pool: Pubkey,
+    pub campaign: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CampaignLeftoversReclaimed {
+    pub pool: Pubkey,
+    pub campaign: Pubkey,
     pub funder: Pubkey,
     pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CampaignClosed {
+    pub pool: Pubkey,
+    pub campaign: Pubkey,
+    pub funder: Pubkey,
+    /// Vault balance returned to the funder, unclaimed rewards included
+    pub returned: u64,
+    pub timestamp: i64,
 }
 
 // ============ ERRORS ============
@@ -713,5 +6895,949 @@ pub enum StakingError {
     InvalidMint,
     #[msg("Stake amount below minimum required")]
     BelowMinimumStake,
+    #[msg("Unknown pause flag bits")]
+    InvalidPauseFlags,
+    #[msg("Pool is not in emergency mode")]
+    NotInEmergencyMode,
+    #[msg("Stake account still holds tokens or unclaimed rewards")]
+    StakeNotEmpty,
+    #[msg("Stake transfers are disabled for this pool")]
+    TransfersDisabled,
+    #[msg("Account does not belong to this stake position")]
+    InvalidPosition,
+    #[msg("Signer does not hold the position NFT")]
+    NotPositionHolder,
+    #[msg("Liquid staking is already enabled for this pool")]
+    LiquidStakingAlreadyEnabled,
+    #[msg("Liquid staking requires rewards paid in the staking token")]
+    LiquidStakingRequiresSameMint,
+    #[msg("Reward account is not this position's reward destination")]
+    InvalidRewardDestination,
+    #[msg("Crank accounts must be writable (user_stake, reward_destination) pairs of this pool")]
+    InvalidCrankAccounts,
+    #[msg("Crank tip exceeds the maximum")]
+    CrankTipTooHigh,
+    #[msg("Reward rate period and stake amount must be non-zero and the rate must fit")]
+    InvalidRewardRate,
+    #[msg("Invalid oracle rate mode settings")]
+    InvalidOracleConfig,
+    #[msg("Pool is not in oracle rate mode")]
+    OracleDisabled,
+    #[msg("Not the pool's price account or not a price update")]
+    InvalidOracleAccount,
+    #[msg("Oracle price is too old")]
+    OraclePriceStale,
+    #[msg("Oracle price is unverified, non-positive or its confidence interval is too wide")]
+    OraclePriceUncertain,
+    #[msg("Stake history before this time is no longer kept")]
+    CheckpointUnavailable,
+    #[msg("Invalid voter weight settings")]
+    InvalidVoterWeightConfig,
+    #[msg("Pool stakes do not count as voting power")]
+    VoterWeightDisabled,
+    #[msg("Not an spl-governance realm")]
+    InvalidRealm,
+    #[msg("Votes need the spl-governance proposal they are cast on")]
+    InvalidProposal,
+    #[msg("Distributor epochs need a non-zero total and 1 to MAX_DISTRIBUTOR_LEAVES leaves")]
+    InvalidDistributorEpoch,
+    #[msg("Merkle proof does not match the distributor epoch")]
+    InvalidMerkleProof,
+    #[msg("Distributor leaf already claimed")]
+    AlreadyClaimed,
+    #[msg("Claims exceed the distributor epoch's total")]
+    DistributionExceeded,
+    #[msg("Referral share exceeds the maximum")]
+    ReferralShareTooHigh,
+    #[msg("Wallets can't refer themselves")]
+    InvalidReferrer,
+    #[msg("Referrers can only be named before the first stake")]
+    ReferrerLocked,
+    #[msg("Referrers must stake at least the pool's minimum stake")]
+    ReferrerNotStaked,
+    #[msg("Referral accounts must be writable stakes of this pool referred by the referrer")]
+    InvalidReferralAccounts,
+    #[msg("Fee exceeds the maximum")]
+    FeeTooHigh,
+    #[msg("The pool charges this fee, so its treasury account is required")]
+    MissingFeeTreasury,
+    #[msg("Fee treasury does not match the pool's")]
+    InvalidFeeTreasury,
+    #[msg("NFT boost multiplier must be between 1x and the maximum")]
+    InvalidBoostMultiplier,
+    #[msg("Pool has no NFT boost")]
+    NftBoostDisabled,
+    #[msg("NFT is not a verified member of the pool's boost collection")]
+    InvalidBoostNft,
+    #[msg("Position has a boost NFT attached")]
+    BoostNftAttached,
+    #[msg("Position has no boost NFT attached")]
+    NoBoostNft,
+    #[msg("Tiers must be 1 to MAX_TIERS thresholds in ascending order")]
+    InvalidTiers,
+    #[msg("Tier multiplier must be between 1x and the maximum")]
+    InvalidTierMultiplier,
+    #[msg("Campaigns must start in the future and last at most MAX_CAMPAIGN_DURATION")]
+    InvalidCampaignWindow,
+    #[msg("Pool already runs the maximum number of campaigns")]
+    CampaignSlotsFull,
+    #[msg("Campaign has not ended yet")]
+    CampaignNotEnded,
+    #[msg("Campaign rewards can still be claimed")]
+    CampaignClaimPeriodActive,
+    #[msg("Campaign has no unallocated rewards left")]
+    NoCampaignLeftovers,
+    #[msg("Campaigns must pay at least one reward token per day of their window")]
+    CampaignTooSmall,
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Floating-point estimate of the exact reward, for range checks
+    fn approx_rewards(staked: u64, index_delta: u128) -> f64 {
+        staked as f64 * index_delta as f64 / RATE_SCALE as f64
+    }
+
+    /// Rewards over `[start, end)` at a constant `rate`, through the reward index
+    fn window_rewards(staked: u64, start: i64, end: i64, rate: RewardRate) -> Result<u64> {
+        let index = RewardIndex { value: 0, updated_at: start };
+        index_rewards(staked, index.at(rate, end))
+    }
+
+    /// A fresh 1x position holding `staked_amount`, boosted by `boost_mint` if it is set
+    fn user_stake(staked_amount: u64, boost_mint: Pubkey) -> UserStake {
+        UserStake {
+            owner: Pubkey::new_unique(),
+            pool: Pubkey::new_unique(),
+            staked_amount,
+            pending_rewards: 0,
+            last_stake_time: 0,
+            last_accrual_time: 0,
+            stake_start_time: 0,
+            total_rewards_claimed: 0,
+            claim_delegate: Pubkey::default(),
+            reward_destination: Pubkey::default(),
+            referrer: Pubkey::default(),
+            referral_pending: 0,
+            boost_mint,
+            tier: 0,
+            campaign_rewards: Default::default(),
+            reward_index_paid: 0,
+            tier_multiplier_bps: BPS_DENOMINATOR,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn mainnet_rate_over_ten_years() {
+        // 0.10 USDC per 1M tokens per 5 min
+        let rate = RewardRate::per_period(100_000, 1_000_000_000_000, 300).unwrap();
+        assert_eq!(rate, RewardRate(333_333_333_333_333_333_333_333_333));
+
+        let staked = 1_000_000_000_000_000; // 1B tokens at 6 decimals
+        let elapsed = 10 * 365 * 24 * 3600;
+        let rewards = window_rewards(staked, 0, elapsed, rate).unwrap();
+        assert_eq!(rewards, 105_119_999_999_999);
+    }
+
+    #[test]
+    fn per_period_expresses_sub_unit_rates() {
+        // 0.01 USDC per 1B 9-decimal tokens per day
+        let rate = RewardRate::per_period(10_000, 1_000_000_000_000_000_000, 86_400).unwrap();
+        assert!(rate.0 > 0);
+        assert_eq!(rate.rewards_for(1_000_000_000_000_000_000, 86_400), Some(9_999));
+    }
+
+    #[test]
+    fn per_period_rejects_zero_divisors() {
+        assert_eq!(RewardRate::per_period(1, 0, 1), None);
+        assert_eq!(RewardRate::per_period(1, 1, 0), None);
+    }
+
+    #[test]
+    fn rewards_beyond_u64_are_an_error_not_truncated() {
+        // One whole token per staked token per second for two seconds: 2 * u64::MAX
+        let one_per_second = RewardRate(RATE_SCALE);
+        assert!(window_rewards(u64::MAX, 0, 2, one_per_second).is_err());
+        assert!(index_rewards(u64::MAX, u128::MAX).is_err());
+    }
+
+    #[test]
+    fn empty_or_backwards_windows_earn_nothing() {
+        let max = RewardRate(u128::MAX);
+        assert_eq!(window_rewards(0, 0, 1_000, max).unwrap(), 0);
+        assert_eq!(window_rewards(1_000, 50, 50, max).unwrap(), 0);
+        assert_eq!(window_rewards(1_000, 50, 10, max).unwrap(), 0);
+        assert_eq!(
+            window_rewards(u64::MAX, i64::MIN, i64::MAX, RewardRate(0)).unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn rate_changes_only_apply_from_when_they_are_made() {
+        let (old_rate, new_rate) = (RewardRate(RATE_SCALE), RewardRate(3 * RATE_SCALE));
+        let index = RewardIndex { value: 0, updated_at: 100 };
+        // Settled when the rate triples at 150
+        let settled = RewardIndex { value: index.at(old_rate, 150), updated_at: 150 };
+        assert_eq!(settled.at(new_rate, 120), settled.value);
+        assert_eq!(index_rewards(10, settled.at(new_rate, 200)).unwrap(), 500 + 1_500);
+
+        // A position last accrued at 120 earns the old rate until 150 only
+        let paid = index.at(old_rate, 120);
+        assert_eq!(index_rewards(10, settled.at(new_rate, 200) - paid).unwrap(), 300 + 1_500);
+
+        // The index wraps, but differences stay exact
+        let wrapping = RewardIndex { value: u128::MAX - RATE_SCALE, updated_at: 0 };
+        let delta = wrapping.at(old_rate, 5).wrapping_sub(wrapping.value);
+        assert_eq!(index_rewards(7, delta).unwrap(), 35);
+    }
+
+    #[test]
+    fn first_liquid_depositor_gets_receipts_one_to_one() {
+        assert_eq!(receipts_for_deposit(1_000, 0, 0).unwrap(), 1_000);
+        // Compounded leftovers with no receipts outstanding go to the next depositor
+        assert_eq!(receipts_for_deposit(1_000, 500, 0).unwrap(), 1_000);
+        assert_eq!(underlying_for_receipts(1_000, 1_000, 1_000).unwrap(), 1_000);
+        assert!(underlying_for_receipts(1, 0, 0).is_err());
+    }
+
+    #[test]
+    fn liquid_exchange_rounds_in_favour_of_the_position() {
+        // 3 underlying per 2 receipts
+        assert_eq!(receipts_for_deposit(1, 3, 2).unwrap(), 0);
+        assert_eq!(receipts_for_deposit(2, 3, 2).unwrap(), 1);
+        assert_eq!(underlying_for_receipts(1, 3, 2).unwrap(), 1);
+        assert_eq!(underlying_for_receipts(2, 3, 2).unwrap(), 3);
+        assert!(underlying_for_receipts(3, 3, 2).is_err());
+
+        // Depositing and redeeming straight away never returns more than was put in
+        for amount in [1, 7, 999, 1_000_001] {
+            let receipts = receipts_for_deposit(amount, 1_000_003, 999_999).unwrap();
+            let back =
+                underlying_for_receipts(receipts, 1_000_003 + amount, 999_999 + receipts).unwrap();
+            assert!(back <= amount, "{amount} -> {back}");
+        }
+    }
+
+    /// Mock Pyth `PriceUpdateV2` account data
+    fn mock_price_update(
+        price: i64,
+        conf: u64,
+        exponent: i32,
+        publish_time: i64,
+        fully_verified: bool,
+    ) -> Vec<u8> {
+        let mut data = PRICE_UPDATE_V2_DISCRIMINATOR.to_vec();
+        data.extend([7; 32]); // write authority
+        if fully_verified {
+            data.push(1);
+        } else {
+            data.extend([0, 3]); // partial, 3 signatures
+        }
+        data.extend([9; 32]); // feed id
+        data.extend(price.to_le_bytes());
+        data.extend(conf.to_le_bytes());
+        data.extend(exponent.to_le_bytes());
+        data.extend(publish_time.to_le_bytes());
+        data.extend(publish_time.to_le_bytes()); // prev publish time
+        data.extend(price.to_le_bytes()); // ema price
+        data.extend(conf.to_le_bytes()); // ema conf
+        data.extend(42u64.to_le_bytes()); // posted slot
+        data
+    }
+
+    fn apr_oracle(target_apr_bps: u32) -> RateOracle {
+        RateOracle {
+            price_account: Pubkey::new_unique(),
+            target_apr_bps,
+            max_staleness: 60,
+            max_confidence_bps: 200,
+            max_rate_change_bps: 1_000,
+            decimals_offset: 0,
+            last_publish_time: 0,
+        }
+    }
+
+    #[test]
+    fn parses_fully_verified_price_updates_only() {
+        let verified = mock_price_update(10_000, 50, -8, 1_000, true);
+        assert_eq!(
+            OraclePrice::from_price_update(&verified).unwrap(),
+            OraclePrice { price: 10_000, conf: 50, exponent: -8, publish_time: 1_000 }
+        );
+        assert!(OraclePrice::from_price_update(&verified[..80]).is_err());
+
+        let partial = mock_price_update(10_000, 50, -8, 1_000, false);
+        assert!(OraclePrice::from_price_update(&partial).is_err());
+
+        let mut wrong_discriminator = verified.clone();
+        wrong_discriminator[0] ^= 1;
+        assert!(OraclePrice::from_price_update(&wrong_discriminator).is_err());
+    }
+
+    #[test]
+    fn target_rate_pays_the_apr_on_staked_value() {
+        // $0.0001 token, 100% APR: 1M tokens ($100) earn $100 a year
+        let oracle = apr_oracle(10_000);
+        let price = OraclePrice { price: 10_000, conf: 0, exponent: -8, publish_time: 0 };
+        let rate = oracle.target_rate(&price).unwrap();
+        let yearly = rate.rewards_for(1_000_000_000_000, SECONDS_PER_YEAR as u64).unwrap();
+        assert!((99_999_999..=100_000_000).contains(&yearly), "{yearly}");
+
+        // 9-decimal staking token paid in 6-decimal USDC
+        let oracle = RateOracle { decimals_offset: -3, ..oracle };
+        let rate = oracle.target_rate(&price).unwrap();
+        let yearly = rate.rewards_for(1_000_000_000_000_000, SECONDS_PER_YEAR as u64).unwrap();
+        assert!((99_999_999..=100_000_000).contains(&yearly), "{yearly}");
+
+        let extreme = OraclePrice { price: i64::MAX, conf: 0, exponent: 30, publish_time: 0 };
+        assert!(apr_oracle(u32::MAX).target_rate(&extreme).is_err());
+        let tiny = OraclePrice { price: 1, conf: 0, exponent: -60, publish_time: 0 };
+        assert_eq!(oracle.target_rate(&tiny).unwrap(), RewardRate(0));
+    }
+
+    #[test]
+    fn rate_moves_are_bounded_per_period() {
+        let oracle = apr_oracle(10_000); // 10% per period
+        let current = RewardRate(1_000_000);
+        let period = RATE_CHANGE_PERIOD;
+        let up = RewardRate(5_000_000);
+        assert_eq!(oracle.bounded_rate(current, up, period), RewardRate(1_100_000));
+        assert_eq!(oracle.bounded_rate(current, RewardRate(0), period), RewardRate(900_000));
+        assert_eq!(
+            oracle.bounded_rate(current, RewardRate(1_050_000), period),
+            RewardRate(1_050_000)
+        );
+
+        // Frequent refreshes move the rate no faster, and long gaps no further
+        assert_eq!(oracle.bounded_rate(current, up, period / 4), RewardRate(1_025_000));
+        assert_eq!(oracle.bounded_rate(current, up, 0), current);
+        assert_eq!(oracle.bounded_rate(current, up, 10 * period), RewardRate(1_100_000));
+
+        assert_eq!(oracle.bounded_rate(RewardRate(0), up, 0), up);
+        assert_eq!(
+            oracle.bounded_rate(RewardRate(u128::MAX), RewardRate(u128::MAX), period),
+            RewardRate(u128::MAX)
+        );
+    }
+
+    #[test]
+    fn rejects_stale_or_uncertain_prices() {
+        let oracle = apr_oracle(10_000); // 60s staleness, 2% confidence
+        let price = OraclePrice { price: 10_000, conf: 200, exponent: -8, publish_time: 1_000 };
+        assert!(oracle.check_price(&price, 1_060).is_ok());
+        assert!(oracle.check_price(&price, 1_061).is_err());
+        assert!(oracle.check_price(&OraclePrice { conf: 201, ..price }, 1_000).is_err());
+        assert!(oracle.check_price(&OraclePrice { price: 0, ..price }, 1_000).is_err());
+        assert!(oracle.check_price(&OraclePrice { price: -10_000, ..price }, 1_000).is_err());
+    }
+
+    fn clock_at(slot: u64, unix_timestamp: i64) -> Clock {
+        Clock { slot, unix_timestamp, ..Clock::default() }
+    }
+
+    fn empty_checkpoints() -> StakeCheckpoints {
+        StakeCheckpoints {
+            pool: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            truncated: false,
+            checkpoints: Vec::new(),
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn checkpoints_exclude_changes_made_at_the_snapshot_time() {
+        let mut history = empty_checkpoints();
+        assert_eq!(history.amount_at(i64::MAX).unwrap(), 0);
+
+        history.record(100, &clock_at(10, 1_000));
+        history.record(250, &clock_at(20, 1_010));
+        history.record(0, &clock_at(30, 1_020));
+
+        assert_eq!(history.amount_at(1_000).unwrap(), 0);
+        assert_eq!(history.amount_at(1_001).unwrap(), 100);
+        assert_eq!(history.amount_at(1_010).unwrap(), 100);
+        assert_eq!(history.amount_at(1_011).unwrap(), 250);
+        assert_eq!(history.amount_at(1_021).unwrap(), 0);
+    }
+
+    #[test]
+    fn checkpoints_coalesce_within_a_slot_and_skip_unchanged_balances() {
+        let mut history = empty_checkpoints();
+        history.record(100, &clock_at(10, 1_000));
+        history.record(5_000, &clock_at(10, 1_000));
+        history.record(5_000, &clock_at(11, 1_001));
+
+        assert_eq!(
+            history.checkpoints,
+            vec![Checkpoint { slot: 10, timestamp: 1_000, amount: 5_000 }]
+        );
+    }
+
+    #[test]
+    fn full_histories_merge_their_oldest_changes_into_the_lower_balance() {
+        let mut history = empty_checkpoints();
+        history.record(500, &clock_at(0, 1_000));
+        for i in 1..=CHECKPOINT_CAPACITY as u64 {
+            history.record(i, &clock_at(i, 1_000 + i as i64));
+        }
+
+        assert!(!history.truncated);
+        assert_eq!(history.checkpoints.len(), CHECKPOINT_CAPACITY);
+        assert_eq!(history.amount_at(1_000).unwrap(), 0);
+        // 500 then 1 merged into 1: a lower bound, never more than was staked
+        assert_eq!(history.amount_at(1_001).unwrap(), 1);
+        assert_eq!(history.amount_at(1_002).unwrap(), 1);
+        assert_eq!(history.amount_at(1_003).unwrap(), 2);
+        assert_eq!(history.amount_at(i64::MAX).unwrap(), CHECKPOINT_CAPACITY as u64);
+    }
+
+    #[test]
+    fn history_from_before_the_first_checkpoint_is_unknown() {
+        let mut history = empty_checkpoints();
+        init_checkpoints(&mut history, Pubkey::new_unique(), Pubkey::new_unique(), 254, 0);
+        assert!(!history.truncated);
+
+        let mut history = empty_checkpoints();
+        history.pool = Pubkey::default();
+        init_checkpoints(&mut history, Pubkey::new_unique(), Pubkey::new_unique(), 254, 500);
+        history.record(400, &clock_at(10, 1_000));
+        assert!(history.amount_at(1_000).is_err());
+        assert_eq!(history.amount_at(1_001).unwrap(), 400);
+    }
+
+    fn checkpoint_ring() -> (CheckpointRing, Vec<CheckpointPage>) {
+        let pool = Pubkey::new_unique();
+        let ring = CheckpointRing::INITIAL;
+        let pages = (0..ring.pages)
+            .map(|page| CheckpointPage {
+                pool,
+                page,
+                sequence: 0,
+                checkpoints: Vec::new(),
+                bump: 255,
+            })
+            .collect();
+        (ring, pages)
+    }
+
+    /// Record `amount` at `slot` (and time `1_000 + slot`) like the program would
+    fn record_total(ring: &mut CheckpointRing, pages: &mut [CheckpointPage], slot: u64) {
+        let [current, next] = pages
+            .get_disjoint_mut([ring.current as usize, ring.next as usize])
+            .unwrap();
+        ring.record(current, next, slot + 1, &clock_at(slot, 1_000 + slot as i64));
+    }
+
+    #[test]
+    fn pool_totals_fill_pages_in_turn() {
+        let (mut ring, mut pages) = checkpoint_ring();
+        assert_eq!(pages[0].amount_at(i64::MAX, None, ring.sequence).unwrap(), 0);
+
+        let capacity = CHECKPOINT_PAGE_CAPACITY as u64;
+        for slot in 0..=capacity {
+            record_total(&mut ring, &mut pages, slot);
+        }
+        assert_eq!((ring.current, ring.next, ring.sequence), (1, 0, 1));
+        assert_eq!(pages[0].checkpoints.len(), CHECKPOINT_PAGE_CAPACITY);
+        assert_eq!(pages[1].checkpoints.len(), 1);
+
+        // Times before the first change read zero from the first page
+        assert_eq!(pages[0].amount_at(1_000, None, ring.sequence).unwrap(), 0);
+        assert_eq!(pages[0].amount_at(1_005, None, ring.sequence).unwrap(), 5);
+        // Past a full page's last change only the page written after it can vouch
+        let last = 1_000 + capacity as i64;
+        assert!(pages[0].amount_at(last, None, ring.sequence).is_err());
+        assert!(pages[0]
+            .amount_at(last + 1, Some(&pages[1]), ring.sequence)
+            .is_err());
+        assert_eq!(
+            pages[0]
+                .amount_at(last, Some(&pages[1]), ring.sequence)
+                .unwrap(),
+            capacity
+        );
+        assert!(pages[1].amount_at(last, None, ring.sequence).is_err());
+        assert_eq!(
+            pages[1].amount_at(i64::MAX, None, ring.sequence).unwrap(),
+            capacity + 1
+        );
+    }
+
+    #[test]
+    fn overwritten_pages_make_older_totals_unavailable() {
+        let (mut ring, mut pages) = checkpoint_ring();
+        let capacity = CHECKPOINT_PAGE_CAPACITY as u64;
+        for slot in 0..=2 * capacity {
+            record_total(&mut ring, &mut pages, slot);
+        }
+        assert_eq!((ring.current, ring.sequence), (0, 2));
+        assert_eq!(pages[0].sequence, 2);
+        assert!(pages[1].amount_at(1_005, None, ring.sequence).is_err());
+        assert!(pages[0].amount_at(1_005, None, ring.sequence).is_err());
+        assert_eq!(
+            pages[1]
+                .amount_at(1_000 + 2 * capacity as i64, Some(&pages[0]), ring.sequence)
+                .unwrap(),
+            2 * capacity
+        );
+
+        // Added pages join the ring after the current page
+        let pool = pages[0].pool;
+        ring.add_page().unwrap();
+        pages.push(CheckpointPage {
+            pool,
+            page: 2,
+            sequence: 0,
+            checkpoints: Vec::new(),
+            bump: 255,
+        });
+        assert_eq!((ring.pages, ring.next), (3, 1));
+        assert!(pages[2].amount_at(1_005, None, ring.sequence).is_err());
+    }
+
+    /// Borsh `Option<Pubkey>`
+    fn optional_key(key: Option<Pubkey>) -> Vec<u8> {
+        match key {
+            Some(key) => [&[1][..], &key.to_bytes()].concat(),
+            None => vec![0],
+        }
+    }
+
+    /// Mock spl-governance `RealmV2` account data
+    fn mock_realm(
+        community_mint: Pubkey,
+        council_mint: Option<Pubkey>,
+        authority: Option<Pubkey>,
+    ) -> Vec<u8> {
+        let mut data = vec![16];
+        data.extend(community_mint.to_bytes());
+        data.extend([0; 2 + 6 + 8]); // legacy, reserved, min weight to create governance
+        data.push(0); // max voter weight source: supply fraction
+        data.extend(10_000_000_000u64.to_le_bytes());
+        data.extend(optional_key(council_mint));
+        data.extend([0; 6 + 2]); // reserved, legacy
+        data.extend(optional_key(authority));
+        data.extend(4u32.to_le_bytes());
+        data.extend(b"Meme");
+        data.extend([0; 128]);
+        data
+    }
+
+    #[test]
+    fn parses_realm_mints_and_authority() {
+        let (community, council, authority) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+        let realm = mock_realm(community, Some(council), Some(authority));
+        assert_eq!(
+            RealmInfo::from_account_data(&realm).unwrap(),
+            RealmInfo {
+                community_mint: community,
+                council_mint: Some(council),
+                authority: Some(authority),
+            }
+        );
+
+        let realm = RealmInfo::from_account_data(&mock_realm(community, None, Some(authority)));
+        assert_eq!(realm.unwrap().authority, Some(authority));
+
+        let mut not_a_realm = mock_realm(community, None, None);
+        not_a_realm[0] = 14; // ProposalV2
+        assert!(RealmInfo::from_account_data(&not_a_realm).is_err());
+        assert!(RealmInfo::from_account_data(&mock_realm(community, None, None)[..60]).is_err());
+    }
+
+    /// Mock spl-governance `ProposalV2` account data, created at `draft_at`
+    fn mock_proposal(governing_token_mint: Pubkey, draft_at: i64) -> Vec<u8> {
+        let mut data = vec![14];
+        data.extend(Pubkey::new_unique().to_bytes()); // governance
+        data.extend(governing_token_mint.to_bytes());
+        data.push(2); // state: voting
+        data.extend(Pubkey::new_unique().to_bytes()); // token owner record
+        data.extend([1, 1]); // signatories, signed off
+        data.extend([1, 0, 1, 2, 1]); // vote type: multi choice
+        data.extend(2u32.to_le_bytes());
+        for label in [&b"Yes"[..], b"Later"] {
+            data.extend((label.len() as u32).to_le_bytes());
+            data.extend(label);
+            data.extend([0; 8 + 1 + 3 * 2]);
+        }
+        data.extend([1, 0, 0, 0, 0, 0, 0, 0, 0]); // deny vote weight
+        data.push(0); // reserved
+        data.push(0); // abstain vote weight
+        data.extend([1].into_iter().chain(draft_at.to_le_bytes())); // start voting at
+        data.extend(draft_at.to_le_bytes());
+        data.extend([0; 64]);
+        data
+    }
+
+    #[test]
+    fn parses_proposal_mint_and_creation_time() {
+        let mint = Pubkey::new_unique();
+        let proposal = mock_proposal(mint, 1_700_000_000);
+        assert_eq!(
+            ProposalInfo::from_account_data(&proposal).unwrap(),
+            ProposalInfo {
+                governing_token_mint: mint,
+                draft_at: 1_700_000_000,
+            }
+        );
+
+        assert!(ProposalInfo::from_account_data(&mock_realm(mint, None, None)).is_err());
+        assert!(ProposalInfo::from_account_data(&proposal[..180]).is_err());
+    }
+
+    #[test]
+    fn vote_weight_stays_fixed_after_a_transfer() {
+        let config = VoterWeightConfig {
+            realm: Pubkey::new_unique(),
+            governing_token_mint: Pubkey::new_unique(),
+            max_lock_boost_bps: 0,
+            lock_boost_saturation: 0,
+        };
+        let (mut sender, mut receiver) = (empty_checkpoints(), empty_checkpoints());
+        sender.record(1_000, &clock_at(10, 1_000));
+
+        // The proposal is created at 1_050, then the stake moves to a second wallet
+        let draft_at = 1_050;
+        sender.record(0, &clock_at(20, 1_060));
+        receiver.record(1_000, &clock_at(20, 1_060));
+
+        let weight = |history: &StakeCheckpoints| {
+            config.voter_weight(history.amount_at(draft_at).unwrap(), 0, 1_100)
+        };
+        assert_eq!(weight(&sender).unwrap(), 1_000);
+        assert_eq!(weight(&receiver).unwrap(), 0);
+
+        // Neither does stake added within the proposal's first second
+        let mut flash = empty_checkpoints();
+        flash.record(2_000, &clock_at(15, draft_at));
+        assert_eq!(weight(&flash).unwrap(), 0);
+    }
+
+    #[test]
+    fn voter_weight_boost_scales_with_remaining_lock() {
+        let config = VoterWeightConfig {
+            realm: Pubkey::new_unique(),
+            governing_token_mint: Pubkey::new_unique(),
+            max_lock_boost_bps: 10_000, // up to 2x
+            lock_boost_saturation: 1_000,
+        };
+        assert_eq!(config.voter_weight(1_000, 5_000, 0).unwrap(), 2_000);
+        assert_eq!(config.voter_weight(1_000, 1_000, 0).unwrap(), 2_000);
+        assert_eq!(config.voter_weight(1_000, 750, 500).unwrap(), 1_250);
+        assert_eq!(config.voter_weight(1_000, 0, 500).unwrap(), 1_000);
+        assert!(config.voter_weight(u64::MAX, 1_000, 0).is_err());
+
+        let unboosted = VoterWeightConfig { lock_boost_saturation: 0, ..config };
+        assert_eq!(unboosted.voter_weight(u64::MAX, i64::MAX, 0).unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn referral_bonus_rounds_down_and_never_exceeds_the_rewards() {
+        assert_eq!(referral_bonus(1_000, 1_000), 100);
+        assert_eq!(referral_bonus(9, 1_000), 0);
+        assert_eq!(referral_bonus(u64::MAX, 0), 0);
+        assert_eq!(referral_bonus(u64::MAX, BPS_DENOMINATOR), u64::MAX);
+    }
+
+    #[test]
+    fn fees_round_down_and_are_capped() {
+        assert_eq!(fee_for(10_000, 150), 150);
+        assert_eq!(fee_for(66, 150), 0);
+        assert_eq!(fee_for(u64::MAX, u16::MAX), u64::MAX);
+
+        let fees = FeeConfig {
+            deposit_bps: MAX_DEPOSIT_FEE_BPS,
+            withdrawal_bps: MAX_WITHDRAWAL_FEE_BPS,
+            performance_bps: MAX_PERFORMANCE_FEE_BPS,
+            ..FeeConfig::default()
+        };
+        assert!(fees.check_caps().is_ok());
+        for fees in [
+            FeeConfig { deposit_bps: MAX_DEPOSIT_FEE_BPS + 1, ..fees },
+            FeeConfig { withdrawal_bps: MAX_WITHDRAWAL_FEE_BPS + 1, ..fees },
+            FeeConfig { performance_bps: MAX_PERFORMANCE_FEE_BPS + 1, ..fees },
+        ] {
+            assert!(fees.check_caps().is_err());
+        }
+    }
+
+    /// Borsh `MetadataV1` up to its collection
+    fn metadata(collection: Option<(bool, Pubkey)>) -> Vec<u8> {
+        let mut data = vec![4];
+        data.extend_from_slice(&[7; 64]);
+        for field in ["Meme #1", "MEME", "https://example.com/1.json"] {
+            data.extend_from_slice(&(field.len() as u32).to_le_bytes());
+            data.extend_from_slice(field.as_bytes());
+        }
+        data.extend_from_slice(&500u16.to_le_bytes());
+        // One creator, then primary sale, mutability, edition nonce and token standard
+        data.push(1);
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&[9; 32]);
+        data.extend_from_slice(&[1, 100]);
+        data.extend_from_slice(&[1, 1, 1, 254, 1, 0]);
+        match collection {
+            Some((verified, key)) => {
+                data.extend_from_slice(&[1, verified as u8]);
+                data.extend_from_slice(key.as_ref());
+            }
+            None => data.push(0),
+        }
+        // Trailing uses, collection details and padding are ignored
+        data.extend_from_slice(&[0; 40]);
+        data
+    }
+
+    #[test]
+    fn only_verified_collections_are_read_from_metadata() {
+        let collection = Pubkey::new_unique();
+        assert_eq!(
+            verified_collection(&metadata(Some((true, collection)))).unwrap(),
+            Some(collection)
+        );
+        assert_eq!(
+            verified_collection(&metadata(Some((false, collection)))).unwrap(),
+            None
+        );
+        assert_eq!(verified_collection(&metadata(None)).unwrap(), None);
+
+        let mut edition = metadata(Some((true, collection)));
+        edition[0] = 6;
+        assert!(verified_collection(&edition).is_err());
+        assert!(verified_collection(&metadata(None)[..100]).is_err());
+    }
+
+    #[test]
+    fn boost_nfts_multiply_the_reward_weight_while_enabled() {
+        let mut user_stake = user_stake(1_000, Pubkey::new_unique());
+        let mut nft_boost = NftBoost {
+            collection: Pubkey::new_unique(),
+            multiplier_bps: 15_000,
+        };
+        assert_eq!(user_stake.boost_bps(&nft_boost), 15_000);
+        assert_eq!(boosted(1_000, user_stake.boost_bps(&nft_boost)), Some(1_500));
+
+        nft_boost.collection = Pubkey::default();
+        assert_eq!(user_stake.boost_bps(&nft_boost), BPS_DENOMINATOR);
+
+        nft_boost.collection = Pubkey::new_unique();
+        user_stake.boost_mint = Pubkey::default();
+        assert_eq!(user_stake.boost_bps(&nft_boost), BPS_DENOMINATOR);
+
+        assert_eq!(boosted(u64::MAX, BPS_DENOMINATOR), Some(u64::MAX));
+        assert_eq!(boosted(u64::MAX, MAX_NFT_BOOST_BPS), None);
+    }
+
+    #[test]
+    fn tiers_are_assigned_by_stake_size() {
+        let tier = |min_stake, multiplier_bps| Tier {
+            min_stake,
+            multiplier_bps,
+        };
+        let tiers = TierTable::new(&[
+            tier(1_000_000, BPS_DENOMINATOR),
+            tier(10_000_000, 15_000),
+            tier(100_000_000, 20_000),
+        ])
+        .unwrap();
+
+        assert_eq!(tiers.min_stake(), 1_000_000);
+        assert_eq!(tiers.tier_for(0), 0);
+        assert_eq!(tiers.tier_for(999_999), 0);
+        assert_eq!(tiers.tier_for(1_000_000), 1);
+        assert_eq!(tiers.tier_for(99_999_999), 2);
+        assert_eq!(tiers.tier_for(u64::MAX), 3);
+        assert_eq!(tiers.multiplier_bps(0), BPS_DENOMINATOR);
+        assert_eq!(tiers.multiplier_bps(2), 15_000);
+        assert_eq!(tiers.multiplier_bps(3), 20_000);
+        assert_eq!(tiers.multiplier_bps(4), BPS_DENOMINATOR);
+
+        assert!(TierTable::new(&[]).is_err());
+        assert!(TierTable::new(&[tier(10, 10_000), tier(10, 20_000)]).is_err());
+        assert!(TierTable::new(&[tier(10, 20_000), tier(1, 10_000)]).is_err());
+        assert!(TierTable::new(&[tier(1, 9_999)]).is_err());
+        assert!(TierTable::new(&[tier(1, MAX_TIER_MULTIPLIER_BPS + 1)]).is_err());
+        assert!(TierTable::new(&[tier(1, 10_000); MAX_TIERS + 1]).is_err());
+    }
+
+    #[test]
+    fn campaigns_split_emissions_by_stake_while_anything_is_staked() {
+        let mut slot = CampaignSlot {
+            campaign: Pubkey::new_unique(),
+            start_time: 100,
+            end_time: 200,
+            amount: 1_000,
+            allocated: 0,
+            reward_per_token: 0,
+            last_update_time: 100,
+        };
+        slot.update(300, 50).unwrap();
+        assert_eq!(slot.last_update_time, 100);
+
+        // Nothing staked for the first half: emitted but unallocated
+        slot.update(0, 150).unwrap();
+        assert_eq!((slot.allocated, slot.reward_per_token), (0, 0));
+        slot.update(300, 250).unwrap();
+        assert_eq!(slot.allocated, 500);
+        assert_eq!(slot.last_update_time, 200);
+        assert_eq!(slot.rewards_since(100, 0), Some(166));
+        assert_eq!(slot.rewards_since(200, 0), Some(333));
+
+        let mut campaigns = CampaignState::default();
+        campaigns.slots[1] = slot;
+        let mut user_stake = user_stake(200, Pubkey::default());
+        // Entries of an earlier campaign in the slot don't count
+        user_stake.campaign_rewards[1] = CampaignReward {
+            campaign: Pubkey::new_unique(),
+            reward_per_token_paid: slot.reward_per_token,
+            pending: 7,
+        };
+        assert_eq!(campaign_claimable(&campaigns, 300, &user_stake, 1, 300), Some(333));
+        assert!(!user_stake.has_campaign_rewards(&campaigns));
+
+        user_stake.campaign_rewards[1] = CampaignReward {
+            campaign: slot.campaign,
+            reward_per_token_paid: slot.reward_per_token,
+            pending: 7,
+        };
+        assert_eq!(campaign_claimable(&campaigns, 300, &user_stake, 1, 300), Some(7));
+        assert!(user_stake.has_campaign_rewards(&campaigns));
+        assert_eq!(campaign_claimable(&campaigns, 300, &user_stake, 0, 300), Some(0));
+    }
+
+    #[test]
+    fn campaigns_pay_at_least_a_token_per_day() {
+        let day = MIN_CAMPAIGN_EMISSION_PERIOD;
+        assert_eq!(min_campaign_amount(1, 6), Some(1_000_000));
+        assert_eq!(min_campaign_amount(day, 6), Some(1_000_000));
+        assert_eq!(min_campaign_amount(day + day / 2, 6), Some(1_500_000));
+        assert_eq!(min_campaign_amount(MAX_CAMPAIGN_DURATION, 0), Some(365));
+        assert_eq!(min_campaign_amount(MAX_CAMPAIGN_DURATION, 18), None);
+    }
+
+    #[test]
+    fn distributor_proofs_only_verify_their_own_leaf() {
+        let claimants = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let leaves: Vec<_> = claimants
+            .iter()
+            .enumerate()
+            .map(|(index, claimant)| distributor_leaf(index as u32, claimant, 100))
+            .collect();
+        let left = distributor_node(&leaves[0], &leaves[1]);
+        let root = distributor_node(&left, &leaves[2]);
+
+        assert!(verify_distributor_proof(&[leaves[1], leaves[2]], &root, leaves[0]));
+        assert!(verify_distributor_proof(&[leaves[0], leaves[2]], &root, leaves[1]));
+        assert!(verify_distributor_proof(&[left], &root, leaves[2]));
+
+        let inflated = distributor_leaf(2, &claimants[2], 101);
+        assert!(!verify_distributor_proof(&[left], &root, inflated));
+        let moved = distributor_leaf(1, &claimants[2], 100);
+        assert!(!verify_distributor_proof(&[left], &root, moved));
+    }
+
+    #[test]
+    fn distributor_leaves_are_claimed_once() {
+        let mut epoch = DistributorEpoch {
+            pool: Pubkey::new_unique(),
+            epoch: 0,
+            merkle_root: [0; 32],
+            total_amount: 1,
+            claimed_amount: 0,
+            num_leaves: 9,
+            claimed_count: 0,
+            bump: 255,
+            claimed: vec![0; DistributorEpoch::bitmap_len(9)],
+        };
+        assert_eq!(epoch.claimed.len(), 2);
+
+        epoch.set_claimed(8).unwrap();
+        assert!(epoch.is_claimed(8));
+        assert!(!epoch.is_claimed(0));
+        assert!(epoch.set_claimed(8).is_err());
+        assert!(epoch.set_claimed(9).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn errors_exactly_when_result_exceeds_u64(
+            staked in any::<u64>(),
+            index_delta in any::<u128>(),
+        ) {
+            let approx = approx_rewards(staked, index_delta);
+            match index_rewards(staked, index_delta) {
+                Ok(rewards) => {
+                    prop_assert!(approx <= u64::MAX as f64 * 1.000_001);
+                    prop_assert!((rewards as f64 - approx).abs() <= approx * 1e-9 + 1.0);
+                }
+                Err(_) => prop_assert!(approx >= u64::MAX as f64 * 0.999_999),
+            }
+        }
+
+        #[test]
+        fn matches_exact_math_for_small_inputs(
+            staked in any::<u32>(),
+            rate in any::<u64>(),
+            elapsed in any::<u32>(),
+        ) {
+            let exact = staked as u128 * elapsed as u128 * rate as u128 / RATE_SCALE;
+            prop_assert_eq!(
+                RewardRate(rate as u128).rewards_for(staked as u64, elapsed as u64),
+                Some(exact as u64)
+            );
+        }
+
+        #[test]
+        fn checkpointing_never_over_credits(
+            staked in any::<u64>(),
+            first in 0..=u128::MAX / 2,
+            second in 0..=u128::MAX / 2,
+        ) {
+            let whole = index_rewards(staked, first + second);
+            let head = index_rewards(staked, first);
+            let tail = index_rewards(staked, second);
+
+            if let (Ok(whole), Ok(head), Ok(tail)) = (whole, head, tail) {
+                let split = head as u128 + tail as u128;
+                prop_assert!(split <= whole as u128);
+                prop_assert!(whole as u128 - split <= 1);
+            }
+        }
+
+        #[test]
+        fn monotonic_in_index_growth(
+            staked in any::<u64>(),
+            smaller in 0..=u128::MAX / 2,
+            extra in 0..=u128::MAX / 2,
+        ) {
+            let short = index_rewards(staked, smaller);
+            let long = index_rewards(staked, smaller + extra);
+            match (short, long) {
+                (Ok(short), Ok(long)) => prop_assert!(short <= long),
+                (Err(_), long) => prop_assert!(long.is_err()),
+                _ => {}
+            }
+        }
+
+        #[test]
+        fn per_period_round_trips(
+            reward in any::<u32>(),
+            per_staked in 1..=u64::MAX,
+            period in 1..=u32::MAX as u64,
+        ) {
+            if let Some(rate) = RewardRate::per_period(reward as u64, per_staked, period) {
+                let paid = rate.rewards_for(per_staked, period).unwrap();
+                prop_assert!(paid <= reward as u64);
+                prop_assert!(reward as u64 - paid <= 1);
+            }
+        }
+    }
+}
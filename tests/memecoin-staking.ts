@@ -4,10 +4,13 @@ import { MemecoinStaking } from "../target/types/memecoin_staking";
 import {
   createMint,
   getOrCreateAssociatedTokenAccount,
+  getAssociatedTokenAddressSync,
   mintTo,
+  TOKEN_2022_PROGRAM_ID,
   TOKEN_PROGRAM_ID,
 } from "@solana/spl-token";
 import { assert } from "chai";
+import { createHash } from "crypto";
 
 describe("memecoin-staking", () => {
   const provider = anchor.AnchorProvider.env();
@@ -25,7 +28,8 @@ describe("memecoin-staking", () => {
   let userTokenAccount: anchor.web3.PublicKey;
   let userRewardAccount: anchor.web3.PublicKey;
 
-  const REWARD_RATE = new anchor.BN(1_000_000); // 0.001 tokens per second per token staked
+  // RewardRate is fixed-point scaled by 1e36: 1e-12 reward units per second per staked unit
+  const REWARD_RATE = new anchor.BN("1000000000000000000000000");
   const LOCK_PERIOD = new anchor.BN(0); // No lock for testing
   const STAKE_AMOUNT = new anchor.BN(100_000_000); // 100 tokens (with 6 decimals)
 
@@ -97,9 +101,187 @@ describe("memecoin-staking", () => {
     );
   });
 
+  // A second wallet with SOL for fees and `tokens` staking tokens
+  const fundedWallet = async (tokens: number) => {
+    const wallet = anchor.web3.Keypair.generate();
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(wallet.publicKey, anchor.web3.LAMPORTS_PER_SOL)
+    );
+    const tokenAccount = (
+      await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        (authority as any).payer,
+        stakingMint,
+        wallet.publicKey
+      )
+    ).address;
+    await mintTo(
+      provider.connection,
+      (authority as any).payer,
+      stakingMint,
+      tokenAccount,
+      authority.publicKey,
+      tokens
+    );
+    const [stake] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("user_stake"), pool.toBuffer(), wallet.publicKey.toBuffer()],
+      program.programId
+    );
+    return { wallet, tokenAccount, stake };
+  };
+
+  // A second wallet whose whole stake of `tokens` has moved into a position NFT
+  const tokenizedPosition = async (tokens: number) => {
+    const staker = await fundedWallet(tokens);
+    await program.methods
+      .stake(new anchor.BN(tokens))
+      .accounts({
+        user: staker.wallet.publicKey,
+        pool,
+        userStake: staker.stake,
+        userTokenAccount: staker.tokenAccount,
+        poolVault,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([staker.wallet])
+      .rpc();
+    const positionMint = anchor.web3.Keypair.generate();
+    const [positionStake] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("position"), positionMint.publicKey.toBuffer()],
+      program.programId
+    );
+    const holderPositionAccount = getAssociatedTokenAddressSync(
+      positionMint.publicKey,
+      staker.wallet.publicKey,
+      false,
+      TOKEN_2022_PROGRAM_ID
+    );
+    await program.methods
+      .tokenizeStake()
+      .accountsPartial({
+        user: staker.wallet.publicKey,
+        pool,
+        userStake: staker.stake,
+        positionMint: positionMint.publicKey,
+        positionStake,
+        userPositionAccount: holderPositionAccount,
+        positionTokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .signers([staker.wallet, positionMint])
+      .rpc();
+    return { holder: staker.wallet, positionStake, holderPositionAccount };
+  };
+
+  const TOKEN_METADATA_PROGRAM_ID = new anchor.web3.PublicKey(
+    "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s"
+  );
+  const metadataAddress = (mint: anchor.web3.PublicKey, ...extra: Buffer[]) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("metadata"), TOKEN_METADATA_PROGRAM_ID.toBuffer(), mint.toBuffer(), ...extra],
+      TOKEN_METADATA_PROGRAM_ID
+    )[0];
+  const borshString = (value: string) => {
+    const length = Buffer.alloc(4);
+    length.writeUInt32LE(value.length);
+    return Buffer.concat([length, Buffer.from(value)]);
+  };
+
+  // An NFT held by the authority: a master edition without `collection`, otherwise
+  // an item verified as part of it
+  const createNft = async (collection: anchor.web3.PublicKey | null) => {
+    const payer = (authority as any).payer;
+    const mint = await createMint(
+      provider.connection,
+      payer,
+      authority.publicKey,
+      authority.publicKey,
+      0
+    );
+    const tokenAccount = (
+      await getOrCreateAssociatedTokenAccount(provider.connection, payer, mint, authority.publicKey)
+    ).address;
+    await mintTo(provider.connection, payer, mint, tokenAccount, authority.publicKey, 1);
+
+    const metadata = metadataAddress(mint);
+    const signer = (pubkey: anchor.web3.PublicKey, isWritable = false) => ({
+      pubkey,
+      isSigner: true,
+      isWritable,
+    });
+    const account = (pubkey: anchor.web3.PublicKey, isWritable = false) => ({
+      pubkey,
+      isSigner: false,
+      isWritable,
+    });
+    const sellerFeeBps = Buffer.alloc(2);
+    const tx = new anchor.web3.Transaction().add(
+      new anchor.web3.TransactionInstruction({
+        programId: TOKEN_METADATA_PROGRAM_ID,
+        keys: [
+          account(metadata, true),
+          account(mint),
+          signer(authority.publicKey),
+          signer(authority.publicKey, true),
+          signer(authority.publicKey),
+          account(anchor.web3.SystemProgram.programId),
+        ],
+        // CreateMetadataAccountV3: no creators, uses or collection details
+        data: Buffer.concat([
+          Buffer.from([33]),
+          borshString("Boost"),
+          borshString("BOOST"),
+          borshString(""),
+          sellerFeeBps,
+          Buffer.from([0]),
+          collection
+            ? Buffer.concat([Buffer.from([1, 0]), collection.toBuffer()])
+            : Buffer.from([0]),
+          Buffer.from([0, 1, 0]),
+        ]),
+      })
+    );
+    if (collection) {
+      tx.add(
+        new anchor.web3.TransactionInstruction({
+          programId: TOKEN_METADATA_PROGRAM_ID,
+          keys: [
+            account(metadata, true),
+            signer(authority.publicKey, true),
+            signer(authority.publicKey, true),
+            account(collection),
+            account(metadataAddress(collection)),
+            account(metadataAddress(collection, Buffer.from("edition"))),
+          ],
+          data: Buffer.from([18]), // VerifyCollection
+        })
+      );
+    } else {
+      tx.add(
+        new anchor.web3.TransactionInstruction({
+          programId: TOKEN_METADATA_PROGRAM_ID,
+          keys: [
+            account(metadataAddress(mint, Buffer.from("edition")), true),
+            account(mint, true),
+            signer(authority.publicKey),
+            signer(authority.publicKey),
+            signer(authority.publicKey, true),
+            account(metadata, true),
+            account(TOKEN_PROGRAM_ID),
+            account(anchor.web3.SystemProgram.programId),
+          ],
+          // CreateMasterEditionV3 with a max supply of zero prints
+          data: Buffer.concat([Buffer.from([17, 1]), Buffer.alloc(8)]),
+        })
+      );
+    }
+    await provider.sendAndConfirm(tx);
+    return { mint, tokenAccount };
+  };
+
   it("Initializes stake pool", async () => {
     const tx = await program.methods
-      .initializePool(REWARD_RATE, LOCK_PERIOD)
+      .initializePool([REWARD_RATE], LOCK_PERIOD)
       .accounts({
         authority: authority.publicKey,
         pool,
@@ -119,9 +301,9 @@ describe("memecoin-staking", () => {
     assert.equal(poolAccount.authority.toString(), authority.publicKey.toString());
     assert.equal(poolAccount.stakingMint.toString(), stakingMint.toString());
     assert.equal(poolAccount.rewardMint.toString(), rewardMint.toString());
-    assert.equal(poolAccount.rewardRate.toString(), REWARD_RATE.toString());
+    assert.equal(poolAccount.rewardRate[0].toString(), REWARD_RATE.toString());
     assert.equal(poolAccount.totalStaked.toString(), "0");
-    assert.equal(poolAccount.paused, false);
+    assert.equal(poolAccount.pauseFlags, 0);
   });
 
   it("Funds reward vault", async () => {
@@ -232,10 +414,10 @@ describe("memecoin-staking", () => {
   });
 
   it("Updates reward rate (admin)", async () => {
-    const newRate = new anchor.BN(2_000_000);
+    const newRate = REWARD_RATE.muln(2);
 
     const tx = await program.methods
-      .updateRewardRate(newRate)
+      .updateRewardRate([newRate])
       .accounts({
         authority: authority.publicKey,
         pool,
@@ -245,7 +427,38 @@ describe("memecoin-staking", () => {
     console.log("Update reward rate tx:", tx);
 
     const poolAccount = await program.account.stakePool.fetch(pool);
-    assert.equal(poolAccount.rewardRate.toString(), newRate.toString());
+    assert.equal(poolAccount.rewardRate[0].toString(), newRate.toString());
+  });
+
+  it("Updates reward rate from a per-period amount (admin)", async () => {
+    // 0.10 reward tokens per 1M staked tokens per 5 minutes (6 decimals each)
+    await program.methods
+      .updateRewardRatePerPeriod(
+        new anchor.BN(100_000),
+        new anchor.BN(1_000_000_000_000),
+        new anchor.BN(300)
+      )
+      .accounts({
+        authority: authority.publicKey,
+        pool,
+      })
+      .rpc();
+
+    const poolAccount = await program.account.stakePool.fetch(pool);
+    assert.equal(poolAccount.rewardRate[0].toString(), "333333333333333333333333333");
+
+    try {
+      await program.methods
+        .updateRewardRatePerPeriod(new anchor.BN(1), new anchor.BN(1), new anchor.BN(0))
+        .accounts({
+          authority: authority.publicKey,
+          pool,
+        })
+        .rpc();
+      assert.fail("Should have rejected a zero period");
+    } catch (e: any) {
+      assert.include(e.message, "InvalidRewardRate");
+    }
   });
 
   it("Pauses and unpauses pool", async () => {
@@ -259,7 +472,7 @@ describe("memecoin-staking", () => {
       .rpc();
 
     let poolAccount = await program.account.stakePool.fetch(pool);
-    assert.equal(poolAccount.paused, true);
+    assert.notEqual(poolAccount.pauseFlags, 0);
 
     // Try to stake while paused (should fail)
     try {
@@ -290,7 +503,1287 @@ describe("memecoin-staking", () => {
       .rpc();
 
     poolAccount = await program.account.stakePool.fetch(pool);
-    assert.equal(poolAccount.paused, false);
+    assert.equal(poolAccount.pauseFlags, 0);
   });
-});
 
+  it("Pauses claims only", async () => {
+    const PAUSE_CLAIM = 1 << 2;
+
+    await program.methods
+      .setPauseFlags(PAUSE_CLAIM)
+      .accounts({
+        authority: authority.publicKey,
+        pool,
+      })
+      .rpc();
+
+    const poolAccount = await program.account.stakePool.fetch(pool);
+    assert.equal(poolAccount.pauseFlags, PAUSE_CLAIM);
+
+    try {
+      await program.methods
+        .claimRewards()
+        .accounts({
+          user: authority.publicKey,
+          pool,
+          userStake: userStakeAccount,
+          userRewardAccount,
+          rewardVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+      assert.fail("Should have thrown error");
+    } catch (err: any) {
+      assert.include(err.toString(), "PoolPaused");
+    }
+
+    await program.methods
+      .setPauseFlags(0)
+      .accounts({
+        authority: authority.publicKey,
+        pool,
+      })
+      .rpc();
+  });
+
+  it("Emergency withdraws full stake and forfeits rewards", async () => {
+    await program.methods
+      .stake(STAKE_AMOUNT)
+      .accounts({
+        user: authority.publicKey,
+        pool,
+        userStake: userStakeAccount,
+        userTokenAccount,
+        poolVault,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+
+    await program.methods
+      .setEmergencyMode(true)
+      .accounts({
+        authority: authority.publicKey,
+        pool,
+      })
+      .rpc();
+
+    const beforeBalance = await provider.connection.getTokenAccountBalance(userTokenAccount);
+
+    await program.methods
+      .emergencyWithdraw()
+      .accounts({
+        user: authority.publicKey,
+        pool,
+        userStake: userStakeAccount,
+        userTokenAccount,
+        poolVault,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+
+    const afterBalance = await provider.connection.getTokenAccountBalance(userTokenAccount);
+    const tokensReturned =
+      Number(afterBalance.value.amount) - Number(beforeBalance.value.amount);
+    assert.equal(tokensReturned, STAKE_AMOUNT.toNumber());
+
+    const userStake = await program.account.userStake.fetch(userStakeAccount);
+    assert.equal(userStake.stakedAmount.toString(), "0");
+    assert.equal(userStake.pendingRewards.toString(), "0");
+
+    await program.methods
+      .setEmergencyMode(false)
+      .accounts({
+        authority: authority.publicKey,
+        pool,
+      })
+      .rpc();
+  });
+
+  it("Closes empty stake account and re-stakes from scratch", async () => {
+    const [userCheckpoints] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("checkpoints"), pool.toBuffer(), authority.publicKey.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .closeUserStake()
+      .accounts({
+        user: authority.publicKey,
+        pool,
+        userStake: userStakeAccount,
+        userCheckpoints,
+      })
+      .rpc();
+
+    const closed = await provider.connection.getAccountInfo(userStakeAccount);
+    assert.isNull(closed);
+    assert.isNull(await provider.connection.getAccountInfo(userCheckpoints));
+
+    await program.methods
+      .stake(STAKE_AMOUNT)
+      .accounts({
+        user: authority.publicKey,
+        pool,
+        userStake: userStakeAccount,
+        userTokenAccount,
+        poolVault,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+
+    const userStake = await program.account.userStake.fetch(userStakeAccount);
+    assert.equal(userStake.stakedAmount.toString(), STAKE_AMOUNT.toString());
+    assert.equal(userStake.totalRewardsClaimed.toString(), "0");
+  });
+
+  it("Stakes on behalf of a beneficiary", async () => {
+    const beneficiary = anchor.web3.Keypair.generate().publicKey;
+    const [beneficiaryStake] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("user_stake"), pool.toBuffer(), beneficiary.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .stakeFor(beneficiary, STAKE_AMOUNT)
+      .accounts({
+        funder: authority.publicKey,
+        pool,
+        userStake: beneficiaryStake,
+        stakingMint,
+        funderTokenAccount: userTokenAccount,
+        poolVault,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        stakingTokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+
+    const userStake = await program.account.userStake.fetch(beneficiaryStake);
+    assert.equal(userStake.owner.toString(), beneficiary.toString());
+    assert.equal(userStake.stakedAmount.toString(), STAKE_AMOUNT.toString());
+  });
+
+  it("Transfers a stake position to another wallet", async () => {
+    const newOwner = anchor.web3.Keypair.generate().publicKey;
+    const [destinationStake] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("user_stake"), pool.toBuffer(), newOwner.toBuffer()],
+      program.programId
+    );
+    const before = await program.account.userStake.fetch(userStakeAccount);
+
+    await program.methods
+      .transferStake(newOwner)
+      .accounts({
+        user: authority.publicKey,
+        pool,
+        userStake: userStakeAccount,
+        destinationStake,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    const source = await program.account.userStake.fetch(userStakeAccount);
+    assert.equal(source.stakedAmount.toString(), "0");
+
+    const destination = await program.account.userStake.fetch(destinationStake);
+    assert.equal(destination.owner.toString(), newOwner.toString());
+    assert.equal(destination.stakedAmount.toString(), before.stakedAmount.toString());
+    assert.equal(
+      destination.stakeStartTime.toString(),
+      before.stakeStartTime.toString()
+    );
+  });
+
+  it("Tokenizes a stake position and redeems it", async () => {
+    await program.methods
+      .stake(STAKE_AMOUNT)
+      .accounts({
+        user: authority.publicKey,
+        pool,
+        userStake: userStakeAccount,
+        userTokenAccount,
+        poolVault,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+
+    const positionMint = anchor.web3.Keypair.generate();
+    const [positionStake] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("position"), positionMint.publicKey.toBuffer()],
+      program.programId
+    );
+    const userPositionAccount = getAssociatedTokenAddressSync(
+      positionMint.publicKey,
+      authority.publicKey,
+      false,
+      TOKEN_2022_PROGRAM_ID
+    );
+
+    await program.methods
+      .tokenizeStake()
+      .accountsPartial({
+        user: authority.publicKey,
+        pool,
+        userStake: userStakeAccount,
+        positionMint: positionMint.publicKey,
+        positionStake,
+        userPositionAccount,
+        positionTokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .signers([positionMint])
+      .rpc();
+
+    let position = await program.account.userStake.fetch(positionStake);
+    assert.equal(position.owner.toString(), positionMint.publicKey.toString());
+    assert.equal(position.stakedAmount.toString(), STAKE_AMOUNT.toString());
+
+    const nftBalance = await provider.connection.getTokenAccountBalance(userPositionAccount);
+    assert.equal(nftBalance.value.amount, "1");
+
+    let userStake = await program.account.userStake.fetch(userStakeAccount);
+    assert.equal(userStake.stakedAmount.toString(), "0");
+
+    await program.methods
+      .redeemPosition()
+      .accountsPartial({
+        holder: authority.publicKey,
+        pool,
+        positionStake,
+        positionMint: positionMint.publicKey,
+        holderPositionAccount: userPositionAccount,
+        userStake: userStakeAccount,
+        positionTokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .rpc();
+
+    userStake = await program.account.userStake.fetch(userStakeAccount);
+    assert.equal(userStake.stakedAmount.toString(), STAKE_AMOUNT.toString());
+    assert.isNull(await provider.connection.getAccountInfo(positionStake));
+  });
+
+  it("Claims through a claim delegate", async () => {
+    const delegate = anchor.web3.Keypair.generate();
+
+    await program.methods
+      .setClaimDelegate(delegate.publicKey)
+      .accounts({
+        user: authority.publicKey,
+        pool,
+        userStake: userStakeAccount,
+      })
+      .rpc();
+
+    await new Promise((resolve) => setTimeout(resolve, 2000));
+    const beforeBalance = await provider.connection.getTokenAccountBalance(userRewardAccount);
+
+    await program.methods
+      .claimRewardsFor()
+      .accountsPartial({
+        claimer: delegate.publicKey,
+        pool,
+        userStake: userStakeAccount,
+        rewardDestination: userRewardAccount,
+        rewardVault,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([delegate])
+      .rpc();
+
+    const afterBalance = await provider.connection.getTokenAccountBalance(userRewardAccount);
+    assert.isAbove(
+      Number(afterBalance.value.amount) - Number(beforeBalance.value.amount),
+      0,
+      "Delegate should have claimed to the owner's reward account"
+    );
+  });
+
+  it("Cranks claims to registered reward destinations", async () => {
+    const destinationOwner = anchor.web3.Keypair.generate().publicKey;
+    const rewardDestination = (
+      await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        (authority as any).payer,
+        rewardMint,
+        destinationOwner
+      )
+    ).address;
+
+    await program.methods
+      .setRewardDestination()
+      .accounts({
+        user: authority.publicKey,
+        pool,
+        userStake: userStakeAccount,
+        rewardDestination,
+      })
+      .rpc();
+    await program.methods
+      .setCrankTip(1_000) // 10%
+      .accounts({
+        authority: authority.publicKey,
+        pool,
+      })
+      .rpc();
+
+    // Tokenized positions can't register a destination, so the crank skips them
+    const { positionStake } = await tokenizedPosition(1_000);
+
+    await new Promise((resolve) => setTimeout(resolve, 2000));
+    const crankerBefore = await provider.connection.getTokenAccountBalance(userRewardAccount);
+
+    await program.methods
+      .crankClaim()
+      .accountsPartial({
+        cranker: authority.publicKey,
+        pool,
+        crankerRewardAccount: userRewardAccount,
+        rewardVault,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts([
+        { pubkey: positionStake, isSigner: false, isWritable: true },
+        { pubkey: rewardDestination, isSigner: false, isWritable: true },
+        { pubkey: userStakeAccount, isSigner: false, isWritable: true },
+        { pubkey: rewardDestination, isSigner: false, isWritable: true },
+      ])
+      .rpc();
+
+    const position = await program.account.userStake.fetch(positionStake);
+    assert.equal(position.totalRewardsClaimed.toString(), "0");
+    const crankerAfter = await provider.connection.getTokenAccountBalance(userRewardAccount);
+    const destination = await provider.connection.getTokenAccountBalance(rewardDestination);
+    const tip = Number(crankerAfter.value.amount) - Number(crankerBefore.value.amount);
+    const payout = Number(destination.value.amount);
+    assert.isAbove(payout, 0, "Crank should have paid the registered destination");
+    assert.equal(tip, Math.floor((payout + tip) / 10), "Cranker should keep exactly the tip");
+
+    await program.methods
+      .setCrankTip(0)
+      .accounts({
+        authority: authority.publicKey,
+        pool,
+      })
+      .rpc();
+  });
+
+  it("Returns pool stats and user position via view instructions", async () => {
+    const stats = await program.methods
+      .getPoolStats()
+      .accounts({ pool, poolVault, rewardVault })
+      .view();
+    const poolAccount = await program.account.stakePool.fetch(pool);
+    assert.equal(stats.totalStaked.toString(), poolAccount.totalStaked.toString());
+
+    const position = await program.methods
+      .getUserPosition(authority.publicKey)
+      .accounts({ pool, userStake: userStakeAccount })
+      .view();
+    const userStake = await program.account.userStake.fetch(userStakeAccount);
+    assert.equal(position.stakedAmount.toString(), userStake.stakedAmount.toString());
+
+    const pending = await program.methods
+      .getPendingRewards(authority.publicKey)
+      .accounts({ pool, userStake: userStakeAccount })
+      .view();
+    assert.isTrue(pending.gte(userStake.pendingRewards));
+  });
+
+  it("Validates oracle rate mode settings", async () => {
+    // Not a price update account
+    try {
+      await program.methods
+        .setRateOracle(10_000, new anchor.BN(60), 200, 1_000)
+        .accounts({
+          authority: authority.publicKey,
+          pool,
+          stakingMint,
+          rewardMint,
+          priceAccount: pool,
+        })
+        .rpc();
+      assert.fail("Should have rejected a non-price account");
+    } catch (e: any) {
+      assert.include(e.message, "InvalidOracleAccount");
+    }
+
+    // Zero staleness window
+    try {
+      await program.methods
+        .setRateOracle(10_000, new anchor.BN(0), 200, 1_000)
+        .accounts({
+          authority: authority.publicKey,
+          pool,
+          stakingMint,
+          rewardMint,
+          priceAccount: pool,
+        })
+        .rpc();
+      assert.fail("Should have rejected a zero staleness window");
+    } catch (e: any) {
+      assert.include(e.message, "InvalidOracleConfig");
+    }
+
+    try {
+      await program.methods
+        .refreshRewardRate()
+        .accounts({ pool, priceAccount: pool })
+        .rpc();
+      assert.fail("Should have rejected a fixed-rate pool");
+    } catch (e: any) {
+      assert.include(e.message, "OracleDisabled");
+    }
+  });
+
+  it("Checkpoints staked balances for governance snapshots", async () => {
+    const [userCheckpoints] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("checkpoints"), pool.toBuffer(), authority.publicKey.toBuffer()],
+      program.programId
+    );
+    const history = await program.account.stakeCheckpoints.fetch(userCheckpoints);
+    assert.isAbove(history.checkpoints.length, 0);
+    const latest = history.checkpoints[history.checkpoints.length - 1];
+
+    const userStake = await program.account.userStake.fetch(userStakeAccount);
+    assert.equal(latest.amount.toString(), userStake.stakedAmount.toString());
+
+    // The balance is only visible from the second after it was recorded
+    const after = await program.methods
+      .getStakeAt(authority.publicKey, latest.timestamp.addn(1))
+      .accounts({ pool })
+      .view();
+    assert.equal(after.toString(), userStake.stakedAmount.toString());
+
+    // The page being written answers for the present without a following page
+    const poolAccount = await program.account.stakePool.fetch(pool);
+    const poolTotal = await program.methods
+      .getPoolStakeAt(new anchor.BN(Number.MAX_SAFE_INTEGER), poolAccount.checkpointRing.current)
+      .accounts({ pool, followingCheckpoints: null })
+      .view();
+    assert.equal(poolTotal.toString(), poolAccount.totalStaked.toString());
+  });
+
+  it("Grows the pool total's checkpoint ring", async () => {
+    const before = await program.account.stakePool.fetch(pool);
+    await program.methods
+      .addCheckpointPage()
+      .accounts({ authority: authority.publicKey, pool })
+      .rpc();
+
+    const after = await program.account.stakePool.fetch(pool);
+    assert.equal(after.checkpointRing.pages, before.checkpointRing.pages + 1);
+    assert.equal(after.checkpointRing.current, before.checkpointRing.current);
+    const [page] = anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("pool_checkpoints"),
+        pool.toBuffer(),
+        new anchor.BN(before.checkpointRing.pages).toArrayLike(Buffer, "le", 4),
+      ],
+      program.programId
+    );
+    const added = await program.account.checkpointPage.fetch(page);
+    assert.equal(added.page, before.checkpointRing.pages);
+    assert.lengthOf(added.checkpoints, 0);
+  });
+
+  it("Refuses voter weight records until a realm is configured", async () => {
+    try {
+      await program.methods
+        .updateVoterWeightRecord(authority.publicKey, { castVote: {} }, null)
+        .accounts({
+          payer: authority.publicKey,
+          pool,
+          realm: anchor.web3.PublicKey.default,
+          proposal: null,
+        })
+        .rpc();
+      assert.fail("Should have rejected a pool without a realm");
+    } catch (e: any) {
+      assert.include(e.message, "VoterWeightDisabled");
+    }
+  });
+
+  it("Claims a Merkle distributor leaf exactly once", async () => {
+    const amount = new anchor.BN(1_000_000);
+    // A single-leaf tree's root is the leaf: sha256(0x00 | index | claimant | amount)
+    const index = Buffer.alloc(4);
+    const leaf = createHash("sha256")
+      .update(Buffer.from([0]))
+      .update(index)
+      .update(authority.publicKey.toBuffer())
+      .update(amount.toArrayLike(Buffer, "le", 8))
+      .digest();
+
+    const poolBefore = await program.account.stakePool.fetch(pool);
+    const epoch = poolBefore.distributor.epochs;
+    const [distributorEpoch] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("distributor"), pool.toBuffer(), epoch.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    await program.methods
+      .postDistributorEpoch(Array.from(leaf), amount, 1)
+      .accounts({
+        authority: authority.publicKey,
+        pool,
+        distributorEpoch,
+        rewardVault,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    const posted = await program.account.stakePool.fetch(pool);
+    assert.equal(
+      posted.distributor.reserved.toString(),
+      poolBefore.distributor.reserved.add(amount).toString()
+    );
+
+    const claim = () =>
+      program.methods
+        .claimDistributor(0, amount, [])
+        .accounts({
+          claimant: authority.publicKey,
+          pool,
+          distributorEpoch,
+          claimantRewardAccount: userRewardAccount,
+          rewardVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+    const before = await provider.connection.getTokenAccountBalance(userRewardAccount);
+    await claim();
+    const after = await provider.connection.getTokenAccountBalance(userRewardAccount);
+    assert.equal(
+      Number(after.value.amount) - Number(before.value.amount),
+      amount.toNumber()
+    );
+
+    try {
+      await claim();
+      assert.fail("Should have rejected a second claim");
+    } catch (e: any) {
+      assert.include(e.message, "AlreadyClaimed");
+    }
+
+    const claimed = await program.account.stakePool.fetch(pool);
+    assert.equal(
+      claimed.distributor.reserved.toString(),
+      poolBefore.distributor.reserved.toString()
+    );
+  });
+
+  it("Rejects self-referrals and unstaked referrers", async () => {
+    const register = (referrer: anchor.web3.PublicKey) =>
+      program.methods
+        .registerReferrer(referrer)
+        .accountsPartial({
+          user: authority.publicKey,
+          pool,
+          userStake: userStakeAccount,
+          referrerStake: anchor.web3.PublicKey.findProgramAddressSync(
+            [Buffer.from("user_stake"), pool.toBuffer(), referrer.toBuffer()],
+            program.programId
+          )[0],
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+
+    try {
+      await register(authority.publicKey);
+      assert.fail("Should have rejected a self-referral");
+    } catch (e: any) {
+      assert.include(e.message, "InvalidReferrer");
+    }
+
+    // A fresh wallet has no position, so it can't refer anyone
+    try {
+      await register(anchor.web3.Keypair.generate().publicKey);
+      assert.fail("Should have rejected a referrer without a stake");
+    } catch (e: any) {
+      assert.include(e.message, "AccountNotInitialized");
+    }
+  });
+
+  it("Pays referrers a share of their referees' rewards", async () => {
+    await program.methods
+      .setReferralBps(1_000) // 10%
+      .accounts({ authority: authority.publicKey, pool })
+      .rpc();
+
+    const referee = await fundedWallet(STAKE_AMOUNT.toNumber());
+    const [referrerStats] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("referrer"), pool.toBuffer(), authority.publicKey.toBuffer()],
+      program.programId
+    );
+    const register = () =>
+      program.methods
+        .registerReferrer(authority.publicKey)
+        .accountsPartial({
+          user: referee.wallet.publicKey,
+          pool,
+          userStake: referee.stake,
+          referrerStats,
+          referrerStake: userStakeAccount,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([referee.wallet])
+        .rpc();
+    await register();
+    await program.methods
+      .stake(STAKE_AMOUNT)
+      .accounts({
+        user: referee.wallet.publicKey,
+        pool,
+        userStake: referee.stake,
+        userTokenAccount: referee.tokenAccount,
+        poolVault,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([referee.wallet])
+      .rpc();
+
+    try {
+      await register();
+      assert.fail("Should have rejected a referrer for an existing stake");
+    } catch (e: any) {
+      assert.include(e.message, "ReferrerLocked");
+    }
+
+    await new Promise((resolve) => setTimeout(resolve, 2000));
+    const statsBefore = await program.account.referrerStats.fetch(referrerStats);
+    const before = await provider.connection.getTokenAccountBalance(userRewardAccount);
+
+    await program.methods
+      .claimReferralRewards()
+      .accountsPartial({
+        pool,
+        referrerStats,
+        referrerRewardAccount: userRewardAccount,
+        rewardVault,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts([{ pubkey: referee.stake, isSigner: false, isWritable: true }])
+      .rpc();
+
+    const after = await provider.connection.getTokenAccountBalance(userRewardAccount);
+    const paid = Number(after.value.amount) - Number(before.value.amount);
+    assert.isAbove(paid, 0, "Referrer should have been paid");
+    const stats = await program.account.referrerStats.fetch(referrerStats);
+    assert.equal(stats.referralCount, statsBefore.referralCount);
+    assert.equal(
+      stats.totalRewardsPaid.sub(statsBefore.totalRewardsPaid).toNumber(),
+      paid
+    );
+    const refereeStake = await program.account.userStake.fetch(referee.stake);
+    assert.equal(refereeStake.referralPending.toString(), "0");
+
+    await program.methods
+      .setReferralBps(0)
+      .accounts({ authority: authority.publicKey, pool })
+      .rpc();
+  });
+
+  it("Caps protocol fees (admin)", async () => {
+    try {
+      await program.methods
+        .setFees(201, 0, 0)
+        .accounts({
+          authority: authority.publicKey,
+          pool,
+          stakingTreasury: userTokenAccount,
+          rewardTreasury: userRewardAccount,
+        })
+        .rpc();
+      assert.fail("Should have rejected a deposit fee above the cap");
+    } catch (e: any) {
+      assert.include(e.message, "FeeTooHigh");
+    }
+
+    const poolAccount = await program.account.stakePool.fetch(pool);
+    assert.equal(poolAccount.fees.depositBps, 0);
+  });
+
+  it("Pays protocol fees to the treasuries", async () => {
+    const balance = async (account: anchor.web3.PublicKey) =>
+      Number((await provider.connection.getTokenAccountBalance(account)).value.amount);
+    const treasuryOwner = anchor.web3.Keypair.generate().publicKey;
+    const stakingTreasury = (
+      await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        (authority as any).payer,
+        stakingMint,
+        treasuryOwner
+      )
+    ).address;
+    const rewardTreasury = (
+      await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        (authority as any).payer,
+        rewardMint,
+        treasuryOwner
+      )
+    ).address;
+    const setFees = (deposit: number, withdrawal: number, performance: number) =>
+      program.methods
+        .setFees(deposit, withdrawal, performance)
+        .accounts({ authority: authority.publicKey, pool, stakingTreasury, rewardTreasury })
+        .rpc();
+    await setFees(100, 100, 1_000); // 1%, 1%, 10%
+
+    const stake = (treasury: anchor.web3.PublicKey | null) =>
+      program.methods
+        .stake(STAKE_AMOUNT)
+        .accountsPartial({
+          user: authority.publicKey,
+          pool,
+          userStake: userStakeAccount,
+          stakingMint,
+          userTokenAccount,
+          poolVault,
+          stakingTreasury: treasury,
+          stakingTokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+    try {
+      await stake(null);
+      assert.fail("Should have required the staking treasury");
+    } catch (e: any) {
+      assert.include(e.message, "MissingFeeTreasury");
+    }
+
+    const stakedBefore = (await program.account.userStake.fetch(userStakeAccount)).stakedAmount;
+    await stake(stakingTreasury);
+    const depositFee = STAKE_AMOUNT.toNumber() / 100;
+    assert.equal(await balance(stakingTreasury), depositFee);
+    const staked = (await program.account.userStake.fetch(userStakeAccount)).stakedAmount;
+    assert.equal(staked.sub(stakedBefore).toNumber(), STAKE_AMOUNT.toNumber() - depositFee);
+
+    // The claimer gets 90% of each claim and the reward treasury the rest
+    await new Promise((resolve) => setTimeout(resolve, 2000));
+    const claimedBefore = await balance(userRewardAccount);
+    await program.methods
+      .claimRewards()
+      .accountsPartial({
+        user: authority.publicKey,
+        pool,
+        userStake: userStakeAccount,
+        userRewardAccount,
+        rewardVault,
+        rewardTreasury,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+    const claimed = (await balance(userRewardAccount)) - claimedBefore;
+    const claimFee = await balance(rewardTreasury);
+    assert.isAbove(claimFee, 0, "Claim should have paid a performance fee");
+    assert.equal(claimFee, Math.floor((claimed + claimFee) / 10));
+
+    await new Promise((resolve) => setTimeout(resolve, 2000));
+    const destination = (await program.account.userStake.fetch(userStakeAccount))
+      .rewardDestination;
+    const paidBefore = await balance(destination);
+    await program.methods
+      .crankClaim()
+      .accountsPartial({
+        cranker: authority.publicKey,
+        pool,
+        crankerRewardAccount: userRewardAccount,
+        rewardVault,
+        rewardTreasury,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts([
+        { pubkey: userStakeAccount, isSigner: false, isWritable: true },
+        { pubkey: destination, isSigner: false, isWritable: true },
+      ])
+      .rpc();
+    const paid = (await balance(destination)) - paidBefore;
+    const crankFee = (await balance(rewardTreasury)) - claimFee;
+    assert.isAbove(crankFee, 0, "Crank should have paid a performance fee");
+    assert.equal(crankFee, Math.floor((paid + crankFee) / 10));
+
+    const unstakeAmount = STAKE_AMOUNT.toNumber() / 2;
+    const tokensBefore = await balance(userTokenAccount);
+    await program.methods
+      .unstake(new anchor.BN(unstakeAmount))
+      .accountsPartial({
+        user: authority.publicKey,
+        pool,
+        userStake: userStakeAccount,
+        stakingMint,
+        userTokenAccount,
+        poolVault,
+        stakingTreasury,
+        stakingTokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+    const withdrawalFee = unstakeAmount / 100;
+    assert.equal((await balance(userTokenAccount)) - tokensBefore, unstakeAmount - withdrawalFee);
+    assert.equal(await balance(stakingTreasury), depositFee + withdrawalFee);
+
+    await setFees(0, 0, 0);
+  });
+
+  it("Rejects NFT boost multipliers below 1x (admin)", async () => {
+    try {
+      await program.methods
+        .setNftBoost(anchor.web3.Keypair.generate().publicKey, 9_999)
+        .accounts({ authority: authority.publicKey, pool })
+        .rpc();
+      assert.fail("Should have rejected a multiplier below 1x");
+    } catch (e: any) {
+      assert.include(e.message, "InvalidBoostMultiplier");
+    }
+
+    const poolAccount = await program.account.stakePool.fetch(pool);
+    assert.isTrue(poolAccount.nftBoost.collection.equals(anchor.web3.PublicKey.default));
+  });
+
+  it("Escrows a collection NFT to boost a stake and returns it", async () => {
+    const collection = await createNft(null);
+    const nft = await createNft(collection.mint);
+    await program.methods
+      .setNftBoost(collection.mint, 20_000) // 2x
+      .accounts({ authority: authority.publicKey, pool })
+      .rpc();
+
+    const [boostEscrow] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("boost_escrow"), userStakeAccount.toBuffer()],
+      program.programId
+    );
+    const attach = (boost: { mint: anchor.web3.PublicKey; tokenAccount: anchor.web3.PublicKey }) =>
+      program.methods
+        .attachBoostNft()
+        .accountsPartial({
+          user: authority.publicKey,
+          pool,
+          userStake: userStakeAccount,
+          nftMint: boost.mint,
+          nftMetadata: metadataAddress(boost.mint),
+          userNftAccount: boost.tokenAccount,
+          boostEscrow,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          nftTokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+    // The collection's own NFT isn't a verified member of it
+    try {
+      await attach(collection);
+      assert.fail("Should have rejected an NFT outside the collection");
+    } catch (e: any) {
+      assert.include(e.message, "InvalidBoostNft");
+    }
+
+    await attach(nft);
+    const attached = await program.account.userStake.fetch(userStakeAccount);
+    assert.isTrue(attached.boostMint.equals(nft.mint));
+    const escrowed = await provider.connection.getTokenAccountBalance(boostEscrow);
+    assert.equal(escrowed.value.amount, "1");
+
+    await new Promise((resolve) => setTimeout(resolve, 2000));
+    await program.methods
+      .detachBoostNft()
+      .accountsPartial({
+        user: authority.publicKey,
+        pool,
+        userStake: userStakeAccount,
+        nftMint: nft.mint,
+        userNftAccount: nft.tokenAccount,
+        boostEscrow,
+        nftTokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+
+    const detached = await program.account.userStake.fetch(userStakeAccount);
+    assert.isTrue(detached.boostMint.equals(anchor.web3.PublicKey.default));
+    assert.isTrue(
+      detached.pendingRewards.gt(attached.pendingRewards),
+      "Boosted rewards should have been settled on detach"
+    );
+    const returned = await provider.connection.getTokenAccountBalance(nft.tokenAccount);
+    assert.equal(returned.value.amount, "1");
+    assert.isNull(await provider.connection.getAccountInfo(boostEscrow));
+
+    await program.methods
+      .setNftBoost(anchor.web3.PublicKey.default, 0)
+      .accounts({ authority: authority.publicKey, pool })
+      .rpc();
+  });
+
+  it("Replaces the staking tiers (admin)", async () => {
+    const tier = (minStake: number, multiplierBps: number) => ({
+      minStake: new anchor.BN(minStake),
+      multiplierBps,
+    });
+    try {
+      await program.methods
+        .setTiers([tier(10, 15_000), tier(1, 10_000)])
+        .accounts({ authority: authority.publicKey, pool })
+        .rpc();
+      assert.fail("Should have rejected descending tiers");
+    } catch (e: any) {
+      assert.include(e.message, "InvalidTiers");
+    }
+
+    await program.methods
+      .setTiers([tier(1, 10_000), tier(10, 15_000), tier(100, 20_000)])
+      .accounts({ authority: authority.publicKey, pool })
+      .rpc();
+
+    const poolAccount = await program.account.stakePool.fetch(pool);
+    assert.equal(poolAccount.tiers.count, 3);
+    assert.equal(poolAccount.tiers.tiers[2].multiplierBps, 20_000);
+  });
+
+  it("Assigns tiers by stake size and reprices them only on refresh", async () => {
+    const tier = (minStake: number, multiplierBps: number) => ({
+      minStake: new anchor.BN(minStake),
+      multiplierBps,
+    });
+    const refresh = (userStake: anchor.web3.PublicKey) =>
+      program.methods.refreshTier().accounts({ pool, userStake }).rpc();
+
+    // A fresh staker lands in the tier of their stake
+    const small = await fundedWallet(50);
+    await program.methods
+      .stake(new anchor.BN(50))
+      .accounts({
+        user: small.wallet.publicKey,
+        pool,
+        userStake: small.stake,
+        userTokenAccount: small.tokenAccount,
+        poolVault,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([small.wallet])
+      .rpc();
+    let position = await program.account.userStake.fetch(small.stake);
+    assert.equal(position.tier, 2);
+    assert.equal(position.tierMultiplierBps, 15_000);
+
+    // Existing positions move onto the tiers set since their last stake when refreshed
+    await refresh(userStakeAccount);
+    position = await program.account.userStake.fetch(userStakeAccount);
+    assert.equal(position.tier, 3);
+    assert.equal(position.tierMultiplierBps, 20_000);
+
+    // New multipliers don't apply until the position is refreshed, settling at the old one
+    await program.methods
+      .setTiers([tier(1, 10_000), tier(10, 15_000), tier(100, 30_000)])
+      .accounts({ authority: authority.publicKey, pool })
+      .rpc();
+    position = await program.account.userStake.fetch(userStakeAccount);
+    assert.equal(position.tierMultiplierBps, 20_000);
+
+    await new Promise((resolve) => setTimeout(resolve, 2000));
+    await refresh(userStakeAccount);
+    const refreshed = await program.account.userStake.fetch(userStakeAccount);
+    assert.equal(refreshed.tier, 3);
+    assert.equal(refreshed.tierMultiplierBps, 30_000);
+    assert.isTrue(refreshed.pendingRewards.gt(position.pendingRewards));
+  });
+
+  it("Rejects campaigns that start in the past", async () => {
+    const now = Math.floor(Date.now() / 1000);
+    try {
+      await program.methods
+        .createCampaign(new anchor.BN(1_000), new anchor.BN(now - 60), new anchor.BN(now + 3_600))
+        .accounts({
+          funder: authority.publicKey,
+          pool,
+          rewardMint,
+          funderTokenAccount: userRewardAccount,
+          rewardTokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+      assert.fail("Should have rejected a campaign that already started");
+    } catch (e: any) {
+      assert.include(e.message, "InvalidCampaignWindow");
+    }
+
+    const poolAccount = await program.account.stakePool.fetch(pool);
+    assert.equal(poolAccount.campaigns.created.toNumber(), 0);
+  });
+
+  it("Pays a campaign to stakers and position holders", async () => {
+    const [campaign] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("campaign"), pool.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const [campaignVault] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("campaign_vault"), campaign.toBuffer()],
+      program.programId
+    );
+    const create = (amount: number, start: number) =>
+      program.methods
+        .createCampaign(new anchor.BN(amount), new anchor.BN(start), new anchor.BN(start + 4))
+        .accountsPartial({
+          funder: authority.publicKey,
+          pool,
+          campaign,
+          rewardMint,
+          campaignVault,
+          funderTokenAccount: userRewardAccount,
+          rewardTokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+    // The holder's whole stake moves into a position NFT before the campaign starts
+    const { holder, positionStake, holderPositionAccount } = await tokenizedPosition(10_000_000);
+    const holderRewardAccount = (
+      await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        (authority as any).payer,
+        rewardMint,
+        holder.publicKey
+      )
+    ).address;
+
+    // Campaign slots are scarce, so anyone can fund one but it must pay at least a whole token
+    const start = Math.floor(Date.now() / 1000) + 2;
+    try {
+      await create(999_999, start);
+      assert.fail("Should have rejected a dust campaign");
+    } catch (e: any) {
+      assert.include(e.message, "CampaignTooSmall");
+    }
+    await create(1_000_000, start);
+    let vault = await provider.connection.getTokenAccountBalance(campaignVault);
+    assert.equal(vault.value.amount, "1000000");
+
+    await new Promise((resolve) => setTimeout(resolve, 8000));
+
+    const balance = async (account: anchor.web3.PublicKey) =>
+      new anchor.BN((await provider.connection.getTokenAccountBalance(account)).value.amount);
+    const campaignAccounts = {
+      pool,
+      campaign,
+      campaignVault,
+      rewardMint,
+      rewardTokenProgram: TOKEN_PROGRAM_ID,
+    };
+
+    await program.methods
+      .claimPositionCampaignRewards()
+      .accountsPartial({
+        ...campaignAccounts,
+        holder: holder.publicKey,
+        positionStake,
+        holderPositionAccount,
+        holderRewardAccount,
+      })
+      .signers([holder])
+      .rpc();
+    const holderShare = await balance(holderRewardAccount);
+    assert.isTrue(holderShare.gtn(0));
+
+    const before = await balance(userRewardAccount);
+    await program.methods
+      .claimCampaignRewards()
+      .accountsPartial({
+        ...campaignAccounts,
+        user: authority.publicKey,
+        userStake: userStakeAccount,
+        userRewardAccount,
+      })
+      .rpc();
+    const stakerShare = (await balance(userRewardAccount)).sub(before);
+    assert.isTrue(stakerShare.gtn(0));
+
+    const campaignAccount = await program.account.campaign.fetch(campaign);
+    assert.equal(campaignAccount.claimed.toString(), holderShare.add(stakerShare).toString());
+    vault = await provider.connection.getTokenAccountBalance(campaignVault);
+    assert.equal(
+      vault.value.amount,
+      new anchor.BN(1_000_000).sub(holderShare).sub(stakerShare).toString()
+    );
+
+    try {
+      await program.methods
+        .claimCampaignRewards()
+        .accountsPartial({
+          ...campaignAccounts,
+          user: authority.publicKey,
+          userStake: userStakeAccount,
+          userRewardAccount,
+        })
+        .rpc();
+      assert.fail("Should have had nothing left to claim");
+    } catch (e: any) {
+      assert.include(e.message, "NoRewards");
+    }
+  });
+
+  it("Mints liquid receipts one-to-one for the first depositor and redeems them", async () => {
+    // Liquid staking compounds rewards into stake, so it needs a same-mint pool
+    const liquidMint = await createMint(
+      provider.connection,
+      (authority as any).payer,
+      authority.publicKey,
+      null,
+      6
+    );
+    const [liquidPool] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("pool"), liquidMint.toBuffer()],
+      program.programId
+    );
+    const [receiptMint] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("receipt_mint"), liquidPool.toBuffer()],
+      program.programId
+    );
+    const [liquidStake] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("liquid_stake"), liquidPool.toBuffer()],
+      program.programId
+    );
+    const liquidTokenAccount = (
+      await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        (authority as any).payer,
+        liquidMint,
+        authority.publicKey
+      )
+    ).address;
+    await mintTo(
+      provider.connection,
+      (authority as any).payer,
+      liquidMint,
+      liquidTokenAccount,
+      authority.publicKey,
+      1_000_000
+    );
+
+    await program.methods
+      .initializePool([REWARD_RATE], LOCK_PERIOD, new anchor.BN(1))
+      .accounts({
+        authority: authority.publicKey,
+        stakingMint: liquidMint,
+        rewardMint: liquidMint,
+        stakingTokenProgram: TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+    await program.methods
+      .initializeLiquidStaking()
+      .accountsPartial({
+        authority: authority.publicKey,
+        pool: liquidPool,
+        stakingMint: liquidMint,
+        receiptMint,
+        liquidStake,
+        receiptTokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+
+    const receiptAccount = (
+      await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        (authority as any).payer,
+        receiptMint,
+        authority.publicKey
+      )
+    ).address;
+    const liquidAccounts = {
+      user: authority.publicKey,
+      pool: liquidPool,
+      liquidStake,
+      receiptMint,
+      userReceiptAccount: receiptAccount,
+      stakingMint: liquidMint,
+      userTokenAccount: liquidTokenAccount,
+      stakingTokenProgram: TOKEN_PROGRAM_ID,
+      receiptTokenProgram: TOKEN_PROGRAM_ID,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    };
+
+    await program.methods
+      .liquidStake(new anchor.BN(250_000))
+      .accountsPartial(liquidAccounts)
+      .rpc();
+
+    let receipts = await provider.connection.getTokenAccountBalance(receiptAccount);
+    assert.equal(receipts.value.amount, "250000");
+    let position = await program.account.userStake.fetch(liquidStake);
+    assert.equal(position.stakedAmount.toString(), "250000");
+
+    // Paused unstakes only reopen for receipt holders once the pool is in emergency mode
+    const PAUSE_UNSTAKE = 1 << 1;
+    const admin = { authority: authority.publicKey, pool: liquidPool };
+    await program.methods.setPauseFlags(PAUSE_UNSTAKE).accounts(admin).rpc();
+    try {
+      await program.methods
+        .liquidUnstake(new anchor.BN(250_000))
+        .accountsPartial(liquidAccounts)
+        .rpc();
+      assert.fail("Should have been paused");
+    } catch (e: any) {
+      assert.include(e.message, "PoolPaused");
+    }
+    await program.methods.setEmergencyMode(true).accounts(admin).rpc();
+
+    // Nothing funds the reward vault, so the rate stays 1:1 and everything comes back
+    await program.methods
+      .liquidUnstake(new anchor.BN(250_000))
+      .accountsPartial(liquidAccounts)
+      .rpc();
+
+    receipts = await provider.connection.getTokenAccountBalance(receiptAccount);
+    assert.equal(receipts.value.amount, "0");
+    const tokens = await provider.connection.getTokenAccountBalance(liquidTokenAccount);
+    assert.equal(tokens.value.amount, "1000000");
+    position = await program.account.userStake.fetch(liquidStake);
+    assert.equal(position.stakedAmount.toString(), "0");
+
+    // Liquid stakes and unstakes pay the pool's deposit and withdrawal fees
+    await program.methods.setEmergencyMode(false).accounts(admin).rpc();
+    await program.methods.setPauseFlags(0).accounts(admin).rpc();
+    const treasury = (
+      await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        (authority as any).payer,
+        liquidMint,
+        anchor.web3.Keypair.generate().publicKey
+      )
+    ).address;
+    await program.methods
+      .setFees(100, 100, 0)
+      .accounts({ ...admin, stakingTreasury: treasury, rewardTreasury: treasury })
+      .rpc();
+    const feeAccounts = { ...liquidAccounts, stakingTreasury: treasury };
+    try {
+      await program.methods
+        .liquidStake(new anchor.BN(100_000))
+        .accountsPartial(liquidAccounts)
+        .rpc();
+      assert.fail("Should have required the staking treasury");
+    } catch (e: any) {
+      assert.include(e.message, "MissingFeeTreasury");
+    }
+    await program.methods
+      .liquidStake(new anchor.BN(100_000))
+      .accountsPartial(feeAccounts)
+      .rpc();
+    receipts = await provider.connection.getTokenAccountBalance(receiptAccount);
+    assert.equal(receipts.value.amount, "99000");
+    await program.methods
+      .liquidUnstake(new anchor.BN(99_000))
+      .accountsPartial(feeAccounts)
+      .rpc();
+    const fees = await provider.connection.getTokenAccountBalance(treasury);
+    assert.equal(fees.value.amount, "1990");
+
+    // The liquid position earns at 1x whatever its size, so it can't be moved into a tier
+    try {
+      await program.methods
+        .refreshTier()
+        .accounts({ pool: liquidPool, userStake: liquidStake })
+        .rpc();
+      assert.fail("Should have rejected the liquid position");
+    } catch (e: any) {
+      assert.include(e.message, "InvalidPosition");
+    }
+  });
+});